@@ -26,6 +26,24 @@ pub struct Account {
     pub booster_staking: Option<BoosterStaking>,
     pub booster_stakings: HashMap<TokenId, BoosterStaking>,
     pub is_locked: bool,
+    /// Claimed farm rewards that are vesting linearly.
+    pub vestings: HashMap<TokenId, RewardVesting>,
+    /// How claimed farm rewards are compounded.
+    pub auto_compound: Option<AutoCompound>,
+    /// Delegations of xBooster to other accounts.
+    pub booster_delegations: HashMap<TokenId, HashMap<AccountId, BoosterDelegation>>,
+    /// Accounts that delegate xBooster to this account.
+    pub booster_delegators: HashMap<TokenId, HashSet<AccountId>>,
+    /// The amount of xBooster received from delegators. It's computed on every farm update.
+    #[borsh_skip]
+    #[serde(skip)]
+    pub received_x_booster_amounts: HashMap<TokenId, Balance>,
+    /// The latest gauge vote of the account.
+    #[serde(skip_serializing)]
+    pub gauge_vote: Option<GaugeVote>,
+    /// The shared early unstake penalty per staked booster token at the last settlement.
+    #[serde(skip_serializing)]
+    pub last_booster_penalty_per_stakes: HashMap<TokenId, BigDecimal>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
@@ -34,6 +52,7 @@ pub enum VAccount {
     V1(AccountV1),
     V2(AccountV2),
     V3(AccountV3),
+    V4(AccountV4),
     Current(Account),
 }
 
@@ -44,6 +63,7 @@ impl VAccount {
             VAccount::V1(c) => c.into_account(is_view),
             VAccount::V2(c) => c.into_account(),
             VAccount::V3(c) => c.into_account(),
+            VAccount::V4(c) => c.into_account(),
             VAccount::Current(c) => c,
         }
     }
@@ -67,6 +87,13 @@ impl Account {
             booster_staking: None,
             booster_stakings: HashMap::new(),
             is_locked: false,
+            vestings: HashMap::new(),
+            auto_compound: None,
+            booster_delegations: HashMap::new(),
+            booster_delegators: HashMap::new(),
+            received_x_booster_amounts: HashMap::new(),
+            gauge_vote: None,
+            last_booster_penalty_per_stakes: HashMap::new(),
         }
     }
 
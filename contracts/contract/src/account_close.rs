@@ -0,0 +1,187 @@
+use crate::*;
+
+impl Account {
+    /// Returns true if the account holds no balances, positions, farms or booster state.
+    pub fn is_empty(&self) -> bool {
+        !self.is_locked
+            && self.supplied.is_empty()
+            && self.positions.is_empty()
+            && self.farms.is_empty()
+            && self.booster_staking.is_none()
+            && self.booster_stakings.is_empty()
+            && self.vestings.is_empty()
+            && self.booster_delegations.is_empty()
+            && self.booster_delegators.is_empty()
+    }
+}
+
+impl MarginAccount {
+    /// Returns true if the margin account holds no balances, positions, pending actions or farms.
+    pub fn is_empty(&self) -> bool {
+        self.supplied.is_empty()
+            && self.margin_positions.is_empty()
+            && self.position_latest_actions.is_empty()
+            && self.farms.is_empty()
+            && self.unclaimed_farm_rewards.is_empty()
+    }
+}
+
+impl Contract {
+    /// Moves supplied balances that are less than one unit of the token,
+    /// and thus can't be withdrawn, into the reserve.
+    /// Panics if any balance can still be withdrawn.
+    fn internal_sweep_dust(&mut self, account_id: &AccountId, supplied: &mut HashMap<TokenId, Shares>) {
+        for (token_id, shares) in supplied.drain() {
+            let mut asset = self.internal_unwrap_asset(&token_id);
+            let amount = asset.supplied.shares_to_amount(shares, false);
+            require!(
+                amount < 10u128.pow(asset.config.extra_decimals as u32),
+                format!("still has supplied {}", token_id)
+            );
+            asset.supplied.withdraw(shares, amount);
+            asset.reserved += amount;
+            let extra_decimals = asset.config.extra_decimals;
+            self.internal_set_asset(&token_id, asset);
+            if amount > 0 {
+                events::emit::sweep_dust(account_id, amount, &token_id, extra_decimals);
+            }
+        }
+    }
+
+    /// Unstakes all unlocked booster stakings of the account into its supplied balance.
+    fn internal_unstake_unlocked_boosters(&mut self, account: &mut Account) {
+        require!(account.booster_staking.is_none(), "still has booster_staking");
+        let timestamp = env::block_timestamp();
+        let booster_token_ids: Vec<TokenId> = account.booster_stakings.keys().cloned().collect();
+        for booster_token_id in booster_token_ids {
+            self.internal_settle_booster_penalty_rewards(account, &booster_token_id);
+            let booster_staking = account.booster_stakings.remove(&booster_token_id).unwrap();
+            if let Some(mut booster_token_info) = self.internal_get_booster_token_info(&booster_token_id) {
+                if booster_token_info.enable {
+                    require!(booster_staking.unlock_timestamp <= timestamp, "still has locked booster stakings");
+                }
+                booster_token_info.total_stake_amount -= booster_staking.staked_booster_amount;
+                self.internal_set_booster_token_info(&booster_token_id, booster_token_info);
+            }
+            self.internal_deposit(account, &booster_token_id, booster_staking.staked_booster_amount);
+            events::emit::booster_unstake(&account.account_id, &booster_token_id, &booster_staking);
+        }
+    }
+
+    /// Deletes the account, the margin account and the storage, and refunds the storage balance.
+    fn internal_remove_account(&mut self, account_id: &AccountId, mut account: Account) {
+        if let Some(mut margin_account) = self.internal_get_margin_account(account_id) {
+            margin_account.margin_positions.clear();
+            margin_account.storage_tracker.clean();
+            self.margin_accounts.remove(account_id);
+        }
+        // The storage of the account is released entirely below.
+        account.storage_tracker.clean();
+        self.accounts.remove(account_id);
+        self.internal_remove_storage(account_id);
+        events::emit::account_closed(account_id);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Fully exits the contract: unstakes unlocked booster tokens, claims pending farm rewards,
+    /// moves dust balances into the reserve, deletes all the account state
+    /// and refunds the storage balance.
+    /// - Requires one yoctoNEAR.
+    /// - Requires no positions, margin positions, pending margin actions, locked booster stakings,
+    ///   booster delegations or vesting rewards.
+    /// - Requires all supplied balances except dust to be withdrawn.
+    #[payable]
+    pub fn close_account(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        assert!(!account.is_locked, "Account is locked!");
+        assert!(account.positions.is_empty(), "still has positions");
+        assert!(
+            account.booster_delegations.is_empty() && account.booster_delegators.is_empty(),
+            "still has booster delegations"
+        );
+
+        // Claims margin farm rewards into the regular account.
+        if let Some(mut margin_account) = self.internal_get_margin_account(&account_id) {
+            assert!(margin_account.margin_positions.is_empty(), "still has margin positions");
+            assert!(margin_account.position_latest_actions.is_empty(), "still has pending margin actions");
+            let potential_farms = margin_account.get_all_potential_farms();
+            margin_account.affected_farms.extend(potential_farms);
+            margin_account.affected_farms.extend(margin_account.farms.keys().cloned());
+            self.internal_margin_account_apply_affected_farms(&mut margin_account);
+            let reward_vesting_configs = read_reward_vesting_configs_from_storage();
+            for (token_id, amount) in std::mem::take(&mut margin_account.unclaimed_farm_rewards) {
+                self.internal_deposit_farm_reward(&mut account, &reward_vesting_configs, &token_id, amount);
+            }
+            self.internal_sweep_dust(&account_id, &mut margin_account.supplied);
+            assert!(margin_account.is_empty(), "still has margin farms");
+            self.internal_set_margin_account(&account_id, margin_account);
+        }
+
+        self.internal_unstake_unlocked_boosters(&mut account);
+        self.internal_remove_gauge_vote(&mut account);
+        account.affected_farms.extend(account.get_all_potential_farms());
+        account.affected_farms.extend(account.farms.keys().cloned());
+        self.internal_account_apply_affected_farms(&mut account);
+
+        // Fully vested rewards are claimed, the rest has to be claimed or exited first.
+        for (token_id, mut reward_vesting) in std::mem::take(&mut account.vestings) {
+            reward_vesting.update();
+            require!(reward_vesting.locked_amount == 0, "still has vesting rewards");
+            if reward_vesting.unlocked_amount > 0 {
+                self.internal_deposit(&mut account, &token_id, reward_vesting.unlocked_amount);
+                events::emit::vested_reward_claimed(
+                    &account_id,
+                    reward_vesting.unlocked_amount,
+                    &token_id,
+                    self.internal_unwrap_asset(&token_id).config.extra_decimals,
+                );
+            }
+        }
+        assert!(account.positions.is_empty(), "still has positions");
+
+        account.affected_farms.extend(account.supplied.keys().cloned().map(FarmId::Supplied));
+        account.affected_farms.extend(account.supplied.keys().cloned().map(FarmId::TokenNetBalance));
+        let mut supplied = std::mem::take(&mut account.supplied);
+        self.internal_sweep_dust(&account_id, &mut supplied);
+        self.internal_account_apply_affected_farms(&mut account);
+        assert!(account.is_empty(), "still has farms");
+
+        self.internal_remove_account(&account_id, account);
+    }
+
+    /// Removes abandoned accounts within the given page of accounts and refunds their storage.
+    /// An account is abandoned if it and its margin account hold nothing at all.
+    /// Removing an account moves the last account into its index, so pages shrink after cleanup.
+    /// Returns the number of removed accounts.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn cleanup_abandoned_accounts(&mut self, from_index: Option<u64>, limit: Option<u64>) -> u32 {
+        assert_one_yocto();
+        self.assert_owner();
+        let keys = self.accounts.keys_as_vector();
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(keys.len());
+        let account_ids: Vec<AccountId> = (from_index..std::cmp::min(keys.len(), from_index + limit))
+            .map(|index| keys.get(index).unwrap())
+            .collect();
+        let mut num_removed = 0;
+        for account_id in account_ids {
+            let is_abandoned = self.internal_get_account(&account_id, true).unwrap().is_empty()
+                && self
+                    .internal_get_margin_account(&account_id)
+                    .map_or(true, |margin_account| margin_account.is_empty());
+            if is_abandoned {
+                // Not a view, so collections of legacy accounts are cleared as well.
+                let account = self.internal_unwrap_account(&account_id);
+                self.internal_remove_account(&account_id, account);
+                num_removed += 1;
+            }
+        }
+        num_removed
+    }
+}
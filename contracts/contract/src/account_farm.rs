@@ -21,6 +21,10 @@ pub enum FarmId {
     Borrowed(TokenId),
     NetTvl,
     TokenNetBalance(TokenId),
+    /// Farms on margin debt shares of the token, taken from margin trading positions.
+    MarginDebt(TokenId),
+    /// Farms on the position amount of the token, taken from margin trading positions.
+    MarginPosition(TokenId),
 }
 
 /// A data required to keep track of a farm for an account.
@@ -152,13 +156,23 @@ impl Contract {
         AccountFarm,
         Vec<(TokenId, Balance)>,
         Vec<(TokenId, Balance)>,
+    ) {
+        self.internal_farm_claim(account.farms.get(farm_id), asset_farm)
+    }
+
+    pub fn internal_farm_claim(
+        &self,
+        account_farm: Option<&AccountFarm>,
+        asset_farm: &AssetFarm,
+    ) -> (
+        AccountFarm,
+        Vec<(TokenId, Balance)>,
+        Vec<(TokenId, Balance)>,
     ) {
         let mut new_rewards = vec![];
         let mut inactive_rewards = vec![];
         let block_timestamp = env::block_timestamp();
-        let mut account_farm: AccountFarm = account
-            .farms
-            .get(farm_id)
+        let mut account_farm: AccountFarm = account_farm
             .cloned()
             .unwrap_or_else(AccountFarm::new);
         if account_farm.block_timestamp != block_timestamp {
@@ -232,23 +246,31 @@ impl Contract {
                 for (token_id, amount) in new_rewards {
                     let new_supplied_farm_id = FarmId::Supplied(token_id.clone());
                     let new_token_net_balance_farm_id = FarmId::TokenNetBalance(token_id.clone());
-                    *all_rewards.entry(token_id).or_default() += amount;
                     if account.add_affected_farm(new_supplied_farm_id.clone()) {
                         farms_ids.push(new_supplied_farm_id);
                     }
                     if account.add_affected_farm(new_token_net_balance_farm_id.clone()) {
                         farms_ids.push(new_token_net_balance_farm_id);
                     }
+                    if let Some(AutoCompound::Repay { .. }) = &account.auto_compound {
+                        let new_borrowed_farm_id = FarmId::Borrowed(token_id.clone());
+                        if account.add_affected_farm(new_borrowed_farm_id.clone()) {
+                            farms_ids.push(new_borrowed_farm_id);
+                        }
+                    }
+                    *all_rewards.entry(token_id).or_default() += amount;
                 }
                 farms.push((farm_id, account_farm, asset_farm, inactive_rewards));
             }
         }
+        let reward_vesting_configs = read_reward_vesting_configs_from_storage();
         for (token_id, &reward) in &all_rewards {
-            self.internal_deposit(account, &token_id, reward);
+            self.internal_deposit_farm_reward(account, &reward_vesting_configs, token_id, reward);
         }
         
         let booster_tokens = read_booster_tokens_from_storage();
         account.sync_booster_policy(&booster_tokens);
+        self.internal_sync_received_x_booster_amounts(account, &booster_tokens);
 
         for (farm_id, mut account_farm, mut asset_farm, inactive_rewards) in farms {
             let shares = if self.blacklist_of_farmers.contains(&account.account_id) {
@@ -258,7 +280,9 @@ impl Contract {
                     FarmId::Supplied(token_id) => account.get_supplied_shares(token_id).0,
                     FarmId::Borrowed(token_id) => account.get_borrowed_shares(token_id).0,
                     FarmId::TokenNetBalance(token_id) => self.get_account_token_net_balance(account, token_id),
-                    FarmId::NetTvl => self.get_account_tvl_shares(account)
+                    FarmId::NetTvl => self.get_account_tvl_shares(account),
+                    // Margin farms are tracked by the margin account.
+                    FarmId::MarginDebt(_) | FarmId::MarginPosition(_) => 0,
                 }
             };
             for (token_id, asset_farm_reward) in asset_farm.rewards.iter_mut() {
@@ -294,16 +318,16 @@ pub fn get_booster_extra_shares(account: &Account, shares: u128, asset_farm_rewa
             return Some(0);
         }
         
-        let booster_staking = account.booster_stakings.get(booster_token_id)?;
+        let x_booster_amount = account.get_x_booster_amount(booster_token_id);
 
         let decimals = booster_token_info.booster_decimals as u32;
         let booster_base = 10u128.pow(decimals) * booster_token_info.boost_suppress_factor;
 
-        if booster_staking.x_booster_amount <= booster_base {
+        if x_booster_amount <= booster_base {
             Some(0)
         } else {
             let log_base = (*booster_log_base as f64) / 10f64.powi(decimals as i32);
-            let ratio = booster_staking.x_booster_amount as f64 / booster_base as f64;
+            let ratio = x_booster_amount as f64 / booster_base as f64;
             let extra = (shares as f64 * ratio.log(log_base)) as u128;
             Some(extra)
         }
@@ -316,6 +340,7 @@ impl Contract {
     /// If the account_id is given, then it claims farms for the given account_id or uses
     /// predecessor_account_id otherwise.
     pub fn account_farm_claim_all(&mut self, account_id: Option<AccountId>) {
+        assert_not_paused(&read_pause_registry_from_storage(), PauseKind::FarmClaim, &[]);
         let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
         let mut account = self.internal_unwrap_account(&account_id);
         account
@@ -77,21 +77,30 @@ impl Contract {
         // Set reliable liquidator context if signer is in whitelist
         self.is_reliable_liquidator_context = in_reliable_liquidator_whitelist(&env::signer_account_id().to_string());
 
+        // Each action only checks its own kind, so liquidations stay independent of Execute.
+        let pause_registry = read_pause_registry_from_storage();
+
         self.internal_set_prices(&prices);
         let mut need_number_check = false;
         let mut risk_check_positions = HashSet::new();
         for action in actions {
             assert!(!account.is_locked, "Account is locked!");
+            let (pause_kind, pause_targets) = action.pause_kind_and_targets();
+            assert_not_paused(&pause_registry, pause_kind, &pause_targets);
+            if let Some(operation) = action.blocked_operation() {
+                assert_not_blocked(account_id, operation);
+            }
             match action {
                 Action::Withdraw(asset_amount) => {
                     assert!(!asset_amount.token_id.to_string().starts_with(SHADOW_V1_TOKEN_PREFIX));
                     if account.supplied.get(&asset_amount.token_id).is_some() {
                         let (amount, ft_amount) = self.internal_withdraw(account, &asset_amount);
+                        let extra_decimals = self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals;
                         if ft_amount > 0 {
                             self.internal_ft_transfer(account_id, &asset_amount.token_id, amount, ft_amount, false, account_id);
-                            events::emit::withdraw_started(&account_id, amount, &asset_amount.token_id);
+                            events::emit::withdraw_started(&account_id, amount, &asset_amount.token_id, extra_decimals);
                         } else {
-                            events::emit::withdraw_succeeded(&account_id, amount, &asset_amount.token_id);
+                            events::emit::withdraw_succeeded(&account_id, amount, &asset_amount.token_id, extra_decimals);
                         }
                     }
                 }
@@ -100,11 +109,12 @@ impl Contract {
                     assert!(!asset_amount.token_id.to_string().starts_with(SHADOW_V1_TOKEN_PREFIX));
                     if account.supplied.get(&asset_amount.token_id).is_some() {
                         let (amount, ft_amount) = self.internal_withdraw(account, &asset_amount);
+                        let extra_decimals = self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals;
                         if ft_amount > 0 {
                             self.internal_ft_transfer_call(account_id, &asset_amount.token_id, amount, ft_amount, client_echo);
-                            events::emit::withdraw_started(&account_id, amount, &asset_amount.token_id);
+                            events::emit::withdraw_started(&account_id, amount, &asset_amount.token_id, extra_decimals);
                         } else {
-                            events::emit::withdraw_succeeded(&account_id, amount, &asset_amount.token_id);
+                            events::emit::withdraw_succeeded(&account_id, amount, &asset_amount.token_id, extra_decimals);
                         }
                     }
                 }
@@ -113,7 +123,7 @@ impl Contract {
                     assert!(!asset_amount.token_id.to_string().starts_with(SHADOW_V1_TOKEN_PREFIX));
                     let position = REGULAR_POSITION.to_string();
                     let amount = self.internal_increase_collateral(&position, account, &asset_amount);
-                    events::emit::increase_collateral(&account_id, amount, &asset_amount.token_id, &position);
+                    events::emit::increase_collateral(&account_id, amount, &asset_amount.token_id, &position, self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals);
                 }
                 Action::PositionIncreaseCollateral { position, asset_amount } => {
                     need_number_check = true;
@@ -123,7 +133,7 @@ impl Contract {
                         assert!(asset_amount.token_id.to_string() == position);
                     }
                     let amount = self.internal_increase_collateral(&position, account, &asset_amount);
-                    events::emit::increase_collateral(&account_id, amount, &asset_amount.token_id, &position);
+                    events::emit::increase_collateral(&account_id, amount, &asset_amount.token_id, &position, self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals);
                 }
                 Action::DecreaseCollateral(asset_amount) => {
                     let position = REGULAR_POSITION.to_string();
@@ -138,7 +148,7 @@ impl Contract {
                         &asset_amount,
                     );
                     account.internal_set_asset(&asset_amount.token_id, account_asset);
-                    events::emit::decrease_collateral(&account_id, amount, &asset_amount.token_id, &position);
+                    events::emit::decrease_collateral(&account_id, amount, &asset_amount.token_id, &position, self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals);
                 }
                 Action::PositionDecreaseCollateral { position, asset_amount } => {
                     if position == REGULAR_POSITION {
@@ -156,7 +166,7 @@ impl Contract {
                         &asset_amount,
                     );
                     account.internal_set_asset(&asset_amount.token_id, account_asset);
-                    events::emit::decrease_collateral(&account_id, amount, &asset_amount.token_id, &position);
+                    events::emit::decrease_collateral(&account_id, amount, &asset_amount.token_id, &position, self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals);
                 }
                 Action::Borrow(asset_amount) => {
                     need_number_check = true;
@@ -164,25 +174,25 @@ impl Contract {
                     risk_check_positions.insert(position.clone());
                     account.add_affected_farm(FarmId::Borrowed(asset_amount.token_id.clone()));
                     let amount = self.internal_borrow(&position, account, &asset_amount);
-                    events::emit::borrow(&account_id, amount, &asset_amount.token_id, &position);
+                    events::emit::borrow(&account_id, amount, &asset_amount.token_id, &position, self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals);
                 }
                 Action::PositionBorrow{ position, asset_amount } => {
                     need_number_check = true;
                     risk_check_positions.insert(position.clone());
                     account.add_affected_farm(FarmId::Borrowed(asset_amount.token_id.clone()));
                     let amount = self.internal_borrow(&position, account, &asset_amount);
-                    events::emit::borrow(&account_id, amount, &asset_amount.token_id, &position);
+                    events::emit::borrow(&account_id, amount, &asset_amount.token_id, &position, self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals);
                 }
                 Action::Repay(asset_amount) => {
                     let position = REGULAR_POSITION.to_string();
                     let amount = self.internal_owner_repay(&position, account, &asset_amount);
                     account.add_affected_farm(FarmId::Borrowed(asset_amount.token_id.clone()));
-                    events::emit::repay(&account_id, amount, &asset_amount.token_id, &position);
+                    events::emit::repay(&account_id, amount, &asset_amount.token_id, &position, self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals);
                 }
                 Action::PositionRepay{ position, asset_amount} => {
                     let amount = self.internal_owner_repay(&position, account, &asset_amount);
                     account.add_affected_farm(FarmId::Borrowed(asset_amount.token_id.clone()));
-                    events::emit::repay(&account_id, amount, &asset_amount.token_id, &position);
+                    events::emit::repay(&account_id, amount, &asset_amount.token_id, &position, self.internal_unwrap_asset(&asset_amount.token_id).config.extra_decimals);
                 }
                 Action::Liquidate {
                     account_id: liquidation_account_id,
@@ -910,7 +920,7 @@ impl Contract {
             },
         );
         account.internal_set_asset(token_id, account_asset);
-        events::emit::decrease_collateral(&account_id, amount, token_id, &position);
+        events::emit::decrease_collateral(account_id, amount, token_id, &position, self.internal_unwrap_asset(token_id).config.extra_decimals);
         assert!(self.compute_max_discount(&position, account, &prices) == BigDecimal::zero());
     }
 
@@ -932,7 +942,7 @@ impl Contract {
         );
         assert!(ft_amount > 0, "Withdraw amount can't be 0");
         let promise = self.internal_ft_transfer(&account_id, token_id, amount, ft_amount, false, &recipient_id);
-        events::emit::withdraw_started(&account_id, amount, token_id);
+        events::emit::withdraw_started(account_id, amount, token_id, self.internal_unwrap_asset(token_id).config.extra_decimals);
         self.internal_account_apply_affected_farms(account);
         promise
     }
@@ -999,7 +1009,8 @@ impl Contract {
         let mut account_asset = account.internal_unwrap_asset(&token_id);
 
         let amount = asset.supplied.shares_to_amount(shares, false);
-        let ft_amount = amount / 10u128.pow(asset.config.extra_decimals as u32);
+        let extra_decimals = asset.config.extra_decimals;
+        let ft_amount = amount / 10u128.pow(extra_decimals as u32);
 
         let available_amount = asset.available_amount();
         assert!(
@@ -1018,7 +1029,7 @@ impl Contract {
         self.internal_set_account(&account_id, account);
 
         if ft_amount > 0 {
-            events::emit::withdraw_started(&account_id, amount, &token_id);
+            events::emit::withdraw_started(&account_id, amount, &token_id, extra_decimals);
             ext_ft_core::ext(token_id.clone())
                 .with_attached_deposit(ONE_YOCTO)
                 .with_static_gas(Gas::ONE_TERA * 30)
@@ -1030,7 +1041,7 @@ impl Contract {
                         .after_ft_transfer_call(account_id.clone(), token_id.clone(), ft_amount.into(), amount.into())
                 ).into()
         } else {
-            events::emit::withdraw_succeeded(&account_id, amount, &token_id);
+            events::emit::withdraw_succeeded(&account_id, amount, &token_id, extra_decimals);
             PromiseOrValue::Value(U128(0))
         }
     }
@@ -8,6 +8,7 @@ impl Contract {
         token_id: &AccountId,
         amount: Balance,  // inner decimal precision
     ) {
+        assert_not_paused(&read_pause_registry_from_storage(), PauseKind::Execute, &[token_id.to_string()]);
         // 1. check stage
         let asset = self.internal_unwrap_asset(&token_id);
         assert!(
@@ -26,6 +27,7 @@ impl Contract {
         // 2. on asset level, supply (including collateral) increased
         let mut asset = self.internal_unwrap_asset(token_id);
         asset.supplied.deposit(shares, amount);
+        let extra_decimals = asset.config.extra_decimals;
         
         // 3. on account level, supply (excluding collateral) untouched but position changed
         let mut account = self.internal_unwrap_account(account_id);
@@ -48,7 +50,7 @@ impl Contract {
         self.internal_set_account(&account_id, account);
 
         // 5. emit event
-        events::emit::increase_collateral(account_id, amount, token_id, &position);
+        events::emit::increase_collateral(account_id, amount, token_id, &position, extra_decimals);
     }
 
     /// Use only transferred tokens to repay debt, remains go to supply.
@@ -58,6 +60,7 @@ impl Contract {
         token_id: &AccountId,
         amount: Balance,  // inner decimal precision
     ) {
+        assert_not_paused(&read_pause_registry_from_storage(), PauseKind::Execute, &[token_id.to_string()]);
         let mut account = self.internal_unwrap_account(&account_id);
         assert!(!account.is_locked, "Account is locked!");
         let mut asset = self.internal_unwrap_asset(token_id);
@@ -76,7 +79,7 @@ impl Contract {
         asset.borrowed.withdraw(repay_shares, repay_amount);
         account.decrease_borrowed(&position, token_id, repay_shares);
         account.add_affected_farm(FarmId::Borrowed(token_id.clone()));
-        events::emit::repay(&account_id, repay_amount, token_id, &position);
+        events::emit::repay(&account_id, repay_amount, token_id, &position, asset.config.extra_decimals);
 
         // 2. remaining supply
         let remain_amount = amount - repay_amount;
@@ -86,7 +89,7 @@ impl Contract {
             account_asset.deposit_shares(shares);
             account.internal_set_asset(&token_id, account_asset);
             asset.supplied.deposit(shares, remain_amount);
-            events::emit::deposit(&account_id, remain_amount, &token_id);
+            events::emit::deposit(&account_id, remain_amount, &token_id, asset.config.extra_decimals);
         }
 
         // udate asset
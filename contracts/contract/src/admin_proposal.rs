@@ -0,0 +1,318 @@
+use crate::*;
+use near_sdk::{is_promise_success, serde_json};
+
+const GAS_FOR_ADMIN_PROPOSAL_CALL: Gas = Gas(Gas::ONE_TERA.0 * 50);
+const GAS_FOR_AFTER_ADMIN_PROPOSAL_CALL: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// Admin methods that can be delayed by the timelock.
+/// Emergency-tightening methods, e.g. `disable_asset_capacity`, `unregister_margin_dex` or
+/// `unregister_margin_token`, are not listed and always take effect immediately.
+/// Methods updating a limit are only delayed when the new value loosens the limit,
+/// see `assert_admin_change`.
+pub const TIMELOCKED_ADMIN_METHODS: &[&str] = &[
+    // config.rs
+    "set_owner_id",
+    "update_price_oracle_config",
+    "update_pyth_oracle_config",
+    "update_lp_config",
+    "update_max_num_assets",
+    "update_force_closing_enabled",
+    "add_asset",
+    "update_asset",
+    "update_asset_limit",
+    "update_asset_holding_position_fee_rate",
+    "update_asset_max_change_rate",
+    "upsert_beneficiary",
+    "enable_oracle",
+    "update_dcl_id",
+    "enable_asset_capacity",
+    "update_asset_net_tvl_multiplier",
+    // margin_config.rs
+    "update_max_leverage_rate",
+    "update_pending_debt_scale",
+    "update_max_slippage_rate",
+    "update_min_safety_buffer",
+    "update_margin_debt_discount_rate",
+    "update_open_position_fee_rate",
+    "register_margin_dex",
+    "update_margin_dex",
+    "register_margin_token",
+    "update_margin_token",
+    "update_max_active_user_margin_position",
+    "update_liquidation_benefits_rates",
+    "update_max_position_action_wait_sec",
+    // margin_base_token_limit.rs
+    "set_margin_base_token_limit_gur",
+    "remove_margin_base_token_limit_gur",
+    "set_margin_base_token_limit",
+    "remove_margin_base_token_limit",
+    // Decreasing a delay is timelocked by the current delay of the method.
+    "set_admin_method_delay",
+];
+
+/// The accounts that can call an admin method directly, besides its admin proposal.
+pub enum AdminCaller {
+    Owner,
+    /// The contract owner or an account with the role.
+    Role(Role),
+    /// The contract owner, an account with the role or guardians.
+    RoleOrGuardians(Role),
+}
+
+impl From<Role> for AdminCaller {
+    fn from(role: Role) -> Self {
+        AdminCaller::Role(role)
+    }
+}
+
+/// A serialized admin call waiting for its timelock.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdminProposal {
+    pub id: u32,
+    pub method_name: String,
+    /// JSON arguments of the call.
+    pub args: String,
+    #[serde(with = "u64_dec_format")]
+    pub submit_timestamp: Timestamp,
+    /// The proposal can be executed since this timestamp.
+    #[serde(with = "u64_dec_format")]
+    pub eta: Timestamp,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct AdminProposals {
+    pub next_id: u32,
+    pub proposals: HashMap<u32, AdminProposal>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct SetAdminMethodDelayArgs {
+    method_name: String,
+}
+
+pub fn read_admin_proposals_from_storage() -> AdminProposals {
+    if let Some(content) = env::storage_read(ADMIN_PROPOSALS_KEY.as_bytes()) {
+        AdminProposals::try_from_slice(&content).expect("deserialize admin proposals failed.")
+    } else {
+        AdminProposals::default()
+    }
+}
+
+pub fn write_admin_proposals_to_storage(data: AdminProposals) {
+    env::storage_write(ADMIN_PROPOSALS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+pub fn read_admin_method_delays_from_storage() -> HashMap<String, DurationSec> {
+    if let Some(content) = env::storage_read(ADMIN_METHOD_DELAYS_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize admin method delays failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_admin_method_delays_to_storage(data: HashMap<String, DurationSec>) {
+    env::storage_write(ADMIN_METHOD_DELAYS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+/// Admin proposals whose call is in flight, by ID.
+pub fn read_executing_admin_proposals_from_storage() -> HashMap<u32, String> {
+    if let Some(content) = env::storage_read(EXECUTING_ADMIN_PROPOSALS_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize executing admin proposals failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_executing_admin_proposals_to_storage(data: HashMap<u32, String>) {
+    env::storage_write(EXECUTING_ADMIN_PROPOSALS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+pub fn get_admin_method_delay(method_name: &str) -> DurationSec {
+    read_admin_method_delays_from_storage()
+        .get(method_name)
+        .cloned()
+        .unwrap_or(0)
+}
+
+fn assert_timelocked_admin_method(method_name: &str) {
+    require!(
+        TIMELOCKED_ADMIN_METHODS.contains(&method_name),
+        format!("{} can't be timelocked", method_name)
+    );
+}
+
+impl Contract {
+    /// Returns true if the method is called by the contract itself
+    /// while an admin proposal of the method is being executed.
+    /// Such calls are authorized by the owner who executed the proposal.
+    pub fn is_admin_proposal_call(&self, method_name: &str) -> bool {
+        env::predecessor_account_id() == env::current_account_id()
+            && read_executing_admin_proposals_from_storage()
+                .values()
+                .any(|executing_method_name| executing_method_name == method_name)
+    }
+
+    /// Requires the admin method to be called by its admin proposal, or by the caller
+    /// if the method has no delay.
+    pub fn assert_admin(&self, caller: impl Into<AdminCaller>, method_name: &str) {
+        self.assert_admin_change(caller, method_name, true);
+    }
+
+    /// Like `assert_admin`, but only a change loosening the limit requires the admin proposal.
+    /// Tightening the limit takes effect immediately.
+    pub fn assert_admin_change(&self, caller: impl Into<AdminCaller>, method_name: &str, is_loosening: bool) {
+        if self.is_admin_proposal_call(method_name) {
+            return;
+        }
+        match caller.into() {
+            AdminCaller::Owner => self.assert_owner(),
+            AdminCaller::Role(role) => self.assert_role(role),
+            AdminCaller::RoleOrGuardians(role) => self.assert_role_or_guardians(role),
+        }
+        if is_loosening {
+            require!(
+                get_admin_method_delay(method_name) == 0,
+                format!("{} requires an admin proposal", method_name)
+            );
+        }
+    }
+
+    fn internal_get_admin_proposal_delay(&self, method_name: &str, args: &str) -> DurationSec {
+        if method_name == "set_admin_method_delay" {
+            let args: SetAdminMethodDelayArgs = serde_json::from_str(args).expect("Invalid args");
+            get_admin_method_delay(&args.method_name)
+        } else {
+            get_admin_method_delay(method_name)
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the timelock delay of the admin method.
+    /// Increasing the delay takes effect immediately,
+    /// while decreasing it requires an admin proposal.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_admin_method_delay(&mut self, method_name: String, delay_sec: DurationSec) {
+        assert_one_yocto();
+        let is_admin_proposal_call = self.is_admin_proposal_call("set_admin_method_delay");
+        if !is_admin_proposal_call {
+            self.assert_owner();
+        }
+        assert_timelocked_admin_method(&method_name);
+        let mut delays = read_admin_method_delays_from_storage();
+        let current_delay_sec = delays.get(&method_name).cloned().unwrap_or(0);
+        if delay_sec < current_delay_sec {
+            require!(is_admin_proposal_call, "Decreasing the delay requires an admin proposal");
+        }
+        events::emit::settings_updated("set_admin_method_delay", &env::predecessor_account_id(), json!({"method_name": method_name, "delay_sec": delay_sec}));
+        if delay_sec > 0 {
+            delays.insert(method_name, delay_sec);
+        } else {
+            delays.remove(&method_name);
+        }
+        write_admin_method_delays_to_storage(delays);
+    }
+
+    pub fn get_admin_method_delays(&self) -> HashMap<String, DurationSec> {
+        read_admin_method_delays_from_storage()
+    }
+
+    /// Queues a call of the timelocked admin method with the given JSON arguments.
+    /// It can be executed after the delay of the method.
+    /// Returns the ID of the proposal.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn submit_admin_proposal(&mut self, method_name: String, args: String) -> u32 {
+        assert_one_yocto();
+        self.assert_owner();
+        assert_timelocked_admin_method(&method_name);
+        require!(
+            serde_json::from_str::<serde_json::Value>(&args).map_or(false, |v| v.is_object()),
+            "The args should be a JSON object"
+        );
+        let submit_timestamp = env::block_timestamp();
+        let eta = submit_timestamp + to_nano(self.internal_get_admin_proposal_delay(&method_name, &args));
+        let mut admin_proposals = read_admin_proposals_from_storage();
+        let id = admin_proposals.next_id;
+        admin_proposals.next_id += 1;
+        let proposal = AdminProposal {
+            id,
+            method_name,
+            args,
+            submit_timestamp,
+            eta,
+        };
+        events::emit::admin_proposal_submitted(&proposal);
+        admin_proposals.proposals.insert(id, proposal);
+        write_admin_proposals_to_storage(admin_proposals);
+        id
+    }
+
+    /// Cancels the pending admin proposal.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or guardians.
+    #[payable]
+    pub fn cancel_admin_proposal(&mut self, id: u32) {
+        assert_one_yocto();
+        self.assert_owner_or_guardians();
+        let mut admin_proposals = read_admin_proposals_from_storage();
+        admin_proposals.proposals.remove(&id).expect("Admin proposal not found");
+        write_admin_proposals_to_storage(admin_proposals);
+        events::emit::admin_proposal_cancelled(id, &env::predecessor_account_id());
+    }
+
+    /// Executes the admin proposal after its ETA by calling the method on the contract itself.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn execute_admin_proposal(&mut self, id: u32) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut admin_proposals = read_admin_proposals_from_storage();
+        let proposal = admin_proposals.proposals.remove(&id).expect("Admin proposal not found");
+        require!(env::block_timestamp() >= proposal.eta, "The admin proposal is still timelocked");
+        write_admin_proposals_to_storage(admin_proposals);
+        let mut executing_admin_proposals = read_executing_admin_proposals_from_storage();
+        executing_admin_proposals.insert(id, proposal.method_name.clone());
+        write_executing_admin_proposals_to_storage(executing_admin_proposals);
+        Promise::new(env::current_account_id())
+            .function_call(
+                proposal.method_name,
+                proposal.args.into_bytes(),
+                ONE_YOCTO,
+                GAS_FOR_ADMIN_PROPOSAL_CALL,
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_AFTER_ADMIN_PROPOSAL_CALL)
+                    .after_admin_proposal_executed(id),
+            )
+    }
+
+    #[private]
+    pub fn after_admin_proposal_executed(&mut self, id: u32) -> bool {
+        let mut executing_admin_proposals = read_executing_admin_proposals_from_storage();
+        executing_admin_proposals.remove(&id);
+        write_executing_admin_proposals_to_storage(executing_admin_proposals);
+        let promise_success = is_promise_success();
+        events::emit::admin_proposal_executed(id, promise_success);
+        promise_success
+    }
+
+    /// Returns pending admin proposals ordered by ID.
+    pub fn get_admin_proposals(&self) -> Vec<AdminProposal> {
+        let mut proposals: Vec<AdminProposal> = read_admin_proposals_from_storage()
+            .proposals
+            .into_values()
+            .collect();
+        proposals.sort_by_key(|proposal| proposal.id);
+        proposals
+    }
+}
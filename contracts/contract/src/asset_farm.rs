@@ -3,7 +3,7 @@ use crate::*;
 static ASSET_FARMS: Lazy<Mutex<HashMap<FarmId, Option<AssetFarm>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-const NANOS_PER_DAY: Duration = 24 * 60 * 60 * 10u64.pow(9);
+pub(crate) const NANOS_PER_DAY: Duration = 24 * 60 * 60 * 10u64.pow(9);
 
 /// A data required to keep track of a farm for an account.
 #[derive(BorshSerialize, BorshDeserialize, Serialize)]
@@ -16,6 +16,11 @@ pub struct AssetFarm {
     /// Inactive rewards
     #[serde(skip_serializing)]
     pub inactive_rewards: LookupMap<TokenId, VAssetFarmReward>,
+    /// Rewards that reached their end timestamp with remaining rewards left.
+    /// The remaining rewards are returned to the reserve when the farm is saved.
+    #[borsh_skip]
+    #[serde(skip_serializing)]
+    pub ended_rewards: Vec<TokenId>,
 }
 
 impl Clone for AssetFarm {
@@ -27,6 +32,7 @@ impl Clone for AssetFarm {
                 &self.inactive_rewards.try_to_vec().unwrap(),
             )
             .unwrap(),
+            ended_rewards: self.ended_rewards.clone(),
         }
     }
 }
@@ -34,6 +40,7 @@ impl Clone for AssetFarm {
 #[derive(BorshSerialize, BorshDeserialize, Clone)]
 pub enum VAssetFarmReward {
     V0(AssetFarmRewardV0),
+    V1(AssetFarmRewardV1),
     Current(AssetFarmReward),
 }
 
@@ -41,6 +48,7 @@ impl From<VAssetFarmReward> for AssetFarmReward {
     fn from(v: VAssetFarmReward) -> Self {
         match v {
             VAssetFarmReward::V0(c) => c.into(),
+            VAssetFarmReward::V1(c) => c.into(),
             VAssetFarmReward::Current(c) => c,
         }
     }
@@ -72,6 +80,55 @@ pub struct AssetFarmReward {
     pub boosted_shares: Balance,
     #[serde(skip)]
     pub reward_per_share: BigDecimal,
+    /// The timestamp to start distributing rewards, None means immediately.
+    pub start_timestamp: Option<U64>,
+    /// The timestamp to stop distributing rewards, None means until remaining_rewards run out.
+    pub end_timestamp: Option<U64>,
+}
+
+impl AssetFarmReward {
+    pub fn is_scheduled(&self, timestamp: Timestamp) -> bool {
+        self.start_timestamp.map_or(false, |start| timestamp < start.0)
+    }
+
+    pub fn is_finished(&self, timestamp: Timestamp) -> bool {
+        self.remaining_rewards == 0 || self.end_timestamp.map_or(false, |end| timestamp >= end.0)
+    }
+}
+
+/// The parameters of a scheduled asset farm reward.
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetFarmRewardSchedule {
+    /// The amount of reward distributed per day.
+    pub new_reward_per_day: U128,
+    /// The log base for each booster token, including decimals of the booster.
+    pub new_booster_log_bases: HashMap<TokenId, U128>,
+    /// The amount of reserved reward tokens added to the reward.
+    pub reward_amount: U128,
+    /// The timestamp to start distributing rewards.
+    pub start_timestamp: U64,
+    /// The timestamp to stop distributing rewards, None means until remaining_rewards run out.
+    pub end_timestamp: Option<U64>,
+}
+
+#[derive(Serialize, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub enum AssetFarmRewardStatus {
+    Scheduled,
+    Active,
+    Finished,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetFarmRewardProgramView {
+    pub reward_token_id: TokenId,
+    pub status: AssetFarmRewardStatus,
+    pub asset_farm_reward: AssetFarmReward,
 }
 
 impl AssetFarm {
@@ -80,25 +137,30 @@ impl AssetFarm {
         if block_timestamp == self.block_timestamp {
             return;
         }
-        let time_diff = block_timestamp - self.block_timestamp;
+        let prev_timestamp = self.block_timestamp;
         self.block_timestamp = block_timestamp;
         let mut new_inactive_reward = vec![];
         for (token_id, reward) in self.rewards.iter_mut() {
-            if reward.boosted_shares == 0 {
-                continue;
+            // Only distribute within [start_timestamp, end_timestamp) of the reward.
+            let from_timestamp = std::cmp::max(prev_timestamp, reward.start_timestamp.map_or(0, |v| v.0));
+            let to_timestamp = std::cmp::min(block_timestamp, reward.end_timestamp.map_or(u64::MAX, |v| v.0));
+            if reward.boosted_shares > 0 && to_timestamp > from_timestamp {
+                let acquired_rewards = std::cmp::min(
+                    reward.remaining_rewards,
+                    u128_ratio(
+                        reward.reward_per_day,
+                        u128::from(to_timestamp - from_timestamp),
+                        u128::from(NANOS_PER_DAY),
+                    ),
+                );
+                reward.remaining_rewards -= acquired_rewards;
+                reward.reward_per_share = reward.reward_per_share
+                    + BigDecimal::from(acquired_rewards) / BigDecimal::from(reward.boosted_shares);
             }
-            let acquired_rewards = std::cmp::min(
-                reward.remaining_rewards,
-                u128_ratio(
-                    reward.reward_per_day,
-                    u128::from(time_diff),
-                    u128::from(NANOS_PER_DAY),
-                ),
-            );
-            reward.remaining_rewards -= acquired_rewards;
-            reward.reward_per_share = reward.reward_per_share
-                + BigDecimal::from(acquired_rewards) / BigDecimal::from(reward.boosted_shares);
-            if reward.remaining_rewards == 0 {
+            if reward.is_finished(block_timestamp) {
+                if reward.remaining_rewards > 0 {
+                    self.ended_rewards.push(token_id.clone());
+                }
                 new_inactive_reward.push(token_id.clone());
             }
         }
@@ -137,6 +199,7 @@ impl AssetFarm {
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum VAssetFarm {
     V0(AssetFarmV0),
+    V1(AssetFarmV1),
     Current(AssetFarm),
 }
 
@@ -144,6 +207,7 @@ impl From<VAssetFarm> for AssetFarm {
     fn from(v: VAssetFarm) -> Self {
         match v {
             VAssetFarm::V0(c) => c.into(),
+            VAssetFarm::V1(c) => c.into(),
             VAssetFarm::Current(c) => c,
         }
     }
@@ -174,7 +238,20 @@ impl Contract {
         })
     }
 
-    pub fn internal_set_asset_farm(&mut self, farm_id: &FarmId, asset_farm: AssetFarm) {
+    pub fn internal_set_asset_farm(&mut self, farm_id: &FarmId, mut asset_farm: AssetFarm) {
+        for token_id in std::mem::take(&mut asset_farm.ended_rewards) {
+            // The reward may have been added again in the meantime, then it keeps the remaining rewards.
+            if let Some(mut reward) = asset_farm.internal_get_inactive_asset_farm_reward(&token_id) {
+                if reward.remaining_rewards > 0 {
+                    let mut asset = self.internal_unwrap_asset(&token_id);
+                    asset.reserved += reward.remaining_rewards;
+                    self.internal_set_asset(&token_id, asset);
+                    events::emit::asset_farm_reward_returned(farm_id, &token_id, reward.remaining_rewards);
+                    reward.remaining_rewards = 0;
+                    asset_farm.internal_set_inactive_asset_farm_reward(&token_id, reward);
+                }
+            }
+        }
         ASSET_FARMS
             .lock()
             .unwrap()
@@ -201,6 +278,44 @@ impl Contract {
             .collect()
     }
 
+    /// Returns scheduled, active and finished reward programs of the farm with a given farm ID.
+    pub fn get_asset_farm_reward_programs(&self, farm_id: FarmId) -> Vec<AssetFarmRewardProgramView> {
+        let block_timestamp = env::block_timestamp();
+        self.internal_get_asset_farm(&farm_id, true)
+            .map(|asset_farm| {
+                let mut programs: Vec<AssetFarmRewardProgramView> = asset_farm
+                    .rewards
+                    .iter()
+                    .map(|(reward_token_id, asset_farm_reward)| {
+                        let status = if asset_farm_reward.is_scheduled(block_timestamp) {
+                            AssetFarmRewardStatus::Scheduled
+                        } else if asset_farm_reward.is_finished(block_timestamp) {
+                            AssetFarmRewardStatus::Finished
+                        } else {
+                            AssetFarmRewardStatus::Active
+                        };
+                        AssetFarmRewardProgramView {
+                            reward_token_id: reward_token_id.clone(),
+                            status,
+                            asset_farm_reward: asset_farm_reward.clone(),
+                        }
+                    })
+                    .collect();
+                // Reward tokens are always registered assets.
+                for reward_token_id in self.asset_ids.iter() {
+                    if let Some(asset_farm_reward) = asset_farm.internal_get_inactive_asset_farm_reward(&reward_token_id) {
+                        programs.push(AssetFarmRewardProgramView {
+                            reward_token_id,
+                            status: AssetFarmRewardStatus::Finished,
+                            asset_farm_reward,
+                        });
+                    }
+                }
+                programs
+            })
+            .unwrap_or_default()
+    }
+
     /// Deprecated. Returns all farms using `get_asset_farms_all()`.
     #[allow(unused)]
     pub fn get_asset_farms_paged(
@@ -217,7 +332,9 @@ impl Contract {
         for token_id in self.asset_ids.iter() {
             farm_ids.push(FarmId::Supplied(token_id.clone()));
             farm_ids.push(FarmId::Borrowed(token_id.clone()));
-            farm_ids.push(FarmId::TokenNetBalance(token_id));
+            farm_ids.push(FarmId::TokenNetBalance(token_id.clone()));
+            farm_ids.push(FarmId::MarginDebt(token_id.clone()));
+            farm_ids.push(FarmId::MarginPosition(token_id));
         }
         farm_ids.push(FarmId::NetTvl);
         self.get_asset_farms(farm_ids)
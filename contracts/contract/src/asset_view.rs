@@ -62,6 +62,8 @@ impl Contract {
                 FarmId::Supplied(token_id.clone()),
                 FarmId::Borrowed(token_id.clone()),
                 FarmId::TokenNetBalance(token_id.clone()),
+                FarmId::MarginDebt(token_id.clone()),
+                FarmId::MarginPosition(token_id.clone()),
             ])
             .into_iter()
             .map(|(farm_id, asset_farm)| AssetFarmView {
@@ -0,0 +1,92 @@
+use crate::*;
+
+/// How the farm rewards claimed by an account are compounded.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum AutoCompound {
+    /// Claimed rewards increase the collateral of the regular position.
+    Collateral,
+    /// Claimed rewards repay the borrow of the reward token in the given position.
+    Repay { position: String },
+}
+
+impl Contract {
+    /// Deposits the claimed farm reward into the supplied balance of the account
+    /// and compounds it according to its auto_compound preference.
+    /// Falls back to a plain claim, i.e. keeps the reward supplied, if the asset can't be used
+    /// as a collateral, the reward would exceed the supply limit or there is nothing to repay.
+    pub fn internal_auto_compound_farm_reward(
+        &mut self,
+        account: &mut Account,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        // The supply limit is checked before the reward is deposited.
+        let asset = self.internal_unwrap_asset(token_id);
+        let exceeds_supplied_limit = asset
+            .config
+            .supplied_limit
+            .map_or(false, |supplied_limit| asset.supplied.balance + amount > supplied_limit.0);
+        self.internal_deposit(account, token_id, amount);
+        if account.is_locked || token_id.as_str().starts_with(SHADOW_V1_TOKEN_PREFIX) {
+            return;
+        }
+        let asset_amount = AssetAmount {
+            token_id: token_id.clone(),
+            amount: None,
+            max_amount: Some(amount.into()),
+        };
+        match account.auto_compound.clone() {
+            Some(AutoCompound::Collateral) => {
+                let asset = self.internal_unwrap_asset(token_id);
+                if !asset.config.can_use_as_collateral || exceeds_supplied_limit {
+                    return;
+                }
+                let position = REGULAR_POSITION.to_string();
+                let is_new_asset = match account.positions.get(&position) {
+                    Some(Position::RegularPosition(regular_position)) => !regular_position.collateral.contains_key(token_id),
+                    _ => true,
+                };
+                if is_new_asset && account.get_assets_num() >= self.internal_config().max_num_assets {
+                    return;
+                }
+                let amount = self.internal_increase_collateral(&position, account, &asset_amount);
+                events::emit::increase_collateral(&account.account_id, amount, token_id, &position, asset.config.extra_decimals);
+            }
+            Some(AutoCompound::Repay { position }) => {
+                if !account.positions.get(&position).map(|p| p.is_borrowed(token_id)).unwrap_or(false) {
+                    return;
+                }
+                account.add_affected_farm(FarmId::Borrowed(token_id.clone()));
+                let amount = self.internal_owner_repay(&position, account, &asset_amount);
+                events::emit::repay(&account.account_id, amount, token_id, &position, self.internal_unwrap_asset(token_id).config.extra_decimals);
+            }
+            None => {}
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets how farm rewards claimed by the predecessor are compounded.
+    /// `None` keeps claimed rewards in the supplied balance.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn set_auto_compound(&mut self, auto_compound: Option<AutoCompound>) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        if let Some(AutoCompound::Repay { position }) = &auto_compound {
+            require!(account.positions.contains_key(position), "Position not found");
+        }
+        events::emit::settings_updated("set_auto_compound", &env::predecessor_account_id(), json!({"auto_compound": auto_compound}));
+        account.auto_compound = auto_compound;
+        self.internal_set_account(&account_id, account);
+    }
+
+    pub fn get_auto_compound(&self, account_id: AccountId) -> Option<AutoCompound> {
+        self.internal_get_account(&account_id, true)
+            .and_then(|account| account.auto_compound)
+    }
+}
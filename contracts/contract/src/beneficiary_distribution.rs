@@ -0,0 +1,260 @@
+use crate::*;
+use near_sdk::serde_json;
+
+pub const BENEFICIARY_FEE_SWAP_OP: &str = "swap_beneficiary_fee";
+
+/// How the accrued fees of a beneficiary are distributed.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct BeneficiaryPayoutConfig {
+    /// The minimum accrued fee of the token to be distributed, in inner decimals.
+    pub min_payouts: HashMap<TokenId, U128>,
+    /// The token all fees are paid in. Fees of other tokens are kept until anyone swaps them
+    /// into the payout token with `SwapBeneficiaryFee` or `swap_beneficiary_fee_with_pyth`,
+    /// at a rate bounded by the oracle prices and the max slippage rate of the margin config.
+    pub payout_token_id: Option<TokenId>,
+}
+
+/// Swaps the accrued fee of a beneficiary into its payout token through a registered dex.
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct BeneficiaryFeeSwap {
+    pub beneficiary_id: AccountId,
+    pub token_in_id: TokenId,
+    /// The fee amount to swap, in inner decimals.
+    pub amount_in: U128,
+    /// The minimum amount of the payout token, in inner decimals. It's bounded by the oracle
+    /// prices and the max slippage rate of the margin config.
+    pub min_amount_out: U128,
+    pub swap_indication: SwapIndication,
+}
+
+pub fn read_beneficiary_payout_configs_from_storage() -> HashMap<AccountId, BeneficiaryPayoutConfig> {
+    if let Some(content) = env::storage_read(BENEFICIARY_PAYOUT_CONFIGS_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize beneficiary payout configs failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_beneficiary_payout_configs_to_storage(data: HashMap<AccountId, BeneficiaryPayoutConfig>) {
+    env::storage_write(BENEFICIARY_PAYOUT_CONFIGS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+impl Contract {
+    /// Requires the operator to be the beneficiary itself, the contract owner,
+    /// a BeneficiaryManager or guardians.
+    pub fn assert_beneficiary_operator(&self, operator_id: &AccountId, beneficiary_id: &AccountId) {
+        if operator_id != beneficiary_id {
+            self.assert_account_role_or_guardians(operator_id, Role::BeneficiaryManager);
+        }
+    }
+
+    /// Takes the fee of the beneficiary and sends it to the dex.
+    /// The output is received through `ft_on_transfer` with the `swap_beneficiary_fee` swap reference,
+    /// which must match the pending swap.
+    /// The amount in can't be below the minimum payout of the token, so fees aren't swapped in dust.
+    pub fn internal_swap_beneficiary_fee(&mut self, swap: BeneficiaryFeeSwap, prices: &Prices) {
+        let payout_config = read_beneficiary_payout_configs_from_storage()
+            .remove(&swap.beneficiary_id)
+            .unwrap_or_default();
+        let token_out_id = payout_config.payout_token_id.expect("The beneficiary has no payout token");
+        let (amount_in, ft_amount_in, swap_detail) = self.internal_verify_swap(
+            &swap.token_in_id,
+            swap.amount_in.0,
+            &token_out_id,
+            swap.min_amount_out.0,
+            &swap.swap_indication,
+            prices,
+        );
+        let min_payout = payout_config.min_payouts.get(&swap.token_in_id).map_or(0, |min_payout| min_payout.0);
+        require!(amount_in >= min_payout, "The amount in is below the minimum payout");
+        let mut asset_in = self.internal_unwrap_asset(&swap.token_in_id);
+        let fee = asset_in.beneficiary_fees.get(&swap.beneficiary_id).map_or(0, |fee| fee.0);
+        let remaining_fee = fee.checked_sub(amount_in).expect("Beneficiary fee not enough!");
+        if remaining_fee > 0 {
+            asset_in.beneficiary_fees.insert(swap.beneficiary_id.clone(), U128(remaining_fee));
+        } else {
+            asset_in.beneficiary_fees.remove(&swap.beneficiary_id);
+        }
+        self.internal_set_asset(&swap.token_in_id, asset_in);
+        let swap_id = self.internal_add_pending_swap(PendingSwap {
+            op: BENEFICIARY_FEE_SWAP_OP.to_string(),
+            account_id: swap.beneficiary_id.clone(),
+            dex_id: swap_detail.dex_id.clone(),
+            token_in_id: swap.token_in_id.clone(),
+            amount_in,
+            token_out_id: token_out_id.clone(),
+            timestamp: env::block_timestamp(),
+        });
+        events::emit::swap_beneficiary_fee_started(swap_id, &swap.beneficiary_id, &swap.token_in_id, amount_in, &token_out_id, swap.min_amount_out.0);
+
+        let swap_ref = SwapReference {
+            account_id: swap.beneficiary_id.clone(),
+            pos_id: swap_id.to_string(),
+            amount_in: amount_in.into(),
+            action_ts: env::block_timestamp().into(),
+            op: BENEFICIARY_FEE_SWAP_OP.to_string(),
+            liquidator_id: None,
+            leg: None,
+        };
+        self.internal_send_swap(swap_detail, &swap_ref, &swap.token_in_id, ft_amount_in)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_FT_TRANSFER_CALL_CALLBACK)
+                    .with_unused_gas_weight(0)
+                    .callback_swap_beneficiary_fee(swap_id, swap.beneficiary_id, swap.token_in_id, amount_in.into()),
+            );
+    }
+
+    /// Adds the output of the swap to the fee of the beneficiary in the payout token.
+    pub fn on_swap_beneficiary_fee_return(&mut self, sender_id: &AccountId, token_out_id: &TokenId, amount_out: Balance, swap_ref: &SwapReference) {
+        let pending_swap = self.internal_take_pending_swap(sender_id, token_out_id, swap_ref);
+        let mut asset_out = self.internal_unwrap_asset(token_out_id);
+        let fee = asset_out.beneficiary_fees.get(&pending_swap.account_id).map_or(0, |fee| fee.0);
+        asset_out.beneficiary_fees.insert(pending_swap.account_id.clone(), U128(fee + amount_out));
+        self.internal_set_asset(token_out_id, asset_out);
+        events::emit::swap_beneficiary_fee_succeeded(
+            &pending_swap.account_id,
+            &pending_swap.token_in_id,
+            pending_swap.amount_in,
+            token_out_id,
+            amount_out,
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets how the fees of the beneficiary are distributed. `None` resets to the defaults.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the beneficiary, the contract owner, a BeneficiaryManager or guardians.
+    #[payable]
+    pub fn set_beneficiary_payout_config(&mut self, beneficiary_id: AccountId, payout_config: Option<BeneficiaryPayoutConfig>) {
+        assert_one_yocto();
+        self.assert_beneficiary_operator(&env::predecessor_account_id(), &beneficiary_id);
+        events::emit::settings_updated(
+            "set_beneficiary_payout_config",
+            &env::predecessor_account_id(),
+            json!({"beneficiary_id": beneficiary_id, "payout_config": payout_config}),
+        );
+        let mut payout_configs = read_beneficiary_payout_configs_from_storage();
+        if let Some(payout_config) = payout_config {
+            if let Some(payout_token_id) = payout_config.payout_token_id.as_ref() {
+                self.internal_unwrap_asset(payout_token_id);
+            }
+            payout_configs.insert(beneficiary_id, payout_config);
+        } else {
+            payout_configs.remove(&beneficiary_id);
+        }
+        write_beneficiary_payout_configs_to_storage(payout_configs);
+    }
+
+    pub fn get_beneficiary_payout_configs(&self) -> HashMap<AccountId, BeneficiaryPayoutConfig> {
+        read_beneficiary_payout_configs_from_storage()
+    }
+
+    /// Transfers the accrued fees of the given assets to every beneficiary.
+    /// Fees below the minimum payout, or fees of another token than the payout token
+    /// of the beneficiary, keep accruing. The latter are paid once anyone swaps them
+    /// into the payout token with `SwapBeneficiaryFee` or `swap_beneficiary_fee_with_pyth`.
+    /// Failed transfers are returned to the fees.
+    /// Returns the number of transfers.
+    /// Can be called by anyone.
+    pub fn distribute_beneficiary_fees(&mut self, token_ids: Vec<TokenId>) -> u32 {
+        let payout_configs = read_beneficiary_payout_configs_from_storage();
+        let mut num_payouts = 0;
+        for token_id in token_ids {
+            let mut asset = self.internal_unwrap_asset(&token_id);
+            let extra_decimals = asset.config.extra_decimals;
+            let extra_decimals_mult = 10u128.pow(extra_decimals as u32);
+            let mut beneficiary_ids: Vec<AccountId> = asset.beneficiary_fees.keys().cloned().collect();
+            beneficiary_ids.sort();
+            let mut payouts = vec![];
+            for beneficiary_id in beneficiary_ids {
+                let amount = asset.beneficiary_fees[&beneficiary_id].0;
+                let payout_config = payout_configs.get(&beneficiary_id);
+                if payout_config
+                    .and_then(|payout_config| payout_config.payout_token_id.as_ref())
+                    .map_or(false, |payout_token_id| payout_token_id != &token_id)
+                {
+                    continue;
+                }
+                let min_payout = payout_config
+                    .and_then(|payout_config| payout_config.min_payouts.get(&token_id))
+                    .map_or(0, |min_payout| min_payout.0);
+                let ft_amount = amount / extra_decimals_mult;
+                if ft_amount > 0 && amount >= min_payout {
+                    asset.beneficiary_fees.remove(&beneficiary_id);
+                    payouts.push((beneficiary_id, amount, ft_amount));
+                }
+            }
+            self.internal_set_asset(&token_id, asset);
+            events::emit::distribute_beneficiary_fees(&env::predecessor_account_id(), &token_id, &payouts);
+            for (beneficiary_id, amount, ft_amount) in payouts {
+                events::emit::withdraw_beneficiary_fee_started(&beneficiary_id, amount, &token_id, extra_decimals);
+                self.internal_beneficiary_withdraw(&beneficiary_id, &token_id, amount, ft_amount);
+                num_payouts += 1;
+            }
+        }
+        num_payouts
+    }
+
+    /// Swaps the beneficiary fee using the pyth oracle prices.
+    /// With the price oracle, the swap is requested through `oracle_on_call`
+    /// with `PriceReceiverMsg::SwapBeneficiaryFee`.
+    /// - Requires one yoctoNEAR.
+    /// - Can be called by anyone.
+    #[payable]
+    pub fn swap_beneficiary_fee_with_pyth(&mut self, swap: BeneficiaryFeeSwap) {
+        assert_one_yocto();
+        assert!(self.internal_config().enable_pyth_oracle, "Pyth oracle disabled");
+        let token_out_id = read_beneficiary_payout_configs_from_storage()
+            .remove(&swap.beneficiary_id)
+            .and_then(|payout_config| payout_config.payout_token_id)
+            .expect("The beneficiary has no payout token");
+        let involved_tokens = vec![swap.token_in_id.clone(), token_out_id];
+        let (promise_token_ids, default_prices) = self.prepare_promise_tokens(&involved_tokens);
+        if !promise_token_ids.is_empty() {
+            let (all_promise_flags, promise) = self.generate_flags_and_promise(&promise_token_ids);
+            promise.then(
+                Self::ext(env::current_account_id())
+                    .callback_swap_beneficiary_fee_with_pyth(involved_tokens, all_promise_flags, swap, default_prices)
+            );
+        } else {
+            self.internal_swap_beneficiary_fee(swap, &Prices::from_prices(default_prices));
+        }
+    }
+
+    #[private]
+    pub fn callback_swap_beneficiary_fee_with_pyth(&mut self, involved_tokens: Vec<TokenId>, all_promise_flags: Vec<String>, swap: BeneficiaryFeeSwap, default_prices: HashMap<TokenId, Price>) {
+        assert!(env::promise_results_count() == all_promise_flags.len() as u64, "Invalid promise count");
+        let all_prices = self.generate_all_prices(involved_tokens, all_promise_flags, default_prices);
+        self.internal_swap_beneficiary_fee(swap, &all_prices);
+    }
+
+    /// Returns the fee to the beneficiary if the dex didn't take the input token.
+    /// The swap is no longer pending then, since no output will be returned.
+    #[private]
+    pub fn callback_swap_beneficiary_fee(&mut self, swap_id: u64, beneficiary_id: AccountId, token_in_id: TokenId, amount_in: U128) {
+        let mut asset_in = self.internal_unwrap_asset(&token_in_id);
+        let extra_decimals_mult = 10u128.pow(asset_in.config.extra_decimals as u32);
+        let ft_amount_in_used = if let Some(cross_call_result) = promise_result_as_success() {
+            serde_json::from_slice::<U128>(&cross_call_result).unwrap().0
+        } else {
+            0
+        };
+        if ft_amount_in_used == 0 {
+            pending_swaps().remove(&swap_id);
+        }
+        let unused_amount = amount_in.0 - ft_amount_in_used * extra_decimals_mult;
+        if unused_amount > 0 {
+            let fee = asset_in.beneficiary_fees.get(&beneficiary_id).map_or(0, |fee| fee.0);
+            asset_in.beneficiary_fees.insert(beneficiary_id.clone(), U128(fee + unused_amount));
+            self.internal_set_asset(&token_in_id, asset_in);
+            events::emit::swap_beneficiary_fee_refunded(&beneficiary_id, &token_in_id, unused_amount);
+        }
+    }
+}
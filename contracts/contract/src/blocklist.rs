@@ -0,0 +1,131 @@
+use crate::*;
+
+/// The operations blocked accounts can't perform.
+/// Repaying, closing positions and withdrawing to themselves stay allowed,
+/// and blocked accounts can still be liquidated.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum BlockedOperation {
+    /// Depositing through `ft_on_transfer`, except for repayments.
+    Deposit,
+    Borrow,
+    /// Opening or increasing margin positions.
+    OpenMarginPosition,
+    CastShadow,
+}
+
+/// Reads the blocked accounts. Membership checks only read the entry of the account,
+/// not the entire list.
+pub fn read_blocklist_from_storage() -> UnorderedSet<AccountId> {
+    if let Some(content) = env::storage_read(BLOCKLIST_KEY.as_bytes()) {
+        UnorderedSet::try_from_slice(&content).expect("deserialize blocklist failed.")
+    } else {
+        UnorderedSet::new(BLOCKLIST_KEY.as_bytes())
+    }
+}
+
+pub fn write_blocklist_to_storage(data: UnorderedSet<AccountId>) {
+    env::storage_write(BLOCKLIST_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+/// Emits the rejection and panics if the account is blocked.
+pub fn assert_not_blocked(account_id: &AccountId, operation: BlockedOperation) {
+    if read_blocklist_from_storage().contains(account_id) {
+        events::emit::blocked_operation_rejected(account_id, operation);
+        env::panic_str(&format!("{} is blocked from {:?}", account_id, operation));
+    }
+}
+
+impl TokenReceiverMsg {
+    /// Whether the transfer is allowed from a blocked sender, i.e. it only repays debts
+    /// or returns the output of a swap.
+    pub fn is_allowed_for_blocked_sender(&self) -> bool {
+        match self {
+            TokenReceiverMsg::OnlyRepay | TokenReceiverMsg::SwapReference { .. } => true,
+            TokenReceiverMsg::Execute { actions } | TokenReceiverMsg::ExecuteWithPyth { actions } => {
+                !actions.is_empty()
+                    && actions
+                        .iter()
+                        .all(|action| matches!(action, Action::Repay(_) | Action::PositionRepay { .. }))
+            }
+            TokenReceiverMsg::MarginExecute { actions } | TokenReceiverMsg::MarginExecuteWithPyth { actions } => {
+                !actions.is_empty()
+                    && actions
+                        .iter()
+                        .all(|action| matches!(action, MarginAction::RepayAndClaimMTPosition { .. }))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Action {
+    pub fn blocked_operation(&self) -> Option<BlockedOperation> {
+        match self {
+            Action::Borrow(_) | Action::PositionBorrow { .. } => Some(BlockedOperation::Borrow),
+            _ => None,
+        }
+    }
+}
+
+impl MarginAction {
+    pub fn blocked_operation(&self) -> Option<BlockedOperation> {
+        match self {
+            MarginAction::OpenPosition { .. } | MarginAction::IncreaseMTPosition { .. } => {
+                Some(BlockedOperation::OpenMarginPosition)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the accounts to the blocklist.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner, a RiskAdmin or guardians.
+    #[payable]
+    pub fn extend_blocklist(&mut self, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_role_or_guardians(Role::RiskAdmin);
+        events::emit::settings_updated("extend_blocklist", &env::predecessor_account_id(), json!({"account_ids": account_ids}));
+        let mut blocklist = read_blocklist_from_storage();
+        for account_id in account_ids {
+            let is_success = blocklist.insert(&account_id);
+            require!(is_success, format!("{} is already blocked", account_id));
+        }
+        write_blocklist_to_storage(blocklist);
+    }
+
+    /// Removes the accounts from the blocklist.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_blocklist(&mut self, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        events::emit::settings_updated("remove_blocklist", &env::predecessor_account_id(), json!({"account_ids": account_ids}));
+        let mut blocklist = read_blocklist_from_storage();
+        for account_id in account_ids {
+            let is_success = blocklist.remove(&account_id);
+            require!(is_success, format!("{} is not blocked", account_id));
+        }
+        write_blocklist_to_storage(blocklist);
+    }
+
+    /// Returns a page of blocked accounts.
+    pub fn get_blocklist(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<AccountId> {
+        let blocklist = read_blocklist_from_storage();
+        let values = blocklist.as_vector();
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(values.len());
+        (from_index..std::cmp::min(values.len(), from_index + limit))
+            .map(|index| values.get(index).unwrap())
+            .collect()
+    }
+
+    pub fn is_blocked(&self, account_id: AccountId) -> bool {
+        read_blocklist_from_storage().contains(&account_id)
+    }
+}
@@ -0,0 +1,415 @@
+use crate::*;
+
+/// The maximum number of accounts that one account can delegate xBooster of a booster token to.
+pub const MAX_NUM_BOOSTER_DELEGATES: usize = 10;
+/// The maximum number of accounts that one account can receive xBooster of a booster token from.
+pub const MAX_NUM_BOOSTER_DELEGATORS: usize = 10;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct BoosterDelegation {
+    /// The share of the delegator xBooster given to the delegate.
+    /// E.g. 2500 means 25%.
+    pub weight: u32,
+    /// When the delegation can be revoked in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub revocable_timestamp: Timestamp,
+    /// Whether the delegate accepted the delegation. Until then the weight stays with the delegator,
+    /// and the delegate doesn't store anything for it.
+    pub is_accepted: bool,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct BoosterDelegationView {
+    pub booster_token_id: TokenId,
+    /// The account that delegates xBooster.
+    pub delegator_id: AccountId,
+    /// The account that receives xBooster.
+    pub delegate_id: AccountId,
+    pub weight: u32,
+    /// The current amount of delegated xBooster.
+    #[serde(with = "u128_dec_format")]
+    pub x_booster_amount: Balance,
+    #[serde(with = "u64_dec_format")]
+    pub revocable_timestamp: Timestamp,
+    pub is_accepted: bool,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountBoosterDelegationsView {
+    /// Delegations from the account to other accounts.
+    pub delegated: Vec<BoosterDelegationView>,
+    /// Delegations from other accounts to the account.
+    pub received: Vec<BoosterDelegationView>,
+}
+
+pub fn read_booster_delegation_cooldown_sec_from_storage() -> DurationSec {
+    if let Some(content) = env::storage_read(BOOSTER_DELEGATION_COOLDOWN_KEY.as_bytes()) {
+        DurationSec::try_from_slice(&content).expect("deserialize booster delegation cooldown failed.")
+    } else {
+        0
+    }
+}
+
+pub fn write_booster_delegation_cooldown_sec_to_storage(data: DurationSec) {
+    env::storage_write(BOOSTER_DELEGATION_COOLDOWN_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+impl Account {
+    /// Returns the total weight of xBooster of the booster token delegated to other accounts,
+    /// including delegations that aren't accepted yet.
+    pub fn get_delegated_booster_weight(&self, booster_token_id: &TokenId) -> u32 {
+        self.booster_delegations
+            .get(booster_token_id)
+            .map(|delegations| delegations.values().map(|d| d.weight).sum())
+            .unwrap_or(0)
+    }
+
+    /// Returns the total weight of xBooster of the booster token given to delegates that accepted it.
+    pub fn get_accepted_delegated_booster_weight(&self, booster_token_id: &TokenId) -> u32 {
+        self.booster_delegations
+            .get(booster_token_id)
+            .map(|delegations| delegations.values().filter(|d| d.is_accepted).map(|d| d.weight).sum())
+            .unwrap_or(0)
+    }
+
+    /// Returns the amount of xBooster that boosts farms of the account,
+    /// which is the own xBooster that is not delegated plus the received xBooster.
+    /// The received xBooster should be synced with `internal_sync_received_x_booster_amounts`.
+    pub fn get_x_booster_amount(&self, booster_token_id: &TokenId) -> Balance {
+        let own_x_booster_amount = self
+            .booster_stakings
+            .get(booster_token_id)
+            .map(|booster_staking| {
+                u128_ratio(
+                    booster_staking.x_booster_amount,
+                    (MAX_RATIO - self.get_accepted_delegated_booster_weight(booster_token_id)) as u128,
+                    MAX_RATIO as u128,
+                )
+            })
+            .unwrap_or(0);
+        own_x_booster_amount
+            + self
+                .received_x_booster_amounts
+                .get(booster_token_id)
+                .cloned()
+                .unwrap_or(0)
+    }
+}
+
+impl Contract {
+    /// Returns the current xBooster amount delegated by the delegator to the delegate.
+    fn internal_get_delegated_x_booster_amount(
+        &self,
+        delegator_id: &AccountId,
+        delegate_id: &AccountId,
+        booster_token_id: &TokenId,
+        booster_tokens: &HashMap<TokenId, BoosterTokenInfo>,
+    ) -> Balance {
+        self.internal_get_account(delegator_id, true)
+            .and_then(|mut delegator| {
+                let booster_delegation = delegator
+                    .booster_delegations
+                    .get(booster_token_id)?
+                    .get(delegate_id)?;
+                if !booster_delegation.is_accepted {
+                    return None;
+                }
+                let weight = booster_delegation.weight;
+                delegator.sync_booster_policy(booster_tokens);
+                let booster_staking = delegator.booster_stakings.get(booster_token_id)?;
+                Some(u128_ratio(booster_staking.x_booster_amount, weight as u128, MAX_RATIO as u128))
+            })
+            .unwrap_or(0)
+    }
+
+    /// Computes xBooster amounts that the account receives from its delegators.
+    pub fn internal_sync_received_x_booster_amounts(
+        &self,
+        account: &mut Account,
+        booster_tokens: &HashMap<TokenId, BoosterTokenInfo>,
+    ) {
+        account.received_x_booster_amounts = account
+            .booster_delegators
+            .iter()
+            .map(|(booster_token_id, delegators)| {
+                let x_booster_amount = delegators
+                    .iter()
+                    .map(|delegator_id| {
+                        self.internal_get_delegated_x_booster_amount(
+                            delegator_id,
+                            &account.account_id,
+                            booster_token_id,
+                            booster_tokens,
+                        )
+                    })
+                    .sum();
+                (booster_token_id.clone(), x_booster_amount)
+            })
+            .collect();
+    }
+
+    fn internal_booster_delegation_into_view(
+        &self,
+        delegator_id: &AccountId,
+        delegate_id: &AccountId,
+        booster_token_id: &TokenId,
+        booster_delegation: &BoosterDelegation,
+        booster_tokens: &HashMap<TokenId, BoosterTokenInfo>,
+    ) -> BoosterDelegationView {
+        BoosterDelegationView {
+            booster_token_id: booster_token_id.clone(),
+            delegator_id: delegator_id.clone(),
+            delegate_id: delegate_id.clone(),
+            weight: booster_delegation.weight,
+            x_booster_amount: self.internal_get_delegated_x_booster_amount(
+                delegator_id,
+                delegate_id,
+                booster_token_id,
+                booster_tokens,
+            ),
+            revocable_timestamp: booster_delegation.revocable_timestamp,
+            is_accepted: booster_delegation.is_accepted,
+        }
+    }
+
+    /// Removes the delegation from both accounts and refreshes their farms.
+    fn internal_remove_booster_delegation(
+        &mut self,
+        mut delegator: Account,
+        mut delegate: Account,
+        booster_token_id: &TokenId,
+    ) {
+        let delegations = delegator
+            .booster_delegations
+            .get_mut(booster_token_id)
+            .expect("Booster delegation not exist");
+        delegations
+            .remove(&delegate.account_id)
+            .expect("Booster delegation not exist");
+        if delegations.is_empty() {
+            delegator.booster_delegations.remove(booster_token_id);
+        }
+        if let Some(delegators) = delegate.booster_delegators.get_mut(booster_token_id) {
+            delegators.remove(&delegator.account_id);
+            if delegators.is_empty() {
+                delegate.booster_delegators.remove(booster_token_id);
+            }
+        }
+        self.internal_account_refresh_farms(delegator);
+        self.internal_account_refresh_farms(delegate);
+    }
+
+    /// Applies all potential farms of the account, so its boosted shares take the
+    /// current xBooster into account, and saves the account.
+    fn internal_account_refresh_farms(&mut self, mut account: Account) {
+        account
+            .affected_farms
+            .extend(account.get_all_potential_farms());
+        self.internal_account_apply_affected_farms(&mut account);
+        let account_id = account.account_id.clone();
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Refreshes farms of the delegates that accepted xBooster of the booster token from the delegator,
+    /// after the xBooster of the delegator changed. The delegator has to be saved first.
+    /// Delegates are saved without the storage check, so they can't block the delegator.
+    pub(crate) fn internal_refresh_booster_delegates(&mut self, delegator_id: &AccountId, booster_token_id: &TokenId) {
+        let delegate_ids: Vec<AccountId> = self
+            .internal_get_account(delegator_id, false)
+            .and_then(|delegator| delegator.booster_delegations.get(booster_token_id).cloned())
+            .map(|delegations| {
+                delegations
+                    .into_iter()
+                    .filter(|(_, booster_delegation)| booster_delegation.is_accepted)
+                    .map(|(delegate_id, _)| delegate_id)
+                    .collect()
+            })
+            .unwrap_or_default();
+        for delegate_id in delegate_ids {
+            if let Some(mut delegate) = self.internal_get_account(&delegate_id, false) {
+                delegate
+                    .affected_farms
+                    .extend(delegate.get_all_potential_farms());
+                self.internal_account_apply_affected_farms(&mut delegate);
+                self.internal_force_set_account(&delegate_id, delegate);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Delegates shares of xBooster of the booster token to the given accounts.
+    /// Weights are added to the existing delegations. The total weight can't exceed 100%.
+    /// Each updated delegation can be revoked only after the delegation cooldown.
+    /// New delegations take effect once the delegate accepts them with `accept_booster_delegation`.
+    /// Farms of the delegates are refreshed when the xBooster of the delegator changes.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn delegate_booster(&mut self, booster_token_id: TokenId, delegations: HashMap<AccountId, u32>) {
+        assert_one_yocto();
+        self.internal_unwrap_booster_token_info(&booster_token_id);
+        let account_id = env::predecessor_account_id();
+        let mut delegator = self.internal_unwrap_account(&account_id);
+        let revocable_timestamp = env::block_timestamp() + to_nano(read_booster_delegation_cooldown_sec_from_storage());
+        let mut delegates = vec![];
+        for (delegate_id, weight) in delegations {
+            require!(weight > 0, "Invalid weight");
+            require!(delegate_id != account_id, "Can't delegate to self");
+            let delegate = self.internal_unwrap_account(&delegate_id);
+            let booster_delegation = delegator
+                .booster_delegations
+                .entry(booster_token_id.clone())
+                .or_default()
+                .entry(delegate_id.clone())
+                .or_insert(BoosterDelegation {
+                    weight: 0,
+                    revocable_timestamp,
+                    is_accepted: false,
+                });
+            booster_delegation.weight += weight;
+            booster_delegation.revocable_timestamp = revocable_timestamp;
+            events::emit::booster_delegated(&account_id, &delegate_id, &booster_token_id, booster_delegation.weight);
+            if booster_delegation.is_accepted {
+                delegates.push(delegate);
+            }
+        }
+        require!(
+            delegator.booster_delegations.get(&booster_token_id).map(|d| d.len()).unwrap_or(0) <= MAX_NUM_BOOSTER_DELEGATES,
+            "Too many booster delegates"
+        );
+        require!(
+            delegator.get_delegated_booster_weight(&booster_token_id) <= MAX_RATIO,
+            "The total weight exceeds 100%"
+        );
+        // The delegator is saved first, so delegates read the updated delegations.
+        self.internal_account_refresh_farms(delegator);
+        for delegate in delegates {
+            self.internal_account_refresh_farms(delegate);
+        }
+    }
+
+    /// Accepts the delegation of xBooster of the booster token from the given account.
+    /// The delegator is stored in the account of the delegate, at the cost of the delegate.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn accept_booster_delegation(&mut self, booster_token_id: TokenId, delegator_id: AccountId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut delegate = self.internal_unwrap_account(&account_id);
+        let mut delegator = self.internal_unwrap_account(&delegator_id);
+        let booster_delegation = delegator
+            .booster_delegations
+            .get_mut(&booster_token_id)
+            .and_then(|delegations| delegations.get_mut(&account_id))
+            .expect("Booster delegation not exist");
+        require!(!booster_delegation.is_accepted, "Booster delegation is already accepted");
+        booster_delegation.is_accepted = true;
+        let delegators = delegate.booster_delegators.entry(booster_token_id.clone()).or_default();
+        delegators.insert(delegator_id.clone());
+        require!(delegators.len() <= MAX_NUM_BOOSTER_DELEGATORS, "Too many booster delegators");
+        events::emit::booster_delegation_accepted(&delegator_id, &account_id, &booster_token_id);
+        // The delegator is saved first, so the delegate reads the accepted delegation.
+        self.internal_account_refresh_farms(delegator);
+        self.internal_account_refresh_farms(delegate);
+    }
+
+    /// Revokes the delegation of xBooster of the booster token to the given account.
+    /// - Requires one yoctoNEAR.
+    /// - Requires the delegation cooldown to be passed.
+    #[payable]
+    pub fn revoke_booster_delegation(&mut self, booster_token_id: TokenId, delegate_id: AccountId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let delegator = self.internal_unwrap_account(&account_id);
+        let booster_delegation = delegator
+            .booster_delegations
+            .get(&booster_token_id)
+            .and_then(|delegations| delegations.get(&delegate_id))
+            .expect("Booster delegation not exist");
+        require!(
+            booster_delegation.revocable_timestamp <= env::block_timestamp(),
+            "Booster delegation is in cooldown"
+        );
+        let delegate = self.internal_unwrap_account(&delegate_id);
+        self.internal_remove_booster_delegation(delegator, delegate, &booster_token_id);
+        events::emit::booster_delegation_revoked(&account_id, &delegate_id, &booster_token_id);
+    }
+
+    /// Declines the delegation of xBooster of the booster token from the given account,
+    /// whether it's accepted or not. Can be done at any time by the delegate.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn decline_booster_delegation(&mut self, booster_token_id: TokenId, delegator_id: AccountId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let delegate = self.internal_unwrap_account(&account_id);
+        let delegator = self.internal_unwrap_account(&delegator_id);
+        self.internal_remove_booster_delegation(delegator, delegate, &booster_token_id);
+        events::emit::booster_delegation_revoked(&delegator_id, &account_id, &booster_token_id);
+    }
+
+    /// Sets the cooldown in seconds after which a new delegation can be revoked.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_booster_delegation_cooldown_sec(&mut self, cooldown_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_owner();
+        write_booster_delegation_cooldown_sec_to_storage(cooldown_sec);
+        events::emit::settings_updated("set_booster_delegation_cooldown_sec", &env::predecessor_account_id(), json!({"cooldown_sec": cooldown_sec}));
+    }
+
+    pub fn get_booster_delegation_cooldown_sec(&self) -> DurationSec {
+        read_booster_delegation_cooldown_sec_from_storage()
+    }
+
+    /// Returns xBooster delegations from and to the given account.
+    pub fn get_account_booster_delegations(&self, account_id: AccountId) -> Option<AccountBoosterDelegationsView> {
+        let booster_tokens = read_booster_tokens_from_storage();
+        self.internal_get_account(&account_id, true).map(|account| {
+            let delegated = account
+                .booster_delegations
+                .iter()
+                .flat_map(|(booster_token_id, delegations)| {
+                    delegations.iter().map(|(delegate_id, booster_delegation)| {
+                        self.internal_booster_delegation_into_view(
+                            &account_id,
+                            delegate_id,
+                            booster_token_id,
+                            booster_delegation,
+                            &booster_tokens,
+                        )
+                    }).collect::<Vec<_>>()
+                })
+                .collect();
+            let received = account
+                .booster_delegators
+                .iter()
+                .flat_map(|(booster_token_id, delegators)| {
+                    delegators.iter().filter_map(|delegator_id| {
+                        let delegator = self.internal_get_account(delegator_id, true)?;
+                        let booster_delegation = delegator
+                            .booster_delegations
+                            .get(booster_token_id)?
+                            .get(&account_id)?;
+                        Some(self.internal_booster_delegation_into_view(
+                            delegator_id,
+                            &account_id,
+                            booster_token_id,
+                            booster_delegation,
+                            &booster_tokens,
+                        ))
+                    }).collect::<Vec<_>>()
+                })
+                .collect();
+            AccountBoosterDelegationsView { delegated, received }
+        })
+    }
+}
@@ -0,0 +1,266 @@
+use crate::*;
+
+/// Where the penalty of an early unstake of booster token goes.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum BoosterPenaltySink {
+    /// The penalty is added to the reserve of the booster token.
+    Reserve,
+    /// The penalty stays locked in the contract and is excluded from the asset balances.
+    /// The burned amount of each booster token is tracked, see `get_booster_burned_penalties`.
+    Burn,
+    /// The penalty is shared by the remaining stakers in proportion to their staked amount.
+    /// Falls back to the reserve if there are no remaining stakers.
+    Stakers,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct BoosterEarlyUnstakeConfig {
+    /// The penalty rate of the staked amount when the remaining lock duration is
+    /// the maximum staking duration. It decreases linearly to 0 at the unlock timestamp.
+    /// E.g. 5000 means 50%.
+    pub max_penalty_rate: u32,
+    pub penalty_sink: BoosterPenaltySink,
+}
+
+impl BoosterEarlyUnstakeConfig {
+    pub fn assert_valid(&self) {
+        assert!(self.max_penalty_rate <= MAX_RATIO, "Invalid max_penalty_rate");
+    }
+
+    pub fn compute_penalty(
+        &self,
+        booster_token_info: &BoosterTokenInfo,
+        booster_staking: &BoosterStaking,
+        timestamp: Timestamp,
+    ) -> Balance {
+        let maximum_staking_duration_ns = to_nano(booster_token_info.maximum_staking_duration_sec);
+        let remain_duration_ns = std::cmp::min(
+            booster_staking.unlock_timestamp.saturating_sub(timestamp),
+            maximum_staking_duration_ns,
+        );
+        u128_ratio(
+            booster_staking.staked_booster_amount,
+            self.max_penalty_rate as u128 * remain_duration_ns as u128,
+            MAX_RATIO as u128 * maximum_staking_duration_ns as u128,
+        )
+    }
+}
+
+pub fn read_booster_early_unstake_configs_from_storage() -> HashMap<TokenId, BoosterEarlyUnstakeConfig> {
+    if let Some(content) = env::storage_read(BOOSTER_EARLY_UNSTAKE_CONFIGS_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize booster early unstake configs failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_booster_early_unstake_configs_to_storage(data: HashMap<TokenId, BoosterEarlyUnstakeConfig>) {
+    env::storage_write(BOOSTER_EARLY_UNSTAKE_CONFIGS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+/// The accumulated early unstake penalty per one staked booster token, shared by stakers.
+pub fn read_booster_penalty_per_stakes_from_storage() -> HashMap<TokenId, BigDecimal> {
+    if let Some(content) = env::storage_read(BOOSTER_PENALTY_PER_STAKES_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize booster penalty per stakes failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_booster_penalty_per_stakes_to_storage(data: HashMap<TokenId, BigDecimal>) {
+    env::storage_write(BOOSTER_PENALTY_PER_STAKES_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+/// The total early unstake penalties burned per booster token, in inner decimals.
+pub fn read_booster_burned_penalties_from_storage() -> HashMap<TokenId, U128> {
+    if let Some(content) = env::storage_read(BOOSTER_BURNED_PENALTIES_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize booster burned penalties failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_booster_burned_penalties_to_storage(data: HashMap<TokenId, U128>) {
+    env::storage_write(BOOSTER_BURNED_PENALTIES_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+impl Contract {
+    /// Deposits the shared early unstake penalties earned by the booster staking of the account
+    /// into its supplied balance. Should be called before the staked amount changes.
+    pub fn internal_settle_booster_penalty_rewards(&mut self, account: &mut Account, booster_token_id: &TokenId) {
+        let penalty_per_stake = read_booster_penalty_per_stakes_from_storage()
+            .remove(booster_token_id)
+            .unwrap_or_default();
+        let last_penalty_per_stake = account
+            .last_booster_penalty_per_stakes
+            .insert(booster_token_id.clone(), penalty_per_stake)
+            .unwrap_or_default();
+        let staked_booster_amount = account
+            .booster_stakings
+            .get(booster_token_id)
+            .map(|booster_staking| booster_staking.staked_booster_amount)
+            .unwrap_or(0);
+        let amount = (penalty_per_stake - last_penalty_per_stake).round_mul_u128(staked_booster_amount);
+        if amount > 0 {
+            self.internal_deposit(account, booster_token_id, amount);
+            events::emit::booster_penalty_reward(
+                &account.account_id,
+                amount,
+                booster_token_id,
+                self.internal_unwrap_asset(booster_token_id).config.extra_decimals,
+            );
+        }
+    }
+
+    fn internal_distribute_booster_penalty(
+        &mut self,
+        penalty_sink: &BoosterPenaltySink,
+        booster_token_info: &BoosterTokenInfo,
+        amount: Balance,
+    ) {
+        let booster_token_id = &booster_token_info.booster_token_id;
+        match penalty_sink {
+            BoosterPenaltySink::Burn => {
+                let mut burned_penalties = read_booster_burned_penalties_from_storage();
+                let burned_penalty = burned_penalties.entry(booster_token_id.clone()).or_insert(U128(0));
+                burned_penalty.0 += amount;
+                write_booster_burned_penalties_to_storage(burned_penalties);
+                events::emit::booster_penalty_burned(booster_token_id, amount);
+            }
+            BoosterPenaltySink::Stakers if booster_token_info.total_stake_amount > 0 => {
+                let mut penalty_per_stakes = read_booster_penalty_per_stakes_from_storage();
+                let penalty_per_stake = penalty_per_stakes.entry(booster_token_id.clone()).or_default();
+                *penalty_per_stake = *penalty_per_stake
+                    + BigDecimal::from(amount) / BigDecimal::from(booster_token_info.total_stake_amount);
+                write_booster_penalty_per_stakes_to_storage(penalty_per_stakes);
+            }
+            _ => {
+                let mut asset = self.internal_unwrap_asset(booster_token_id);
+                asset.reserved += amount;
+                self.internal_set_asset(booster_token_id, asset);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the early unstake config of the booster token. `None` disables early unstake.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_booster_early_unstake_config(
+        &mut self,
+        booster_token_id: TokenId,
+        early_unstake_config: Option<BoosterEarlyUnstakeConfig>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_unwrap_booster_token_info(&booster_token_id);
+        let mut early_unstake_configs = read_booster_early_unstake_configs_from_storage();
+        events::emit::settings_updated("set_booster_early_unstake_config", &env::predecessor_account_id(), json!({"booster_token_id": booster_token_id, "early_unstake_config": early_unstake_config}));
+        if let Some(early_unstake_config) = early_unstake_config {
+            early_unstake_config.assert_valid();
+            early_unstake_configs.insert(booster_token_id, early_unstake_config);
+        } else {
+            early_unstake_configs.remove(&booster_token_id);
+        }
+        write_booster_early_unstake_configs_to_storage(early_unstake_configs);
+    }
+
+    pub fn get_booster_early_unstake_configs(&self) -> HashMap<TokenId, BoosterEarlyUnstakeConfig> {
+        read_booster_early_unstake_configs_from_storage()
+    }
+
+    /// Returns the early unstake penalties burned per booster token, in inner decimals.
+    /// These tokens are held by the contract, but aren't part of any asset balance.
+    pub fn get_booster_burned_penalties(&self) -> HashMap<TokenId, U128> {
+        read_booster_burned_penalties_from_storage()
+    }
+
+    /// Returns the penalty the account would pay to unstake the booster token now.
+    pub fn get_booster_early_unstake_penalty(&self, account_id: AccountId, booster_token_id: TokenId) -> U128 {
+        let early_unstake_config = read_booster_early_unstake_configs_from_storage()
+            .remove(&booster_token_id)
+            .expect("Early unstake is not enabled");
+        let booster_token_info = self.internal_unwrap_booster_token_info(&booster_token_id);
+        self.internal_get_account(&account_id, true)
+            .and_then(|account| {
+                account.booster_stakings.get(&booster_token_id).map(|booster_staking| {
+                    early_unstake_config.compute_penalty(&booster_token_info, booster_staking, env::block_timestamp())
+                })
+            })
+            .unwrap_or(0)
+            .into()
+    }
+
+    /// Unstakes all booster token before the unlock timestamp.
+    /// The penalty decreases linearly with the time left on the lock
+    /// and goes to the penalty sink of the early unstake config.
+    /// Like a normal unstake, farms of the delegates of the account are refreshed without its xBooster.
+    /// The gauge vote of the current epoch is removed, since it was cast with the staking.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn account_early_unstake_booster(&mut self, booster_token_id: AccountId) {
+        assert_one_yocto();
+        assert_not_paused(&read_pause_registry_from_storage(), PauseKind::BoosterStaking, &[booster_token_id.to_string()]);
+        let early_unstake_config = read_booster_early_unstake_configs_from_storage()
+            .remove(&booster_token_id)
+            .expect("Early unstake is not enabled");
+        let mut booster_token_info = self.internal_unwrap_booster_token_info(&booster_token_id);
+        require!(booster_token_info.enable, "Disabled booster token ID");
+
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        self.internal_settle_booster_penalty_rewards(&mut account, &booster_token_id);
+        if read_gauge_config_from_storage().map_or(false, |gauge_config| gauge_config.booster_token_id == booster_token_id) {
+            self.internal_remove_gauge_vote(&mut account);
+        }
+
+        let timestamp = env::block_timestamp();
+        let booster_staking = account
+            .booster_stakings
+            .remove(&booster_token_id)
+            .expect("No staked booster token");
+        require!(
+            booster_staking.unlock_timestamp > timestamp,
+            "The staking is unlocked, use account_unstake_booster"
+        );
+        let penalty = early_unstake_config.compute_penalty(&booster_token_info, &booster_staking, timestamp);
+        let unstake_amount = booster_staking.staked_booster_amount - penalty;
+
+        booster_token_info.total_stake_amount -= booster_staking.staked_booster_amount;
+        if penalty > 0 {
+            self.internal_distribute_booster_penalty(&early_unstake_config.penalty_sink, &booster_token_info, penalty);
+        }
+        self.internal_set_booster_token_info(&booster_token_id, booster_token_info);
+
+        if unstake_amount > 0 {
+            self.internal_deposit(&mut account, &booster_token_id, unstake_amount);
+        }
+        account
+            .affected_farms
+            .extend(account.get_all_potential_farms());
+        self.internal_account_apply_affected_farms(&mut account);
+
+        events::emit::booster_early_unstake(&account_id, &booster_token_id, unstake_amount, penalty, &booster_staking);
+        self.internal_set_account(&account_id, account);
+        self.internal_refresh_booster_delegates(&account_id, &booster_token_id);
+    }
+
+    /// Claims the shared early unstake penalties earned by the booster staking
+    /// of the predecessor into its supplied balance.
+    pub fn account_claim_booster_penalty_rewards(&mut self, booster_token_id: AccountId) {
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        self.internal_settle_booster_penalty_rewards(&mut account, &booster_token_id);
+        account.add_affected_farm(FarmId::Supplied(booster_token_id.clone()));
+        account.add_affected_farm(FarmId::TokenNetBalance(booster_token_id));
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+    }
+}
@@ -28,6 +28,7 @@ impl Contract {
         duration: DurationSec,
     ) {
         assert_one_yocto();
+        assert_not_paused(&read_pause_registry_from_storage(), PauseKind::BoosterStaking, &[booster_token_id.to_string()]);
         let booster_tokens = read_booster_tokens_from_storage();
         let mut booster_token_info = booster_tokens
             .get(&booster_token_id)
@@ -48,6 +49,7 @@ impl Contract {
             "Blacklisted account"
         );
         let mut account = self.internal_unwrap_account(&account_id);
+        self.internal_settle_booster_penalty_rewards(&mut account, &booster_token_id);
 
         // Computing and withdrawing amount from supplied.
         let mut asset = self.internal_unwrap_asset(&booster_token_id);
@@ -117,17 +119,19 @@ impl Contract {
 
         account
             .booster_stakings
-            .insert(booster_token_id, booster_staking);
+            .insert(booster_token_id.clone(), booster_staking);
 
         account
             .affected_farms
             .extend(account.get_all_potential_farms());
         self.internal_account_apply_affected_farms(&mut account);
         self.internal_set_account(&account_id, account);
+        self.internal_refresh_booster_delegates(&account_id, &booster_token_id);
     }
 
     /// Unstakes all booster token.
     /// The current timestamp must be greater than the unlock_timestamp.
+    /// Farms of the delegates of the account are refreshed without its xBooster.
     #[payable]
     pub fn account_unstake_booster(
         &mut self,
@@ -142,6 +146,7 @@ impl Contract {
 
         let timestamp = env::block_timestamp();
         let (booster_token_id, booster_staking) = if let Some(booster_token_id) = booster_token_id {
+            self.internal_settle_booster_penalty_rewards(&mut account, &booster_token_id);
             (
                 booster_token_id.clone(),
                 account
@@ -158,6 +163,7 @@ impl Contract {
                     .expect("No staked booster token"),
             )
         };
+        assert_not_paused(&read_pause_registry_from_storage(), PauseKind::BoosterStaking, &[booster_token_id.to_string()]);
         let unstake_amount = booster_staking.staked_booster_amount;
 
         if let Some(mut booster_token_info) =
@@ -185,9 +191,9 @@ impl Contract {
                     ft_amount,
                     client_echo,
                 );
-                events::emit::withdraw_started(&account_id, unstake_amount, &booster_token_id);
+                events::emit::withdraw_started(&account_id, unstake_amount, &booster_token_id, asset.config.extra_decimals);
             } else {
-                events::emit::withdraw_succeeded(&account_id, unstake_amount, &booster_token_id);
+                events::emit::withdraw_succeeded(&account_id, unstake_amount, &booster_token_id, asset.config.extra_decimals);
             }
         } else {
             self.internal_deposit(&mut account, &booster_token_id, unstake_amount);
@@ -199,6 +205,7 @@ impl Contract {
 
         events::emit::booster_unstake(&account_id, &booster_token_id, &booster_staking);
         self.internal_set_account(&account_id, account);
+        self.internal_refresh_booster_delegates(&account_id, &booster_token_id);
     }
 }
 
@@ -220,3 +227,23 @@ pub fn compute_x_booster_amount(
             )) * u128::from(MIN_BOOSTER_MULTIPLIER),
         )
 }
+
+/// Returns the voting power of the booster staking, which is the staked amount
+/// decaying linearly from the maximum staking duration to zero at the unlock timestamp.
+/// The staked amount is used rather than xBooster, which already rewards the staking duration.
+pub fn compute_voting_power(
+    booster_token_info: &BoosterTokenInfo,
+    booster_staking: &BoosterStaking,
+    timestamp: Timestamp,
+) -> u128 {
+    if booster_staking.unlock_timestamp <= timestamp {
+        return 0;
+    }
+    let maximum_staking_duration_ns = to_nano(booster_token_info.maximum_staking_duration_sec);
+    let remain_duration_ns = std::cmp::min(booster_staking.unlock_timestamp - timestamp, maximum_staking_duration_ns);
+    u128_ratio(
+        booster_staking.staked_booster_amount,
+        remain_duration_ns as u128,
+        maximum_staking_duration_ns as u128,
+    )
+}
@@ -113,7 +113,7 @@ impl Contract {
         boost_suppress_factor: U128,
     ) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::FarmManager);
         let booster_tokens = read_booster_tokens_from_storage();
         require!(
             !booster_tokens.contains_key(&booster_token_id),
@@ -128,6 +128,7 @@ impl Contract {
             boost_suppress_factor.0, 
         );
         booster_token_info.assert_valid();
+        events::emit::settings_updated("add_booster_token_info", &env::predecessor_account_id(), json!({"booster_token_info": booster_token_info}));
         self.internal_set_booster_token_info(&booster_token_id, booster_token_info);
     }
 
@@ -141,7 +142,7 @@ impl Contract {
         enable: Option<bool>,
     ) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::FarmManager);
         let mut booster_token_info = self.internal_unwrap_booster_token_info(&booster_token_id);
         if let Some((minimum_staking_duration_sec, maximum_staking_duration_sec)) = min_max_staking_duration {
             booster_token_info.minimum_staking_duration_sec = minimum_staking_duration_sec;
@@ -157,6 +158,7 @@ impl Contract {
             booster_token_info.enable = enable;
         }
         booster_token_info.assert_valid();
+        events::emit::settings_updated("update_booster_token_info", &env::predecessor_account_id(), json!({"booster_token_info": booster_token_info}));
         self.internal_set_booster_token_info(&booster_token_id, booster_token_info);
     }
 
@@ -166,11 +168,12 @@ impl Contract {
         booster_token_id: TokenId,
     ) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::FarmManager);
         let mut booster_tokens = read_booster_tokens_from_storage();
         let booster_token_info = booster_tokens.remove(&booster_token_id).expect("Invalid booster_token_id");
         require!(booster_token_info.total_stake_amount == 0, "Already has staking");
         write_booster_tokens_to_storage(booster_tokens);
+        events::emit::settings_updated("remove_booster_token_info", &env::predecessor_account_id(), json!({"booster_token_id": booster_token_id}));
     }
 
     pub fn get_booster_tokens(&self) -> HashMap<TokenId, BoosterTokenInfo> {
@@ -5,13 +5,14 @@ impl Contract {
     #[payable]
     pub fn append_client_echo_sender_whitelist(&mut self, sender_list: Vec<String>) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::MarginDexManager);
         let mut sender_whitelist: UnorderedSet<String> =
             if env::storage_has_key(CLIENT_ECHO_SENDER_WHITELIST.as_bytes()) {
                 internal_get_client_echo_sender_whitelist()
             } else {
                 UnorderedSet::new(CLIENT_ECHO_SENDER_WHITELIST.as_bytes())
             };
+        events::emit::settings_updated("append_client_echo_sender_whitelist", &env::predecessor_account_id(), json!({"sender_list": sender_list}));
         for sender in sender_list {
             let is_success = sender_whitelist.insert(&sender);
             require!(is_success, format!("exist sender: {}", sender));
@@ -25,8 +26,9 @@ impl Contract {
     #[payable]
     pub fn remove_client_echo_sender_whitelist(&mut self, sender_list: Vec<String>) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::MarginDexManager);
         let mut sender_whitelist = internal_get_client_echo_sender_whitelist();
+        events::emit::settings_updated("remove_client_echo_sender_whitelist", &env::predecessor_account_id(), json!({"sender_list": sender_list}));
         for sender in sender_list {
             let is_success = sender_whitelist.remove(&sender);
             require!(is_success, format!("sender {} not exist", sender));
@@ -94,16 +94,16 @@ impl Contract {
         self.internal_config().oracle_account_id.into()
     }
 
+    pub fn is_owner_predecessor(&self) -> bool {
+        env::predecessor_account_id() == self.internal_config().owner_id
+    }
+
     pub fn assert_owner(&self) {
-        assert_eq!(
-            &env::predecessor_account_id(),
-            &self.internal_config().owner_id,
-            "Not an owner"
-        );
+        assert!(self.is_owner_predecessor(), "Not an owner");
     }
 
     pub fn assert_owner_or_guardians(&self) {
-        assert!(env::predecessor_account_id() == self.internal_config().owner_id 
+        assert!(self.is_owner_predecessor()
             || self.guardians.contains(&env::predecessor_account_id()), "Not allowed");
     }
 }
@@ -115,21 +115,23 @@ impl Contract {
         self.internal_config()
     }
 
+    /// Proposes the next owner, who becomes the owner after calling `accept_owner_id`.
+    /// Replaces the previously proposed owner, if any.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
     #[payable]
     pub fn set_owner_id(&mut self, owner_id: AccountId) {
         assert_one_yocto();
-        self.assert_owner();
-        // The owner must be a registered account.
-        self.internal_unwrap_account(&owner_id);
-        let mut config = self.internal_config();
-        config.owner_id = owner_id;
-        self.config.set(&config);
+        self.assert_admin(AdminCaller::Owner, "set_owner_id");
+        require!(owner_id != self.internal_config().owner_id, "Already the owner");
+        env::storage_write(PENDING_OWNER_ID_KEY.as_bytes(), &owner_id.try_to_vec().unwrap());
+        events::emit::owner_transfer_proposed(&self.internal_config().owner_id, &owner_id);
     }
 
     #[payable]
     pub fn update_price_oracle_config(&mut self, oracle_account_id: Option<AccountId>, maximum_recency_duration_sec: Option<DurationSec>, maximum_staleness_duration_sec: Option<DurationSec>) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::OracleAdmin, "update_price_oracle_config");
         let mut config = self.internal_config();
         if let Some(oracle_account_id) = oracle_account_id {
             config.oracle_account_id = oracle_account_id;
@@ -141,12 +143,13 @@ impl Contract {
             config.maximum_staleness_duration_sec = maximum_staleness_duration_sec;
         }
         self.config.set(&config);
+        events::emit::settings_updated("update_price_oracle_config", &env::predecessor_account_id(), json!({"config": config}));
     }
 
     #[payable]
     pub fn update_pyth_oracle_config(&mut self, pyth_oracle_account_id: Option<AccountId>, pyth_price_valid_duration_sec: Option<DurationSec>) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::OracleAdmin, "update_pyth_oracle_config");
         let mut config = self.internal_config();
         if let Some(pyth_oracle_account_id) = pyth_oracle_account_id {
             config.pyth_oracle_account_id = pyth_oracle_account_id;
@@ -155,12 +158,13 @@ impl Contract {
             config.pyth_price_valid_duration_sec = pyth_price_valid_duration_sec;
         }
         self.config.set(&config);
+        events::emit::settings_updated("update_pyth_oracle_config", &env::predecessor_account_id(), json!({"config": config}));
     }
 
     #[payable]
     pub fn update_lp_config(&mut self, ref_exchange_id: Option<AccountId>, lp_tokens_info_valid_duration_sec: Option<DurationSec>) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::OracleAdmin, "update_lp_config");
         let mut config = self.internal_config();
         if let Some(ref_exchange_id) = ref_exchange_id {
             config.ref_exchange_id = ref_exchange_id;
@@ -169,38 +173,42 @@ impl Contract {
             config.lp_tokens_info_valid_duration_sec = lp_tokens_info_valid_duration_sec;
         }
         self.config.set(&config);
+        events::emit::settings_updated("update_lp_config", &env::predecessor_account_id(), json!({"config": config}));
     }
 
     #[payable]
     pub fn update_max_num_assets(&mut self, max_num_assets: u32) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "update_max_num_assets");
         let mut config = self.internal_config();
         config.max_num_assets = max_num_assets;
         config.assert_valid();
         self.config.set(&config);
+        events::emit::settings_updated("update_max_num_assets", &env::predecessor_account_id(), json!({"config": config}));
     }
 
     #[payable]
     pub fn update_force_closing_enabled(&mut self, force_closing_enabled: bool) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "update_force_closing_enabled");
         let mut config = self.internal_config();
         config.force_closing_enabled = force_closing_enabled;
         self.config.set(&config);
+        events::emit::settings_updated("update_force_closing_enabled", &env::predecessor_account_id(), json!({"config": config}));
     }
 
     /// Adds an asset with a given token_id and a given asset_config.
     /// - Panics if the asset config is invalid.
     /// - Panics if an asset with the given token_id already exists.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a RiskAdmin.
     #[payable]
     pub fn add_asset(&mut self, token_id: AccountId, asset_config: AssetConfig) {
         assert_one_yocto();
         asset_config.assert_valid();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "add_asset");
         assert!(self.asset_ids.insert(&token_id));
+        events::emit::settings_updated("add_asset", &env::predecessor_account_id(), json!({"token_id": token_id, "asset_config": asset_config}));
         self.internal_set_asset(&token_id, Asset::new(env::block_timestamp(), asset_config))
     }
 
@@ -208,12 +216,12 @@ impl Contract {
     /// - Panics if the asset config is invalid.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a RiskAdmin.
     #[payable]
     pub fn update_asset(&mut self, token_id: AccountId, asset_config: AssetConfig) {
         assert_one_yocto();
         asset_config.assert_valid();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "update_asset");
         let mut asset = self.internal_unwrap_asset(&token_id);
         if asset.config.extra_decimals != asset_config.extra_decimals {
             assert!(
@@ -224,18 +232,30 @@ impl Contract {
             );
         }
         asset.config = asset_config;
+        events::emit::settings_updated("update_asset", &env::predecessor_account_id(), json!({"token_id": token_id, "asset_config": asset.config}));
         self.internal_set_asset(&token_id, asset);
     }
 
     /// Updates the limit for the asset with the a given token_id.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a RiskAdmin.
     #[payable]
     pub fn update_asset_limit(&mut self, token_id: AccountId, supplied_limit: Option<U128>, borrowed_limit: Option<U128>, min_borrowed_amount: Option<U128>) {
         assert_one_yocto();
-        self.assert_owner();
         let mut asset = self.internal_unwrap_asset(&token_id);
+        let is_raising = |limit: Option<U128>, current_limit: Option<U128>| {
+            limit.map_or(false, |limit| current_limit.map_or(true, |current_limit| limit.0 > current_limit.0))
+        };
+        self.assert_admin_change(
+            Role::RiskAdmin,
+            "update_asset_limit",
+            is_raising(supplied_limit, asset.config.supplied_limit)
+                || is_raising(borrowed_limit, asset.config.borrowed_limit)
+                || min_borrowed_amount.map_or(false, |amount| {
+                    amount.0 < asset.config.min_borrowed_amount.map_or(0, |current_amount| current_amount.0)
+                }),
+        );
         if supplied_limit.is_some() {
             asset.config.supplied_limit = supplied_limit;
         }
@@ -246,34 +266,42 @@ impl Contract {
             asset.config.min_borrowed_amount = min_borrowed_amount;
         }
         asset.config.assert_valid();
+        events::emit::settings_updated("update_asset_limit", &env::predecessor_account_id(), json!({"token_id": token_id, "asset_config": asset.config}));
         self.internal_set_asset(&token_id, asset);
     }
 
     /// Updates the holding_position_fee_rate for the asset with the a given token_id.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a RiskAdmin.
     #[payable]
     pub fn update_asset_holding_position_fee_rate(&mut self, token_id: AccountId, holding_position_fee_rate: LowU128) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "update_asset_holding_position_fee_rate");
         assert!(holding_position_fee_rate.0 >= BIG_DIVISOR, "Invalid holding_position_fee_rate");
         let mut asset = self.internal_unwrap_asset(&token_id);
         asset.config.holding_position_fee_rate = holding_position_fee_rate;
+        events::emit::settings_updated("update_asset_holding_position_fee_rate", &env::predecessor_account_id(), json!({"token_id": token_id, "asset_config": asset.config}));
         self.internal_set_asset(&token_id, asset);
     }
     
     /// Updates the max_change_rate for the asset with the a given token_id.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a RiskAdmin.
     #[payable]
     pub fn update_asset_max_change_rate(&mut self, token_id: AccountId, max_change_rate: Option<u32>) {
         assert_one_yocto();
-        self.assert_owner();
-        assert!(max_change_rate.is_none() || max_change_rate.unwrap() <= MAX_RATIO);
         let mut asset = self.internal_unwrap_asset(&token_id);
+        let is_loosening = match (max_change_rate, asset.config.max_change_rate) {
+            (Some(max_change_rate), Some(current_max_change_rate)) => max_change_rate > current_max_change_rate,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        self.assert_admin_change(Role::RiskAdmin, "update_asset_max_change_rate", is_loosening);
+        assert!(max_change_rate.is_none() || max_change_rate.unwrap() <= MAX_RATIO);
         asset.config.max_change_rate = max_change_rate;
+        events::emit::settings_updated("update_asset_max_change_rate", &env::predecessor_account_id(), json!({"token_id": token_id, "asset_config": asset.config}));
         self.internal_set_asset(&token_id, asset);
     }
 
@@ -282,11 +310,11 @@ impl Contract {
     /// - Panics if the beneficiaries is invalid after upsert.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner or guardians.
+    /// - Requires to be called by the contract owner, a BeneficiaryManager or guardians.
     #[payable]
     pub fn upsert_beneficiary(&mut self, token_id: AccountId, account_id: AccountId, bps: u32) {
         assert_one_yocto();
-        self.assert_owner_or_guardians();
+        self.assert_admin(AdminCaller::RoleOrGuardians(Role::BeneficiaryManager), "upsert_beneficiary");
         let mut asset = self.internal_unwrap_asset(&token_id);
         let old_bps = asset.config.beneficiaries.insert(account_id.clone(), bps);
         asset.config.assert_valid();
@@ -297,12 +325,12 @@ impl Contract {
     /// Remove a beneficiary from a given asset.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner or guardians.
+    /// - Requires to be called by the contract owner, a BeneficiaryManager or guardians.
     /// Note: removing a beneficiary doesn't affect his currently collected fee.
     #[payable]
     pub fn remove_beneficiary(&mut self, token_id: AccountId, account_id: AccountId) {
         assert_one_yocto();
-        self.assert_owner_or_guardians();
+        self.assert_role_or_guardians(Role::BeneficiaryManager);
         let mut asset = self.internal_unwrap_asset(&token_id);
         let bps = asset.config.beneficiaries.remove(&account_id).expect(format!("{} not exist", account_id).as_str());
         self.internal_set_asset(&token_id, asset);
@@ -311,39 +339,41 @@ impl Contract {
 
     /// Enable or disable oracle
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a OracleAdmin.
     #[payable]
     pub fn enable_oracle(&mut self, enable_price_oracle: bool, enable_pyth_oracle: bool) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::OracleAdmin, "enable_oracle");
         assert!(enable_price_oracle == !enable_pyth_oracle, "Only one oracle can be started at a time");
         let mut config = self.internal_config();
         config.enable_price_oracle = enable_price_oracle;
         config.enable_pyth_oracle = enable_pyth_oracle;
         self.config.set(&config);
+        events::emit::settings_updated("enable_oracle", &env::predecessor_account_id(), json!({"config": config}));
     }
 
     /// Update dcl contract id
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a OracleAdmin.
     #[payable]
     pub fn update_dcl_id(&mut self, dcl_id: AccountId) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::OracleAdmin, "update_dcl_id");
         let mut config = self.internal_config();
         config.dcl_id = Some(dcl_id);
         self.config.set(&config);
+        events::emit::settings_updated("update_dcl_id", &env::predecessor_account_id(), json!({"config": config}));
     }
 
     /// Enable the capacity for the asset with the a given token_id.
     /// - Panics if the capacity is invalid.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a RiskAdmin.
     #[payable]
     pub fn enable_asset_capacity(&mut self, token_id: AccountId, can_deposit: Option<bool>, can_withdraw: Option<bool>, can_use_as_collateral: Option<bool>, can_borrow: Option<bool>) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "enable_asset_capacity");
         let mut asset = self.internal_unwrap_asset(&token_id);
         if let Some(can_deposit) = can_deposit {
             assert!(can_deposit, "Invalid can_deposit!");
@@ -361,6 +391,7 @@ impl Contract {
             assert!(can_borrow, "Invalid can_borrow!");
             asset.config.can_borrow = can_borrow;
         }
+        events::emit::settings_updated("enable_asset_capacity", &env::predecessor_account_id(), json!({"token_id": token_id, "asset_config": asset.config}));
         self.internal_set_asset(&token_id, asset);
     }
 
@@ -368,11 +399,11 @@ impl Contract {
     /// - Panics if the capacity is invalid.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner or guardians.
+    /// - Requires to be called by the contract owner, a PauseGuardian or guardians.
     #[payable]
     pub fn disable_asset_capacity(&mut self, token_id: AccountId, can_deposit: Option<bool>, can_withdraw: Option<bool>, can_use_as_collateral: Option<bool>, can_borrow: Option<bool>) {
         assert_one_yocto();
-        self.assert_owner_or_guardians();
+        self.assert_role_or_guardians(Role::PauseGuardian);
         let mut asset = self.internal_unwrap_asset(&token_id);
         if let Some(can_deposit) = can_deposit {
             assert!(!can_deposit, "Invalid can_deposit!");
@@ -390,6 +421,7 @@ impl Contract {
             assert!(!can_borrow, "Invalid can_borrow!");
             asset.config.can_borrow = can_borrow;
         }
+        events::emit::settings_updated("disable_asset_capacity", &env::predecessor_account_id(), json!({"token_id": token_id, "asset_config": asset.config}));
         self.internal_set_asset(&token_id, asset);
     }
 
@@ -397,14 +429,15 @@ impl Contract {
     /// - Panics if the net_tvl_multiplier is invalid.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a RiskAdmin.
     #[payable]
     pub fn update_asset_net_tvl_multiplier(&mut self, token_id: AccountId, net_tvl_multiplier: u32) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "update_asset_net_tvl_multiplier");
         assert!(net_tvl_multiplier <= MAX_RATIO);
         let mut asset = self.internal_unwrap_asset(&token_id);
         asset.config.net_tvl_multiplier = net_tvl_multiplier;
+        events::emit::settings_updated("update_asset_net_tvl_multiplier", &env::predecessor_account_id(), json!({"token_id": token_id, "asset_config": asset.config}));
         self.internal_set_asset(&token_id, asset);
     }
 
@@ -413,11 +446,13 @@ impl Contract {
     /// taken from the asset reserved balance.
     /// - The booster log base should include decimals of the token for better precision of the log
     ///    base. For example, if token decimals is `6` the log base of `10_500_000` will be `10.5`.
+    /// - Keeps the start and end timestamps of a running or scheduled reward.
     /// - Panics if the farm asset token_id doesn't exists.
     /// - Panics if an asset with the given token_id doesn't exists.
     /// - Panics if an asset with the given token_id doesn't have enough reserved balance.
+    /// - Panics if the reward is managed by the gauge.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a FarmManager.
     #[payable]
     pub fn add_asset_farm_reward(
         &mut self,
@@ -428,68 +463,66 @@ impl Contract {
         reward_amount: U128,
     ) {
         assert_one_yocto();
-        self.assert_owner();
-        let check_new_booster_log_bases_valid = new_booster_log_bases.iter().all(|(k, v)| 
-            v.0 > 0 || self.internal_unwrap_booster_token_info(k).enable
-        );
-        require!(check_new_booster_log_bases_valid, "Invalid new_booster_log_bases");
-        match &farm_id {
-            FarmId::Supplied(token_id) | FarmId::Borrowed(token_id) | FarmId::TokenNetBalance(token_id) => {
-                assert!(self.assets.contains_key(token_id));
-            }
-            FarmId::NetTvl => {}
-        };
-        let reward_token_id: TokenId = reward_token_id.into();
-        let mut reward_asset = self.internal_unwrap_asset(&reward_token_id);
-        assert!(
-            reward_asset.reserved >= reward_amount.0
-                && reward_asset.available_amount() >= reward_amount.0,
-            "Not enough reserved reward balance"
-        );
-        reward_asset.reserved -= reward_amount.0;
-        self.internal_set_asset(&reward_token_id, reward_asset);
-        let mut asset_farm = self
-            .internal_get_asset_farm(&farm_id, false)
-            .unwrap_or_else(|| AssetFarm {
-                block_timestamp: env::block_timestamp(),
-                rewards: HashMap::new(),
-                inactive_rewards: LookupMap::new(StorageKey::InactiveAssetFarmRewards {
-                    farm_id: farm_id.clone(),
-                }),
-            });
+        self.assert_role(Role::FarmManager);
+        self.assert_not_gauge_reward(&farm_id, &reward_token_id);
+        events::emit::settings_updated("add_asset_farm_reward", &env::predecessor_account_id(), json!({
+            "farm_id": farm_id,
+            "reward_token_id": reward_token_id,
+            "new_reward_per_day": new_reward_per_day,
+            "new_booster_log_bases": new_booster_log_bases,
+            "reward_amount": reward_amount,
+        }));
+        self.internal_add_asset_farm_reward(farm_id, reward_token_id, new_reward_per_day, new_booster_log_bases, reward_amount, None);
+    }
 
-        let mut asset_farm_reward = asset_farm
-            .rewards
-            .remove(&reward_token_id)
-            .or_else(|| asset_farm.internal_remove_inactive_asset_farm_reward(&reward_token_id))
-            .unwrap_or_default();
-        asset_farm_reward.reward_per_day = new_reward_per_day.into();
-        asset_farm_reward.booster_log_bases = new_booster_log_bases;
-        asset_farm_reward.remaining_rewards += reward_amount.0;
-        asset_farm
-            .rewards
-            .insert(reward_token_id, asset_farm_reward);
-        self.internal_set_asset_farm(&farm_id, asset_farm);
+    /// Schedules an asset farm reward for the farm with a given farm_id, which distributes
+    /// rewards only between start_timestamp and end_timestamp (if given) of the schedule.
+    /// The other parameters of the schedule are the same as in `add_asset_farm_reward`.
+    /// - Panics if start_timestamp is not in the future or end_timestamp is not after start_timestamp.
+    /// - Panics if the reward of the same token_id is running in the farm.
+    ///   Rewards that aren't distributed by the end_timestamp are returned to the reserve.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a FarmManager.
+    #[payable]
+    pub fn schedule_asset_farm_reward(
+        &mut self,
+        farm_id: FarmId,
+        reward_token_id: AccountId,
+        schedule: AssetFarmRewardSchedule,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::FarmManager);
+        self.assert_not_gauge_reward(&farm_id, &reward_token_id);
+        let AssetFarmRewardSchedule { new_reward_per_day, new_booster_log_bases, reward_amount, start_timestamp, end_timestamp } = schedule.clone();
+        require!(start_timestamp.0 > env::block_timestamp(), "Invalid start_timestamp");
+        require!(end_timestamp.map_or(true, |end| end.0 > start_timestamp.0), "Invalid end_timestamp");
+        events::emit::settings_updated("schedule_asset_farm_reward", &env::predecessor_account_id(), json!({
+            "farm_id": farm_id,
+            "reward_token_id": reward_token_id,
+            "schedule": schedule,
+        }));
+        self.internal_add_asset_farm_reward(farm_id, reward_token_id, new_reward_per_day, new_booster_log_bases, reward_amount, Some((start_timestamp, end_timestamp)));
     }
 
     /// Claim prot_fee from asset with the a given token_id.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner or guardians.
+    /// - Requires to be called by the contract owner, a TreasuryManager or guardians.
     #[payable]
     pub fn claim_prot_fee(&mut self, token_id: AccountId, stdd_amount: Option<U128>) {
         assert_one_yocto();
-        self.assert_owner_or_guardians();
+        self.assert_role_or_guardians(Role::TreasuryManager);
         let mut asset = self.internal_unwrap_asset(&token_id);
         let stdd_amount: u128 = stdd_amount.map(|v| v.into()).unwrap_or(asset.prot_fee);
         
         if stdd_amount > 0 {
             asset.prot_fee = asset.prot_fee.checked_sub(stdd_amount).expect("Asset prot_fee balance not enough!");
+            let extra_decimals = asset.config.extra_decimals;
             self.internal_set_asset(&token_id, asset);
 
             self.deposit_to_owner(&token_id, stdd_amount);
 
-            events::emit::claim_prot_fee(&self.internal_config().owner_id, stdd_amount, &token_id);
+            events::emit::claim_prot_fee(&self.internal_config().owner_id, stdd_amount, &token_id, extra_decimals);
         }
     }
 
@@ -501,12 +534,13 @@ impl Contract {
         let mut asset = self.internal_unwrap_asset(&token_id);
 
         let stdd_amount = asset.beneficiary_fees.get(&beneficiary).unwrap_or(&U128(0)).0;
-        let ft_amount = stdd_amount / 10u128.pow(asset.config.extra_decimals as u32);
+        let extra_decimals = asset.config.extra_decimals;
+        let ft_amount = stdd_amount / 10u128.pow(extra_decimals as u32);
         
         if ft_amount > 0 {
             asset.beneficiary_fees.remove(&beneficiary);
             self.internal_set_asset(&token_id, asset);
-            events::emit::withdraw_beneficiary_fee_started(&beneficiary, stdd_amount, &token_id);
+            events::emit::withdraw_beneficiary_fee_started(&beneficiary, stdd_amount, &token_id, extra_decimals);
             self.internal_beneficiary_withdraw(&beneficiary, &token_id, stdd_amount, ft_amount).into()
         } else {
             PromiseOrValue::Value(false)
@@ -516,38 +550,40 @@ impl Contract {
     /// Decrease reserved from asset with the a given token_id.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner or guardians.
+    /// - Requires to be called by the contract owner, a TreasuryManager or guardians.
     #[payable]
     pub fn decrease_reserved(&mut self, token_id: AccountId, stdd_amount: Option<U128>) {
         assert_one_yocto();
-        self.assert_owner_or_guardians();
+        self.assert_role_or_guardians(Role::TreasuryManager);
         let mut asset = self.internal_unwrap_asset(&token_id);
         let stdd_amount: u128 = stdd_amount.map(|v| v.into()).unwrap_or(asset.reserved);
         
         if stdd_amount > 0 {
             asset.reserved = asset.reserved.checked_sub(stdd_amount).expect("Asset reserved balance not enough!");
+            let extra_decimals = asset.config.extra_decimals;
             self.internal_set_asset(&token_id, asset);
 
             self.deposit_to_owner(&token_id, stdd_amount);
 
-            if self.guardians.contains(&env::predecessor_account_id()) {
+            // Only the owner can decrease the reserve below the reserve ratio.
+            if !self.is_owner_predecessor() {
                 let asset = self.internal_unwrap_asset(&token_id);
                 let reserve_ratio = BigDecimal::from(asset.reserved).div_u128(asset.supplied.balance + asset.reserved);
                 let config_reserve_ratio = BigDecimal::from_ratio(asset.config.reserve_ratio);
                 assert!(reserve_ratio >= config_reserve_ratio);
             }
-            events::emit::decrease_reserved(&self.internal_config().owner_id, stdd_amount, &token_id);
+            events::emit::decrease_reserved(&self.internal_config().owner_id, stdd_amount, &token_id, extra_decimals);
         }
     }
 
     /// Increase reserved from asset with the a given token_id.
     /// - Panics if an asset with the given token_id doesn't exist.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner or guardians.
+    /// - Requires to be called by the contract owner, a TreasuryManager or guardians.
     #[payable]
     pub fn increase_reserved(&mut self, asset_amount: AssetAmount) {
         assert_one_yocto();
-        self.assert_owner_or_guardians();
+        self.assert_role_or_guardians(Role::TreasuryManager);
         let owner_id = self.internal_config().owner_id;
         let mut account = self.internal_unwrap_account(&owner_id);
         let mut account_asset = account.internal_unwrap_asset(&asset_amount.token_id);
@@ -561,21 +597,22 @@ impl Contract {
 
         asset.supplied.withdraw(shares, increase_amount);
         asset.reserved += increase_amount;
+        let extra_decimals = asset.config.extra_decimals;
         self.internal_set_asset(&asset_amount.token_id, asset);
         
         self.internal_account_apply_affected_farms(&mut account);
         self.internal_set_account(&owner_id, account);
 
-        events::emit::increase_reserved(&owner_id, increase_amount, &asset_amount.token_id);
+        events::emit::increase_reserved(&owner_id, increase_amount, &asset_amount.token_id, extra_decimals);
     }
 
     /// Return the lostfound shares to the margin account.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a TreasuryManager.
     #[payable]
     pub fn return_margin_account_lostfound_supply_shares(&mut self, account_id: AccountId, token_id: AccountId, shares: U128) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::TreasuryManager);
         let mut asset = self.internal_unwrap_asset(&token_id);
         assert!(shares.0 <= asset.lostfound_shares, "Invalid shares");
         asset.lostfound_shares -= shares.0;
@@ -583,10 +620,77 @@ impl Contract {
         margin_account.deposit_supply_shares(&token_id, &shares);
         self.internal_set_margin_account(&account_id, margin_account);
         self.internal_set_asset(&token_id, asset);
+        events::emit::settings_updated("return_margin_account_lostfound_supply_shares", &env::predecessor_account_id(), json!({"margin_account_id": account_id, "token_id": token_id, "shares": shares}));
     }
 }
 
 impl Contract {
+    pub fn internal_add_asset_farm_reward(
+        &mut self,
+        farm_id: FarmId,
+        reward_token_id: AccountId,
+        new_reward_per_day: U128,
+        new_booster_log_bases: HashMap<TokenId, U128>,
+        reward_amount: U128,
+        schedule: Option<(U64, Option<U64>)>,
+    ) {
+        let check_new_booster_log_bases_valid = new_booster_log_bases.iter().all(|(k, v)| 
+            v.0 > 0 || self.internal_unwrap_booster_token_info(k).enable
+        );
+        require!(check_new_booster_log_bases_valid, "Invalid new_booster_log_bases");
+        match &farm_id {
+            FarmId::Supplied(token_id) | FarmId::Borrowed(token_id) | FarmId::TokenNetBalance(token_id)
+            | FarmId::MarginDebt(token_id) | FarmId::MarginPosition(token_id) => {
+                assert!(self.assets.contains_key(token_id));
+            }
+            FarmId::NetTvl => {}
+        };
+        let reward_token_id: TokenId = reward_token_id.into();
+        let mut reward_asset = self.internal_unwrap_asset(&reward_token_id);
+        assert!(
+            reward_asset.reserved >= reward_amount.0
+                && reward_asset.available_amount() >= reward_amount.0,
+            "Not enough reserved reward balance"
+        );
+        reward_asset.reserved -= reward_amount.0;
+        self.internal_set_asset(&reward_token_id, reward_asset);
+        let mut asset_farm = self
+            .internal_get_asset_farm(&farm_id, false)
+            .unwrap_or_else(|| AssetFarm {
+                block_timestamp: env::block_timestamp(),
+                rewards: HashMap::new(),
+                inactive_rewards: LookupMap::new(StorageKey::InactiveAssetFarmRewards {
+                    farm_id: farm_id.clone(),
+                }),
+                ended_rewards: vec![],
+            });
+
+        let (mut asset_farm_reward, is_running) = if let Some(asset_farm_reward) = asset_farm.rewards.remove(&reward_token_id) {
+            let is_running = !asset_farm_reward.is_scheduled(env::block_timestamp());
+            (asset_farm_reward, is_running)
+        } else {
+            // A finished reward starts over without the previous schedule.
+            let mut asset_farm_reward = asset_farm
+                .internal_remove_inactive_asset_farm_reward(&reward_token_id)
+                .unwrap_or_default();
+            asset_farm_reward.start_timestamp = None;
+            asset_farm_reward.end_timestamp = None;
+            (asset_farm_reward, false)
+        };
+        if let Some((start_timestamp, end_timestamp)) = schedule {
+            require!(!is_running, "Reward is running");
+            asset_farm_reward.start_timestamp = Some(start_timestamp);
+            asset_farm_reward.end_timestamp = end_timestamp;
+        }
+        asset_farm_reward.reward_per_day = new_reward_per_day.into();
+        asset_farm_reward.booster_log_bases = new_booster_log_bases;
+        asset_farm_reward.remaining_rewards += reward_amount.0;
+        asset_farm
+            .rewards
+            .insert(reward_token_id, asset_farm_reward);
+        self.internal_set_asset_farm(&farm_id, asset_farm);
+    }
+
     pub fn deposit_to_owner(&mut self, token_id: &AccountId, stdd_amount: u128) {
         let owner_id = self.internal_config().owner_id;
         let mut account = self.internal_unwrap_account(&owner_id);
@@ -2,7 +2,10 @@ use crate::*;
 
 pub mod emit {
     use super::*;
-    use near_sdk::serde_json::json;
+    use near_sdk::serde_json::{self, json};
+
+    /// The version of the event payloads. It changes whenever a payload changes.
+    pub const EVENT_VERSION: &str = "1.1.0";
 
     #[derive(Serialize)]
     #[serde(crate = "near_sdk::serde")]
@@ -10,6 +13,9 @@ pub mod emit {
         pub account_id: &'a AccountId,
         #[serde(with = "u128_dec_format")]
         pub amount: Balance,
+        /// The amount in the decimals of the fungible token, if they differ from the inner ones.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub ft_amount: Option<U128>,
         pub token_id: &'a TokenId,
     }
 
@@ -19,6 +25,9 @@ pub mod emit {
         pub account_id: &'a AccountId,
         #[serde(with = "u128_dec_format")]
         pub amount: Balance,
+        /// The amount in the decimals of the fungible token, if they differ from the inner ones.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub ft_amount: Option<U128>,
         pub token_id: &'a TokenId,
         pub position: &'a String,
     }
@@ -26,7 +35,7 @@ pub mod emit {
     fn log_event<T: Serialize>(event: &str, data: T) {
         let event = json!({
             "standard": "burrow",
-            "version": "1.0.0",
+            "version": EVENT_VERSION,
             "event": event,
             "data": [data]
         });
@@ -34,114 +43,134 @@ pub mod emit {
         log!("EVENT_JSON:{}", event.to_string());
     }
 
-    pub fn deposit_to_reserve(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    /// Converts the inner amount into the decimals of the fungible token,
+    /// given the extra decimals of the asset. Returns None if there are none.
+    fn ft_amount(extra_decimals: u8, amount: Balance) -> Option<U128> {
+        if extra_decimals > 0 {
+            Some(U128(amount / 10u128.pow(extra_decimals as u32)))
+        } else {
+            None
+        }
+    }
+
+    pub fn deposit_to_reserve(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "deposit_to_reserve",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn deposit(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn deposit(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "deposit",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn margin_deposit(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn margin_deposit(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "margin_deposit",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn withdraw_started(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn withdraw_started(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "withdraw_started",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn withdraw_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn withdraw_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "withdraw_failed",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn withdraw_succeeded(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn withdraw_succeeded(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "withdraw_succeeded",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn increase_collateral(account_id: &AccountId, amount: Balance, token_id: &TokenId, position: &String) {
+    pub fn increase_collateral(account_id: &AccountId, amount: Balance, token_id: &TokenId, position: &String, extra_decimals: u8) {
         log_event(
             "increase_collateral",
             AccountAmountTokenPosition {
                 account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id,
                 position
             }
         );
     }
 
-    pub fn decrease_collateral(account_id: &AccountId, amount: Balance, token_id: &TokenId, position: &String) {
+    pub fn decrease_collateral(account_id: &AccountId, amount: Balance, token_id: &TokenId, position: &String, extra_decimals: u8) {
         log_event(
             "decrease_collateral",
             AccountAmountTokenPosition {
                 account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id,
                 position
             }
         );
     }
 
-    pub fn borrow(account_id: &AccountId, amount: Balance, token_id: &TokenId, position: &String) {
+    pub fn borrow(account_id: &AccountId, amount: Balance, token_id: &TokenId, position: &String, extra_decimals: u8) {
         log_event(
             "borrow",
             AccountAmountTokenPosition {
                 account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id,
                 position
             }
         );
     }
 
-    pub fn repay(account_id: &AccountId, amount: Balance, token_id: &TokenId, position: &String) {
+    pub fn repay(account_id: &AccountId, amount: Balance, token_id: &TokenId, position: &String, extra_decimals: u8) {
         log_event(
             "repay",
             AccountAmountTokenPosition {
                 account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id,
                 position
             }
@@ -243,67 +272,73 @@ pub mod emit {
         );
     }
 
-    pub fn claim_prot_fee(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn claim_prot_fee(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "claim_prot_fee",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn increase_reserved(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn increase_reserved(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "increase_reserved",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn decrease_reserved(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn decrease_reserved(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "decrease_reserved",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn margin_asset_withdraw_started(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn margin_asset_withdraw_started(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "withdraw_started_margin_asset",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn margin_asset_withdraw_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn margin_asset_withdraw_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "withdraw_failed_margin_asset",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn margin_asset_withdraw_succeeded(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn margin_asset_withdraw_succeeded(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "withdraw_succeeded_margin_asset",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
@@ -369,6 +404,61 @@ pub mod emit {
         );
     }
 
+    #[derive(Serialize)]
+    #[serde(crate = "near_sdk::serde")]
+    pub struct EventDataMarginIncrease {
+        pub account_id: AccountId,
+        pub pos_id: String,
+        pub token_d_id: TokenId,
+        #[serde(with = "u128_dec_format")]
+        pub token_d_amount: Balance,
+        pub token_p_id: TokenId,
+        #[serde(with = "u128_dec_format")]
+        pub token_p_amount: Balance,
+    }
+
+    pub fn margin_increase_started(data: EventDataMarginIncrease) {
+        log_event(
+            "margin_increase_started",
+            data,
+        );
+    }
+
+    pub fn margin_increase_failed(account_id: &AccountId, pos_id: &String) {
+        log_event(
+            "margin_increase_failed",
+            json!({
+                "account_id": account_id,
+                "pos_id": pos_id,
+            }),
+        );
+    }
+
+    #[derive(Serialize)]
+    #[serde(crate = "near_sdk::serde")]
+    pub struct EventDataMarginIncreaseResult {
+        pub account_id: AccountId,
+        pub pos_id: String,
+        pub token_d_id: TokenId,
+        #[serde(with = "u128_dec_format")]
+        pub token_d_amount: Balance,
+        pub token_d_shares: U128,
+        pub token_p_id: TokenId,
+        #[serde(with = "u128_dec_format")]
+        pub token_p_amount: Balance,
+        #[serde(with = "u128_dec_format")]
+        pub debt_cap: Balance,
+        #[serde(with = "u128_dec_format")]
+        pub uahpi_at_open: Balance,
+    }
+
+    pub fn margin_increase_succeeded(data: EventDataMarginIncreaseResult) {
+        log_event(
+            "margin_increase_succeeded",
+            data,
+        );
+    }
+
     #[derive(Serialize)]
     #[serde(crate = "near_sdk::serde")]
     pub struct EventDataMarginDecrease {
@@ -400,6 +490,17 @@ pub mod emit {
         );
     }
 
+    pub fn margin_swap_legs_failed(account_id: &AccountId, pos_id: &String, amount_in: Balance) {
+        log_event(
+            "margin_swap_legs_failed",
+            json!({
+                "account_id": account_id,
+                "pos_id": pos_id,
+                "amount_in": U128(amount_in),
+            }),
+        );
+    }
+
     #[derive(Serialize)]
     #[serde(crate = "near_sdk::serde")]
     pub struct EventDataMarginDecreaseResult {
@@ -431,6 +532,8 @@ pub mod emit {
             "margin_stop_loss_succeeded"
         } else if op_id == "stop_profit" {
             "margin_stop_profit_succeeded"
+        } else if op_id == "repay_and_claim" {
+            "margin_repay_and_claim_succeeded"
         } else {
             op_id
         };
@@ -475,32 +578,35 @@ pub mod emit {
         );
     }
 
-    pub fn new_protocol_debts(token_id: &AccountId, amount: u128) {
+    pub fn new_protocol_debts(token_id: &AccountId, amount: u128, extra_decimals: u8) {
         log_event(
             "new_protocol_debts",
             json!({
                 "token_id": token_id,
                 "amount": U128(amount),
+                "ft_amount": ft_amount(extra_decimals, amount),
             }),
         );
     }
 
-    pub fn repay_protocol_debts(token_id: &AccountId, amount: u128) {
+    pub fn repay_protocol_debts(token_id: &AccountId, amount: u128, extra_decimals: u8) {
         log_event(
             "repay_protocol_debts",
             json!({
                 "token_id": token_id,
                 "amount": U128(amount),
+                "ft_amount": ft_amount(extra_decimals, amount),
             }),
         );
     }
 
-    pub fn forceclose_protocol_loss(token_id: &AccountId, amount: u128) {
+    pub fn forceclose_protocol_loss(token_id: &AccountId, amount: u128, extra_decimals: u8) {
         log_event(
             "forceclose_protocol_loss",
             json!({
                 "token_id": token_id,
                 "amount": U128(amount),
+                "ft_amount": ft_amount(extra_decimals, amount),
             }),
         );
     }
@@ -570,34 +676,37 @@ pub mod emit {
         );
     }
 
-    pub fn withdraw_beneficiary_fee_started(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn withdraw_beneficiary_fee_started(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "withdraw_beneficiary_fee_started",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn withdraw_beneficiary_fee_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn withdraw_beneficiary_fee_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "withdraw_beneficiary_fee_failed",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn withdraw_beneficiary_fee_succeeded(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn withdraw_beneficiary_fee_succeeded(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
         log_event(
             "withdraw_beneficiary_fee_succeeded",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
+                ft_amount: ft_amount(extra_decimals, amount),
                 token_id: &token_id,
             },
         );
@@ -619,4 +728,635 @@ pub mod emit {
             }),
         );
     }
+
+    pub fn margin_position_transfer_proposed(account_id: &AccountId, receiver_id: &AccountId, pos_id: &String) {
+        log_event(
+            "margin_position_transfer_proposed",
+            json!({
+                "account_id": account_id,
+                "receiver_id": receiver_id,
+                "pos_id": pos_id,
+            }),
+        );
+    }
+
+    pub fn margin_position_transfer_cancelled(account_id: &AccountId, receiver_id: &AccountId, pos_id: &String) {
+        log_event(
+            "margin_position_transfer_cancelled",
+            json!({
+                "account_id": account_id,
+                "receiver_id": receiver_id,
+                "pos_id": pos_id,
+            }),
+        );
+    }
+
+    pub fn margin_position_transferred(account_id: &AccountId, receiver_id: &AccountId, pos_id: &String) {
+        log_event(
+            "margin_position_transferred",
+            json!({
+                "account_id": account_id,
+                "receiver_id": receiver_id,
+                "pos_id": pos_id,
+            }),
+        );
+    }
+
+    pub fn reward_vested(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
+        log_event(
+            "reward_vested",
+            AccountAmountToken {
+                account_id,
+                amount,
+                ft_amount: ft_amount(extra_decimals, amount),
+                token_id,
+            },
+        );
+    }
+
+    pub fn vested_reward_claimed(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
+        log_event(
+            "vested_reward_claimed",
+            AccountAmountToken {
+                account_id,
+                amount,
+                ft_amount: ft_amount(extra_decimals, amount),
+                token_id,
+            },
+        );
+    }
+
+    pub fn reward_vesting_exited(account_id: &AccountId, amount: Balance, penalty: Balance, token_id: &TokenId) {
+        log_event(
+            "reward_vesting_exited",
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+                "penalty": U128(penalty),
+                "token_id": token_id,
+            }),
+        );
+    }
+
+    pub fn booster_delegated(account_id: &AccountId, delegate_id: &AccountId, booster_token_id: &TokenId, weight: u32) {
+        log_event(
+            "booster_delegated",
+            json!({
+                "account_id": account_id,
+                "delegate_id": delegate_id,
+                "booster_token_id": booster_token_id,
+                "weight": weight,
+            }),
+        );
+    }
+
+    pub fn booster_delegation_accepted(account_id: &AccountId, delegate_id: &AccountId, booster_token_id: &TokenId) {
+        log_event(
+            "booster_delegation_accepted",
+            json!({
+                "account_id": account_id,
+                "delegate_id": delegate_id,
+                "booster_token_id": booster_token_id,
+            }),
+        );
+    }
+
+    pub fn booster_delegation_revoked(account_id: &AccountId, delegate_id: &AccountId, booster_token_id: &TokenId) {
+        log_event(
+            "booster_delegation_revoked",
+            json!({
+                "account_id": account_id,
+                "delegate_id": delegate_id,
+                "booster_token_id": booster_token_id,
+            }),
+        );
+    }
+
+    pub fn asset_farm_reward_returned(farm_id: &FarmId, reward_token_id: &TokenId, amount: Balance) {
+        log_event(
+            "asset_farm_reward_returned",
+            json!({
+                "farm_id": farm_id,
+                "reward_token_id": reward_token_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn gauge_voted(account_id: &AccountId, epoch: u64, votes: &HashMap<FarmId, Balance>) {
+        log_event(
+            "gauge_voted",
+            json!({
+                "account_id": account_id,
+                "epoch": epoch,
+                "votes": votes.iter().map(|(farm_id, voting_power)| json!({
+                    "farm_id": farm_id,
+                    "voting_power": U128(*voting_power),
+                })).collect::<Vec<_>>(),
+            }),
+        );
+    }
+
+    pub fn gauge_vote_removed(account_id: &AccountId, epoch: u64) {
+        log_event(
+            "gauge_vote_removed",
+            json!({
+                "account_id": account_id,
+                "epoch": epoch,
+            }),
+        );
+    }
+
+    pub fn gauge_epoch_applied(epoch: u64, reward_token_id: &TokenId, rewards: &[(FarmId, Balance)]) {
+        log_event(
+            "gauge_epoch_applied",
+            json!({
+                "epoch": epoch,
+                "reward_token_id": reward_token_id,
+                "rewards": rewards.iter().map(|(farm_id, amount)| json!({
+                    "farm_id": farm_id,
+                    "amount": U128(*amount),
+                })).collect::<Vec<_>>(),
+            }),
+        );
+    }
+
+    pub fn booster_early_unstake(
+        account_id: &AccountId,
+        booster_token_id: &AccountId,
+        amount: Balance,
+        penalty: Balance,
+        booster_staking: &BoosterStaking,
+    ) {
+        log_event(
+            "booster_early_unstake",
+            json!({
+                "account_id": account_id,
+                "booster_token_id": booster_token_id,
+                "amount": U128(amount),
+                "penalty": U128(penalty),
+                "total_booster_amount": U128(booster_staking.staked_booster_amount),
+                "total_x_booster_amount": U128(booster_staking.x_booster_amount),
+            }),
+        );
+    }
+
+    pub fn booster_penalty_burned(booster_token_id: &TokenId, amount: Balance) {
+        log_event(
+            "booster_penalty_burned",
+            json!({
+                "booster_token_id": booster_token_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn booster_penalty_reward(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
+        log_event(
+            "booster_penalty_reward",
+            AccountAmountToken {
+                account_id,
+                amount,
+                ft_amount: ft_amount(extra_decimals, amount),
+                token_id,
+            },
+        );
+    }
+
+    pub fn storage_sponsored(sponsor_id: &AccountId, account_id: &AccountId, amount: Balance) {
+        log_event(
+            "storage_sponsored",
+            json!({
+                "sponsor_id": sponsor_id,
+                "account_id": account_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn storage_sponsor_refund(sponsor_id: &AccountId, account_id: &AccountId, amount: Balance) {
+        log_event(
+            "storage_sponsor_refund",
+            json!({
+                "sponsor_id": sponsor_id,
+                "account_id": account_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn storage_sponsor_pool_deposit(sponsor_id: &AccountId, amount: Balance) {
+        log_event(
+            "storage_sponsor_pool_deposit",
+            json!({
+                "sponsor_id": sponsor_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn storage_sponsor_pool_withdraw(sponsor_id: &AccountId, amount: Balance) {
+        log_event(
+            "storage_sponsor_pool_withdraw",
+            json!({
+                "sponsor_id": sponsor_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn sweep_dust(account_id: &AccountId, amount: Balance, token_id: &TokenId, extra_decimals: u8) {
+        log_event(
+            "sweep_dust",
+            AccountAmountToken {
+                account_id,
+                amount,
+                ft_amount: ft_amount(extra_decimals, amount),
+                token_id,
+            },
+        );
+    }
+
+    pub fn account_closed(account_id: &AccountId) {
+        log_event(
+            "account_closed",
+            json!({
+                "account_id": account_id,
+            }),
+        );
+    }
+
+    pub fn admin_proposal_submitted(proposal: &AdminProposal) {
+        log_event("admin_proposal_submitted", proposal);
+    }
+
+    pub fn admin_proposal_cancelled(id: u32, account_id: &AccountId) {
+        log_event(
+            "admin_proposal_cancelled",
+            json!({
+                "id": id,
+                "account_id": account_id,
+            }),
+        );
+    }
+
+    pub fn admin_proposal_executed(id: u32, success: bool) {
+        log_event(
+            "admin_proposal_executed",
+            json!({
+                "id": id,
+                "success": success,
+            }),
+        );
+    }
+
+    pub fn paused(account_id: &AccountId, pause_key: &PauseKey) {
+        log_event(
+            "paused",
+            json!({
+                "account_id": account_id,
+                "kind": pause_key.kind,
+                "target": pause_key.target,
+            }),
+        );
+    }
+
+    pub fn unpaused(account_id: &AccountId, pause_key: &PauseKey) {
+        log_event(
+            "unpaused",
+            json!({
+                "account_id": account_id,
+                "kind": pause_key.kind,
+                "target": pause_key.target,
+            }),
+        );
+    }
+
+    pub fn role_granted(account_id: &AccountId, role: &Role) {
+        log_event(
+            "role_granted",
+            json!({
+                "account_id": account_id,
+                "role": role,
+            }),
+        );
+    }
+
+    pub fn role_revoked(account_id: &AccountId, role: &Role) {
+        log_event(
+            "role_revoked",
+            json!({
+                "account_id": account_id,
+                "role": role,
+            }),
+        );
+    }
+
+    pub fn owner_transfer_proposed(owner_id: &AccountId, pending_owner_id: &AccountId) {
+        log_event(
+            "owner_transfer_proposed",
+            json!({
+                "owner_id": owner_id,
+                "pending_owner_id": pending_owner_id,
+            }),
+        );
+    }
+
+    pub fn owner_transferred(old_owner_id: &AccountId, new_owner_id: &AccountId) {
+        log_event(
+            "owner_transferred",
+            json!({
+                "old_owner_id": old_owner_id,
+                "new_owner_id": new_owner_id,
+            }),
+        );
+    }
+
+    pub fn socialize_protocol_debt(account_id: &AccountId, resolution: &ProtocolDebtHaircutView) {
+        log_event(
+            "socialize_protocol_debt",
+            json!({
+                "account_id": account_id,
+                "token_id": resolution.token_id,
+                "protocol_debt": U128(resolution.protocol_debt),
+                "reserve_amount": U128(resolution.reserve_amount),
+                "haircut_amount": U128(resolution.haircut_amount),
+                "haircut_rate": resolution.haircut_rate,
+                "remaining_debt": U128(resolution.remaining_debt),
+            }),
+        );
+    }
+
+    pub fn swap_reserve_started(
+        swap_id: u64,
+        account_id: &AccountId,
+        token_in_id: &TokenId,
+        amount_in: Balance,
+        token_out_id: &TokenId,
+        min_amount_out: Balance,
+    ) {
+        log_event(
+            "swap_reserve_started",
+            json!({
+                "swap_id": U64(swap_id),
+                "account_id": account_id,
+                "token_in_id": token_in_id,
+                "amount_in": U128(amount_in),
+                "token_out_id": token_out_id,
+                "min_amount_out": U128(min_amount_out),
+            }),
+        );
+    }
+
+    pub fn swap_reserve_succeeded(
+        account_id: &AccountId,
+        token_in_id: &TokenId,
+        amount_in: Balance,
+        token_out_id: &TokenId,
+        amount_out: Balance,
+        repaid_protocol_debt: Balance,
+    ) {
+        log_event(
+            "swap_reserve_succeeded",
+            json!({
+                "account_id": account_id,
+                "token_in_id": token_in_id,
+                "amount_in": U128(amount_in),
+                "token_out_id": token_out_id,
+                "amount_out": U128(amount_out),
+                "repaid_protocol_debt": U128(repaid_protocol_debt),
+                "reserved": U128(amount_out - repaid_protocol_debt),
+            }),
+        );
+    }
+
+    pub fn pending_swap_settled(swap_id: u64, account_id: &AccountId, op: &str) {
+        log_event(
+            "pending_swap_settled",
+            json!({
+                "swap_id": U64(swap_id),
+                "account_id": account_id,
+                "op": op,
+            }),
+        );
+    }
+
+    pub fn pending_swap_expired(swap_id: u64, account_id: &AccountId, op: &str, token_in_id: &TokenId, amount_in: Balance) {
+        log_event(
+            "pending_swap_expired",
+            json!({
+                "swap_id": U64(swap_id),
+                "account_id": account_id,
+                "op": op,
+                "token_in_id": token_in_id,
+                "amount_in": U128(amount_in),
+            }),
+        );
+    }
+
+    pub fn swap_reserve_refunded(account_id: &AccountId, token_in_id: &TokenId, amount: Balance) {
+        log_event(
+            "swap_reserve_refunded",
+            json!({
+                "account_id": account_id,
+                "token_in_id": token_in_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    /// Emitted by methods that update the contract settings, named after the method.
+    /// The data is a JSON object with the updated settings.
+    pub fn settings_updated(method: &str, account_id: &AccountId, mut data: serde_json::Value) {
+        data["account_id"] = json!(account_id);
+        log_event(method, data);
+    }
+
+    pub fn storage_deposit(account_id: &AccountId, predecessor_id: &AccountId, amount: Balance) {
+        log_event(
+            "storage_deposit",
+            json!({
+                "account_id": account_id,
+                "predecessor_id": predecessor_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn storage_withdraw(account_id: &AccountId, amount: Balance) {
+        log_event(
+            "storage_withdraw",
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn sync_staking_token_price(token_id: &TokenId, price: Option<Balance>) {
+        log_event(
+            "sync_staking_token_price",
+            json!({
+                "token_id": token_id,
+                "price": price.map(U128),
+            }),
+        );
+    }
+
+    pub fn sync_lp_token_infos(lp_token_ids: &[String]) {
+        log_event(
+            "sync_lp_token_infos",
+            json!({
+                "lp_token_ids": lp_token_ids,
+            }),
+        );
+    }
+
+    pub fn accounts_migrated(
+        account_id: &AccountId,
+        from_index: u64,
+        num_accounts: u64,
+        num_margin_accounts: u64,
+        num_assets: u64,
+    ) {
+        log_event(
+            "accounts_migrated",
+            json!({
+                "account_id": account_id,
+                "from_index": from_index,
+                "num_accounts": num_accounts,
+                "num_margin_accounts": num_margin_accounts,
+                "num_assets": num_assets,
+            }),
+        );
+    }
+
+    pub fn distribute_beneficiary_fees(
+        account_id: &AccountId,
+        token_id: &TokenId,
+        payouts: &[(AccountId, Balance, Balance)],
+    ) {
+        let payouts: Vec<_> = payouts
+            .iter()
+            .map(|(beneficiary_id, amount, ft_amount)| {
+                json!({
+                    "beneficiary_id": beneficiary_id,
+                    "amount": U128(*amount),
+                    "ft_amount": U128(*ft_amount),
+                })
+            })
+            .collect();
+        log_event(
+            "distribute_beneficiary_fees",
+            json!({
+                "account_id": account_id,
+                "token_id": token_id,
+                "payouts": payouts,
+            }),
+        );
+    }
+
+    pub fn swap_beneficiary_fee_started(
+        swap_id: u64,
+        account_id: &AccountId,
+        token_in_id: &TokenId,
+        amount_in: Balance,
+        token_out_id: &TokenId,
+        min_amount_out: Balance,
+    ) {
+        log_event(
+            "swap_beneficiary_fee_started",
+            json!({
+                "swap_id": U64(swap_id),
+                "account_id": account_id,
+                "token_in_id": token_in_id,
+                "amount_in": U128(amount_in),
+                "token_out_id": token_out_id,
+                "min_amount_out": U128(min_amount_out),
+            }),
+        );
+    }
+
+    pub fn swap_beneficiary_fee_succeeded(
+        account_id: &AccountId,
+        token_in_id: &TokenId,
+        amount_in: Balance,
+        token_out_id: &TokenId,
+        amount_out: Balance,
+    ) {
+        log_event(
+            "swap_beneficiary_fee_succeeded",
+            json!({
+                "account_id": account_id,
+                "token_in_id": token_in_id,
+                "amount_in": U128(amount_in),
+                "token_out_id": token_out_id,
+                "amount_out": U128(amount_out),
+            }),
+        );
+    }
+
+    pub fn swap_beneficiary_fee_refunded(account_id: &AccountId, token_in_id: &TokenId, amount: Balance) {
+        log_event(
+            "swap_beneficiary_fee_refunded",
+            json!({
+                "account_id": account_id,
+                "token_in_id": token_in_id,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn reserve_contribution(
+        account_id: &AccountId,
+        token_id: &TokenId,
+        purpose: ReserveContributionPurpose,
+        amount: Balance,
+    ) {
+        log_event(
+            "reserve_contribution",
+            json!({
+                "account_id": account_id,
+                "token_id": token_id,
+                "purpose": purpose,
+                "amount": U128(amount),
+            }),
+        );
+    }
+
+    pub fn return_reserve_contributions(
+        account_id: &AccountId,
+        token_id: &TokenId,
+        purpose: ReserveContributionPurpose,
+        returns: &[(AccountId, Balance)],
+    ) {
+        let returns: Vec<_> = returns
+            .iter()
+            .map(|(contributor_id, amount)| {
+                json!({
+                    "contributor_id": contributor_id,
+                    "amount": U128(*amount),
+                })
+            })
+            .collect();
+        log_event(
+            "return_reserve_contributions",
+            json!({
+                "account_id": account_id,
+                "token_id": token_id,
+                "purpose": purpose,
+                "returns": returns,
+            }),
+        );
+    }
+
+    pub fn blocked_operation_rejected(account_id: &AccountId, operation: BlockedOperation) {
+        log_event(
+            "blocked_operation_rejected",
+            json!({
+                "account_id": account_id,
+                "operation": operation,
+            }),
+        );
+    }
 }
@@ -14,6 +14,7 @@ pub enum TokenReceiverMsg {
     Execute { actions: Vec<Action> },
     ExecuteWithPyth { actions: Vec<Action> },
     DepositToReserve,
+    DepositToReserveFor { purpose: ReserveContributionPurpose },
     DepositToMargin,
     MarginExecute { actions: Vec<MarginAction> },
     MarginExecuteWithPyth { actions: Vec<MarginAction> },
@@ -39,54 +40,48 @@ impl FungibleTokenReceiver for Contract {
         self.is_reliable_liquidator_context = in_reliable_liquidator_whitelist(&sender_id.to_string());
 
         let token_id = env::predecessor_account_id();
-        let mut asset = self.internal_unwrap_asset(&token_id);
+        let asset = self.internal_unwrap_asset(&token_id);
         assert!(
             asset.config.can_deposit,
             "Deposits for this asset are not enabled"
         );
 
         let ft_amount = amount.0;
-        let amount = amount.0 * 10u128.pow(asset.config.extra_decimals as u32);
+        let extra_decimals = asset.config.extra_decimals;
+        let amount = amount.0 * 10u128.pow(extra_decimals as u32);
 
         let (actions, with_pyth) = if msg.is_empty() {
+            assert_not_blocked(&sender_id, BlockedOperation::Deposit);
             (vec![], false)
         } else {
             let token_receiver_msg: TokenReceiverMsg =
                 serde_json::from_str(&msg).expect("Can't parse TokenReceiverMsg");
+            if !token_receiver_msg.is_allowed_for_blocked_sender() {
+                assert_not_blocked(&sender_id, BlockedOperation::Deposit);
+            }
             match token_receiver_msg {
                 TokenReceiverMsg::Execute { actions } => (actions, false),
                 TokenReceiverMsg::ExecuteWithPyth { actions } => (actions, true),
                 TokenReceiverMsg::DepositToReserve => {
-                    let mut protocol_debts = read_protocol_debts_from_storage();
-                    let amount_to_reserved = if let Some(debt) = protocol_debts.remove(&token_id) {
-                        let repay_amount = std::cmp::min(amount, debt);
-                        let remain_debt = debt - repay_amount;
-                        if remain_debt > 0 {
-                            protocol_debts.insert(token_id.clone(), remain_debt);
-                        }
-                        write_protocol_debts_to_storage(protocol_debts);
-                        events::emit::repay_protocol_debts(&token_id, repay_amount);
-                        amount - repay_amount
-                    } else {
-                        amount
-                    };
-                    asset.reserved += amount_to_reserved;
-                    self.internal_set_asset(&token_id, asset);
-                    events::emit::deposit_to_reserve(&sender_id, amount, &token_id);
+                    self.internal_deposit_to_reserve(&sender_id, &token_id, amount, ReserveContributionPurpose::General);
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::DepositToReserveFor { purpose } => {
+                    self.internal_deposit_to_reserve(&sender_id, &token_id, amount, purpose);
                     return PromiseOrValue::Value(U128(0));
                 }
                 TokenReceiverMsg::DepositToMargin => {
                     // keep this independent deposit to support price oracle flow
                     let mut account = self.internal_unwrap_margin_account(&sender_id);
                     self.internal_margin_deposit(&mut account, &token_id, amount);
-                    events::emit::margin_deposit(&sender_id, amount, &token_id);
+                    events::emit::margin_deposit(&sender_id, amount, &token_id, extra_decimals);
                     self.internal_set_margin_account(&sender_id, account);
                     return PromiseOrValue::Value(U128(0));
                 }
                 TokenReceiverMsg::MarginExecute { actions } => {
                     let mut account = self.internal_unwrap_margin_account(&sender_id);
                     self.internal_margin_deposit(&mut account, &token_id, amount);
-                    events::emit::margin_deposit(&sender_id, amount, &token_id);
+                    events::emit::margin_deposit(&sender_id, amount, &token_id, extra_decimals);
                     self.internal_margin_execute(&sender_id, &mut account, actions, Prices::new());
                     self.internal_set_margin_account(&sender_id, account);
                     return PromiseOrValue::Value(U128(0));
@@ -94,12 +89,24 @@ impl FungibleTokenReceiver for Contract {
                 TokenReceiverMsg::MarginExecuteWithPyth { actions } => {
                     let mut account = self.internal_unwrap_margin_account(&sender_id);
                     self.internal_margin_deposit(&mut account, &token_id, amount);
-                    events::emit::margin_deposit(&sender_id, amount, &token_id);
+                    events::emit::margin_deposit(&sender_id, amount, &token_id, extra_decimals);
                     self.internal_margin_execute_with_pyth(&sender_id, &mut account, actions);
                     self.internal_set_margin_account(&sender_id, account);
                     return PromiseOrValue::Value(U128(0));
                 }
                 TokenReceiverMsg::SwapReference { swap_ref } => {
+                    if swap_ref.op == RESERVE_SWAP_OP {
+                        self.on_swap_reserve_return(&sender_id, &token_id, amount, &swap_ref);
+                        return PromiseOrValue::Value(U128(0));
+                    }
+                    if swap_ref.op == BENEFICIARY_FEE_SWAP_OP {
+                        self.on_swap_beneficiary_fee_return(&sender_id, &token_id, amount, &swap_ref);
+                        return PromiseOrValue::Value(U128(0));
+                    }
+                    if let Some(leg) = swap_ref.leg {
+                        self.on_split_trade_leg_return(&sender_id, amount, &swap_ref, leg);
+                        return PromiseOrValue::Value(U128(0));
+                    }
                     let config = self.internal_config();
                     let mut account = self.internal_unwrap_margin_account(&swap_ref.account_id);
                     let action_ts = account.position_latest_actions.remove(&swap_ref.pos_id).expect("There is no action for the position").0;
@@ -109,18 +116,7 @@ impl FungibleTokenReceiver for Contract {
                     } else {
                         require!(self.internal_margin_config().registered_dexes.contains_key(&sender_id), "Not allow");
                     }
-                    if swap_ref.op == "open" {
-                        self.on_open_trade_return(account, amount, &swap_ref);
-                    } else if swap_ref.op == "decrease"
-                        || swap_ref.op == "close"
-                        || swap_ref.op == "liquidate"
-                        || swap_ref.op == "forceclose"
-                        || swap_ref.op == "stop_loss"
-                        || swap_ref.op == "stop_profit"
-                    {
-                        let event = self.on_decrease_trade_return(account, amount, &swap_ref);
-                        events::emit::margin_decrease_succeeded(&swap_ref.op, event);
-                    }
+                    self.internal_on_trade_return(account, amount, &swap_ref);
                     return PromiseOrValue::Value(U128(0));
                 }
                 TokenReceiverMsg::OnlyIncreaseCollateral => {
@@ -135,7 +131,7 @@ impl FungibleTokenReceiver for Contract {
                     assert!(in_client_echo_sender_whitelist(sender_id.as_str()), "Unauthorized client echo sender: {}", sender_id);
                     let mut account = self.internal_unwrap_account(&sender_id);
                     let supplied_shares = self.internal_deposit(&mut account, &token_id, amount);
-                    events::emit::deposit(&sender_id, amount, &token_id);
+                    events::emit::deposit(&sender_id, amount, &token_id, extra_decimals);
                     self.internal_account_apply_affected_farms(&mut account);
                     self.internal_set_account(&sender_id, account);
                     Promise::new(sender_id.clone())
@@ -158,9 +154,13 @@ impl FungibleTokenReceiver for Contract {
             }
         };
 
+        assert_not_paused(&read_pause_registry_from_storage(), PauseKind::Execute, &[token_id.to_string()]);
+        // Gasless onboarding: a large enough first deposit of an unregistered account
+        // draws the storage balance from a sponsor pool.
+        self.internal_sponsor_registration(&sender_id, &token_id, amount);
         let mut account = self.internal_unwrap_account(&sender_id);
         self.internal_deposit(&mut account, &token_id, amount);
-        events::emit::deposit(&sender_id, amount, &token_id);
+        events::emit::deposit(&sender_id, amount, &token_id, extra_decimals);
         if with_pyth {
             self.internal_execute_with_pyth(&sender_id, &mut account, actions);
         } else {
@@ -273,14 +273,15 @@ impl ExtSelf for Contract {
         amount: U128,
     ) -> bool {
         let promise_success = is_promise_success();
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let extra_decimals = asset.config.extra_decimals;
         if !promise_success {
-            let mut asset = self.internal_unwrap_asset(&token_id);
             let old_amount = asset.beneficiary_fees.get(&account_id).unwrap_or(&U128(0)).0;
             asset.beneficiary_fees.insert(account_id.clone(), U128(old_amount + amount.0));
             self.internal_set_asset(&token_id, asset);
-            events::emit::withdraw_beneficiary_fee_failed(&account_id, amount.0, &token_id);
+            events::emit::withdraw_beneficiary_fee_failed(&account_id, amount.0, &token_id, extra_decimals);
         } else {
-            events::emit::withdraw_beneficiary_fee_succeeded(&account_id, amount.0, &token_id);
+            events::emit::withdraw_beneficiary_fee_succeeded(&account_id, amount.0, &token_id, extra_decimals);
         }
         promise_success
     }
@@ -293,13 +294,14 @@ impl ExtSelf for Contract {
         amount: U128,
     ) -> bool {
         let promise_success = is_promise_success();
+        let extra_decimals = self.internal_unwrap_asset(&token_id).config.extra_decimals;
         if !promise_success {
             let mut account = self.internal_unwrap_account(&account_id);
             self.internal_deposit_without_asset_basic_check(&mut account, &token_id, amount.0);
-            events::emit::withdraw_failed(&account_id, amount.0, &token_id);
+            events::emit::withdraw_failed(&account_id, amount.0, &token_id, extra_decimals);
             self.internal_force_set_account(&account_id, account);
         } else {
-            events::emit::withdraw_succeeded(&account_id, amount.0, &token_id);
+            events::emit::withdraw_succeeded(&account_id, amount.0, &token_id, extra_decimals);
         }
         promise_success
     }
@@ -319,8 +321,9 @@ impl ExtSelf for Contract {
             }
             None => ft_amount.0,
         };
+        let extra_decimals = self.internal_unwrap_asset(&token_id).config.extra_decimals;
         if remain_ft_amount == 0 {
-            events::emit::withdraw_succeeded(&account_id, amount.0, &token_id);
+            events::emit::withdraw_succeeded(&account_id, amount.0, &token_id, extra_decimals);
             None
         } else {
             let remain_amount = u128_ratio(amount.0, remain_ft_amount, ft_amount.0);
@@ -328,10 +331,10 @@ impl ExtSelf for Contract {
             let redeposit_shares = self.internal_deposit_without_asset_basic_check(&mut account, &token_id, remain_amount);
             self.internal_force_set_account(&account_id, account);
             if remain_ft_amount == ft_amount.0 {
-                events::emit::withdraw_failed(&account_id, amount.0, &token_id);
+                events::emit::withdraw_failed(&account_id, amount.0, &token_id, extra_decimals);
             } else {
-                events::emit::withdraw_failed(&account_id, remain_amount, &token_id);
-                events::emit::withdraw_succeeded(&account_id, amount.0 - remain_amount, &token_id);
+                events::emit::withdraw_failed(&account_id, remain_amount, &token_id, extra_decimals);
+                events::emit::withdraw_succeeded(&account_id, amount.0 - remain_amount, &token_id, extra_decimals);
             }
             Some((U128(remain_amount), redeposit_shares))
         }
@@ -345,13 +348,14 @@ impl ExtSelf for Contract {
         amount: U128,
     ) -> bool {
         let promise_success = is_promise_success();
+        let extra_decimals = self.internal_unwrap_asset(&token_id).config.extra_decimals;
         if !promise_success {
             let mut margin_account = self.internal_unwrap_margin_account(&account_id);
             self.internal_margin_deposit_without_asset_basic_check(&mut margin_account, &token_id, amount.0);
-            events::emit::margin_asset_withdraw_failed(&account_id, amount.0, &token_id);
+            events::emit::margin_asset_withdraw_failed(&account_id, amount.0, &token_id, extra_decimals);
             self.internal_force_set_margin_account(&account_id, margin_account);
         } else {
-            events::emit::margin_asset_withdraw_succeeded(&account_id, amount.0, &token_id);
+            events::emit::margin_asset_withdraw_succeeded(&account_id, amount.0, &token_id, extra_decimals);
         }
         promise_success
     }
@@ -0,0 +1,312 @@
+use crate::*;
+
+/// Configuration of the gauge voting on how the reward budget is split across farms.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct GaugeConfig {
+    /// The booster token, staking of which gives the voting power.
+    pub booster_token_id: TokenId,
+    /// The reward token, which is taken from the reserve.
+    /// The rewards of this token in the gauge farms are managed by the gauge only,
+    /// so they can't be added or scheduled manually.
+    pub reward_token_id: TokenId,
+    /// The amount of the reward token distributed to farms after each epoch.
+    pub reward_amount_per_epoch: U128,
+    /// Booster log bases of the farm rewards created by the gauge.
+    pub booster_log_bases: HashMap<TokenId, U128>,
+    /// The farms that can be voted for.
+    pub farm_ids: Vec<FarmId>,
+    /// The duration of one voting epoch in seconds, e.g. a week.
+    pub epoch_duration_sec: DurationSec,
+    /// When the first epoch starts in nanoseconds.
+    pub start_timestamp: U64,
+}
+
+impl GaugeConfig {
+    pub fn assert_valid(&self) {
+        assert!(self.epoch_duration_sec > 0, "The epoch_duration_sec must be greater than 0");
+        assert!(!self.farm_ids.is_empty(), "The farm_ids can't be empty");
+        assert!(
+            self.farm_ids.iter().collect::<HashSet<_>>().len() == self.farm_ids.len(),
+            "Duplicate farm_ids"
+        );
+    }
+
+    pub fn get_epoch(&self, timestamp: Timestamp) -> u64 {
+        timestamp.saturating_sub(self.start_timestamp.0) / to_nano(self.epoch_duration_sec)
+    }
+
+    /// Whether the reward of the farm is managed by the gauge.
+    pub fn is_gauge_reward(&self, farm_id: &FarmId, reward_token_id: &TokenId) -> bool {
+        &self.reward_token_id == reward_token_id && self.farm_ids.contains(farm_id)
+    }
+}
+
+/// The voting result of the current epoch.
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct GaugeEpoch {
+    pub epoch: u64,
+    pub votes: HashMap<FarmId, Balance>,
+}
+
+/// The vote of an account in the given epoch.
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct GaugeVote {
+    pub epoch: u64,
+    pub votes: HashMap<FarmId, Balance>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct GaugeVoteView {
+    pub farm_id: FarmId,
+    #[serde(with = "u128_dec_format")]
+    pub voting_power: Balance,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct GaugeEpochView {
+    pub epoch: u64,
+    pub votes: Vec<GaugeVoteView>,
+}
+
+impl From<(u64, HashMap<FarmId, Balance>)> for GaugeEpochView {
+    fn from((epoch, votes): (u64, HashMap<FarmId, Balance>)) -> Self {
+        Self {
+            epoch,
+            votes: votes
+                .into_iter()
+                .map(|(farm_id, voting_power)| GaugeVoteView { farm_id, voting_power })
+                .collect(),
+        }
+    }
+}
+
+pub fn read_gauge_config_from_storage() -> Option<GaugeConfig> {
+    env::storage_read(GAUGE_CONFIG_KEY.as_bytes())
+        .map(|content| GaugeConfig::try_from_slice(&content).expect("deserialize gauge config failed."))
+}
+
+pub fn write_gauge_config_to_storage(data: GaugeConfig) {
+    env::storage_write(GAUGE_CONFIG_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+pub fn read_gauge_epoch_from_storage() -> GaugeEpoch {
+    if let Some(content) = env::storage_read(GAUGE_EPOCH_KEY.as_bytes()) {
+        GaugeEpoch::try_from_slice(&content).expect("deserialize gauge epoch failed.")
+    } else {
+        GaugeEpoch::default()
+    }
+}
+
+pub fn write_gauge_epoch_to_storage(data: GaugeEpoch) {
+    env::storage_write(GAUGE_EPOCH_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+impl Contract {
+    pub fn internal_unwrap_gauge_config(&self) -> GaugeConfig {
+        read_gauge_config_from_storage().expect("Gauge is not configured")
+    }
+
+    pub fn internal_get_voting_power(&self, account: &Account, booster_token_id: &TokenId) -> Balance {
+        let booster_tokens = read_booster_tokens_from_storage();
+        booster_tokens
+            .get(booster_token_id)
+            .filter(|booster_token_info| booster_token_info.enable)
+            .and_then(|booster_token_info| {
+                account.booster_stakings.get(booster_token_id).map(|booster_staking| {
+                    compute_voting_power(booster_token_info, booster_staking, env::block_timestamp())
+                })
+            })
+            .unwrap_or(0)
+    }
+
+    /// Panics if the reward of the farm is managed by the gauge.
+    pub fn assert_not_gauge_reward(&self, farm_id: &FarmId, reward_token_id: &TokenId) {
+        require!(
+            !read_gauge_config_from_storage().map_or(false, |gauge_config| gauge_config.is_gauge_reward(farm_id, reward_token_id)),
+            "The reward is managed by the gauge"
+        );
+    }
+
+    /// Removes the vote of the account from the current epoch,
+    /// e.g. when its voting power is gone before the epoch is applied.
+    pub fn internal_remove_gauge_vote(&mut self, account: &mut Account) {
+        if let Some(gauge_vote) = account.gauge_vote.take() {
+            let mut gauge_epoch = read_gauge_epoch_from_storage();
+            if gauge_vote.epoch == gauge_epoch.epoch {
+                for (farm_id, votes) in gauge_vote.votes {
+                    if let Some(total_votes) = gauge_epoch.votes.get_mut(&farm_id) {
+                        *total_votes -= votes;
+                        if *total_votes == 0 {
+                            gauge_epoch.votes.remove(&farm_id);
+                        }
+                    }
+                }
+                write_gauge_epoch_to_storage(gauge_epoch);
+            }
+            events::emit::gauge_vote_removed(&account.account_id, gauge_vote.epoch);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the gauge config. Votes of the current epoch are kept
+    /// only for the farms that remain in the config.
+    /// - Panics if a new gauge farm already has a reward of the reward token, since it'd be replaced by the gauge.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_gauge_config(&mut self, gauge_config: GaugeConfig) {
+        assert_one_yocto();
+        self.assert_owner();
+        gauge_config.assert_valid();
+        self.internal_unwrap_asset(&gauge_config.reward_token_id);
+        self.internal_unwrap_booster_token_info(&gauge_config.booster_token_id);
+        let prev_gauge_config = read_gauge_config_from_storage();
+        for farm_id in gauge_config.farm_ids.iter() {
+            if prev_gauge_config
+                .as_ref()
+                .map_or(false, |prev_gauge_config| prev_gauge_config.is_gauge_reward(farm_id, &gauge_config.reward_token_id))
+            {
+                continue;
+            }
+            require!(
+                self.internal_get_asset_farm(farm_id, true)
+                    .map_or(true, |asset_farm| !asset_farm.rewards.contains_key(&gauge_config.reward_token_id)),
+                "The farm already has a reward of the gauge reward token"
+            );
+        }
+        let mut gauge_epoch = read_gauge_epoch_from_storage();
+        let epoch = gauge_config.get_epoch(env::block_timestamp());
+        if prev_gauge_config.is_none() {
+            gauge_epoch = GaugeEpoch { epoch, votes: HashMap::new() };
+        }
+        gauge_epoch.votes.retain(|farm_id, _| gauge_config.farm_ids.contains(farm_id));
+        events::emit::settings_updated("set_gauge_config", &env::predecessor_account_id(), json!({"gauge_config": gauge_config}));
+        write_gauge_epoch_to_storage(gauge_epoch);
+        write_gauge_config_to_storage(gauge_config);
+    }
+
+    /// Votes with the voting power of the booster staking of the predecessor on how
+    /// the reward budget of the current epoch is split across farms.
+    /// Weights are in basis points and their sum can't exceed 100%.
+    /// Voting again in the same epoch replaces the previous vote.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn vote_gauges(&mut self, weights: Vec<(FarmId, u32)>) {
+        assert_one_yocto();
+        let num_weights = weights.len();
+        let weights: HashMap<FarmId, u32> = weights.into_iter().collect();
+        require!(weights.len() == num_weights, "Duplicate farm_ids");
+        let gauge_config = self.internal_unwrap_gauge_config();
+        let mut gauge_epoch = read_gauge_epoch_from_storage();
+        require!(
+            gauge_epoch.epoch == gauge_config.get_epoch(env::block_timestamp()),
+            "The previous gauge epoch should be applied first"
+        );
+        require!(
+            weights.keys().all(|farm_id| gauge_config.farm_ids.contains(farm_id)),
+            "Invalid farm_id"
+        );
+        require!(
+            weights.values().map(|w| *w as u64).sum::<u64>() <= MAX_RATIO as u64,
+            "The total weight exceeds 100%"
+        );
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let voting_power = self.internal_get_voting_power(&account, &gauge_config.booster_token_id);
+        require!(voting_power > 0, "No voting power");
+
+        if let Some(gauge_vote) = account.gauge_vote.take() {
+            if gauge_vote.epoch == gauge_epoch.epoch {
+                for (farm_id, votes) in gauge_vote.votes {
+                    if let Some(total_votes) = gauge_epoch.votes.get_mut(&farm_id) {
+                        *total_votes -= votes;
+                    }
+                }
+            }
+        }
+        let votes: HashMap<FarmId, Balance> = weights
+            .into_iter()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(farm_id, weight)| (farm_id, u128_ratio(voting_power, weight as u128, MAX_RATIO as u128)))
+            .collect();
+        for (farm_id, votes) in votes.iter() {
+            *gauge_epoch.votes.entry(farm_id.clone()).or_default() += votes;
+        }
+        events::emit::gauge_voted(&account_id, gauge_epoch.epoch, &votes);
+        account.gauge_vote = Some(GaugeVote { epoch: gauge_epoch.epoch, votes });
+        write_gauge_epoch_to_storage(gauge_epoch);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Splits the reward budget of the finished epoch across farms in proportion to
+    /// their votes and adds it as farm rewards for the duration of one epoch.
+    /// These rewards are owned by the gauge, so they don't replace manually added rewards.
+    /// Starts the voting of the current epoch. Can be called by anyone.
+    /// - Requires enough reserved reward token for the budget.
+    pub fn apply_gauge_epoch(&mut self) {
+        let gauge_config = self.internal_unwrap_gauge_config();
+        let gauge_epoch = read_gauge_epoch_from_storage();
+        let epoch = gauge_config.get_epoch(env::block_timestamp());
+        require!(gauge_epoch.epoch < epoch, "The gauge epoch is not finished");
+
+        let total_votes: Balance = gauge_epoch.votes.values().sum();
+        let mut rewards = vec![];
+        if total_votes > 0 {
+            let reward_amount_per_epoch = gauge_config.reward_amount_per_epoch.0;
+            let epoch_duration_ns = to_nano(gauge_config.epoch_duration_sec) as u128;
+            for farm_id in gauge_config.farm_ids.iter() {
+                let votes = gauge_epoch.votes.get(farm_id).cloned().unwrap_or(0);
+                let reward_amount = u128_ratio(reward_amount_per_epoch, votes, total_votes);
+                if reward_amount == 0 {
+                    continue;
+                }
+                let reward_per_day = u128_ratio(reward_amount, NANOS_PER_DAY as u128, epoch_duration_ns);
+                self.internal_add_asset_farm_reward(
+                    farm_id.clone(),
+                    gauge_config.reward_token_id.clone(),
+                    reward_per_day.into(),
+                    gauge_config.booster_log_bases.clone(),
+                    reward_amount.into(),
+                    None,
+                );
+                rewards.push((farm_id.clone(), reward_amount));
+            }
+        }
+        events::emit::gauge_epoch_applied(gauge_epoch.epoch, &gauge_config.reward_token_id, &rewards);
+        write_gauge_epoch_to_storage(GaugeEpoch { epoch, votes: HashMap::new() });
+    }
+
+    pub fn get_gauge_config(&self) -> Option<GaugeConfig> {
+        read_gauge_config_from_storage()
+    }
+
+    /// Returns votes for farms in the current voting epoch.
+    pub fn get_gauge_epoch(&self) -> GaugeEpochView {
+        let gauge_epoch = read_gauge_epoch_from_storage();
+        (gauge_epoch.epoch, gauge_epoch.votes).into()
+    }
+
+    /// Returns the current voting power of the given account.
+    pub fn get_account_voting_power(&self, account_id: AccountId) -> U128 {
+        let gauge_config = self.internal_unwrap_gauge_config();
+        self.internal_get_account(&account_id, true)
+            .map(|account| self.internal_get_voting_power(&account, &gauge_config.booster_token_id))
+            .unwrap_or(0)
+            .into()
+    }
+
+    /// Returns the latest gauge vote of the given account.
+    pub fn get_account_gauge_vote(&self, account_id: AccountId) -> Option<GaugeEpochView> {
+        self.internal_get_account(&account_id, true)
+            .and_then(|account| account.gauge_vote)
+            .map(|gauge_vote| (gauge_vote.epoch, gauge_vote.votes).into())
+    }
+}
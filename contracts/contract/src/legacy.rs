@@ -1,6 +1,7 @@
 #![allow(deprecated)]
 
 use crate::*;
+use near_sdk::StorageUsage;
 
 /// Default multiplier for Net TVL farming. Equals to 1.
 const DEFAULT_NET_TVL_MULTIPLIER: u32 = 10000;
@@ -126,6 +127,13 @@ impl AccountV1 {
             booster_staking,
             booster_stakings: HashMap::new(),
             is_locked: false,
+            vestings: HashMap::new(),
+            auto_compound: None,
+            booster_delegations: HashMap::new(),
+            booster_delegators: HashMap::new(),
+            received_x_booster_amounts: HashMap::new(),
+            gauge_vote: None,
+            last_booster_penalty_per_stakes: HashMap::new(),
         }
     }
 }
@@ -174,6 +182,13 @@ impl AccountV2 {
             booster_staking,
             booster_stakings: HashMap::new(),
             is_locked: false,
+            vestings: HashMap::new(),
+            auto_compound: None,
+            booster_delegations: HashMap::new(),
+            booster_delegators: HashMap::new(),
+            received_x_booster_amounts: HashMap::new(),
+            gauge_vote: None,
+            last_booster_penalty_per_stakes: HashMap::new(),
         }
     }
 }
@@ -220,6 +235,68 @@ impl AccountV3 {
             booster_staking,
             booster_stakings: HashMap::new(),
             is_locked,
+            vestings: HashMap::new(),
+            auto_compound: None,
+            booster_delegations: HashMap::new(),
+            booster_delegators: HashMap::new(),
+            received_x_booster_amounts: HashMap::new(),
+            gauge_vote: None,
+            last_booster_penalty_per_stakes: HashMap::new(),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AccountV4 {
+    /// A copy of an account ID. Saves one storage_read when iterating on accounts.
+    pub account_id: AccountId,
+    /// A list of assets that are supplied by the account (but not used a collateral).
+    /// It's not returned for account pagination.
+    pub supplied: HashMap<TokenId, Shares>,
+    pub positions: HashMap<String, Position>,
+    /// Keeping track of data required for farms for this account.
+    pub farms: HashMap<FarmId, AccountFarm>,
+    #[borsh_skip]
+    pub affected_farms: HashSet<FarmId>,
+    /// Tracks changes in storage usage by persistent collections in this account.
+    #[borsh_skip]
+    pub storage_tracker: StorageTracker,
+    /// Staking of booster token.
+    pub booster_staking: Option<BoosterStaking>,
+    pub booster_stakings: HashMap<TokenId, BoosterStaking>,
+    pub is_locked: bool,
+}
+
+impl AccountV4 {
+    pub fn into_account(self) -> Account {
+        let AccountV4 {
+            account_id,
+            supplied,
+            positions,
+            farms,
+            affected_farms,
+            storage_tracker,
+            booster_staking,
+            booster_stakings,
+            is_locked,
+        } = self;
+        Account {
+            account_id,
+            supplied,
+            positions,
+            farms,
+            affected_farms,
+            storage_tracker,
+            booster_staking,
+            booster_stakings,
+            is_locked,
+            vestings: HashMap::new(),
+            auto_compound: None,
+            booster_delegations: HashMap::new(),
+            booster_delegators: HashMap::new(),
+            received_x_booster_amounts: HashMap::new(),
+            gauge_vote: None,
+            last_booster_penalty_per_stakes: HashMap::new(),
         }
     }
 }
@@ -252,6 +329,9 @@ impl From<MarginAccountV0> for MarginAccount {
             margin_positions,
             position_latest_actions: HashMap::new(),
             stops: HashMap::new(),
+            farms: HashMap::new(),
+            affected_farms: HashSet::new(),
+            unclaimed_farm_rewards: HashMap::new(),
             storage_tracker,
         }
     }
@@ -288,6 +368,51 @@ impl From<MarginAccountV1> for MarginAccount {
             margin_positions,
             position_latest_actions,
             stops: HashMap::new(),
+            farms: HashMap::new(),
+            affected_farms: HashSet::new(),
+            unclaimed_farm_rewards: HashMap::new(),
+            storage_tracker,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct MarginAccountV2 {
+    /// A copy of an account ID. Saves one storage_read when iterating on accounts.
+    pub account_id: AccountId,
+    /// A list of assets that are supplied by the account (but not used a collateral).
+    /// It's not returned for account pagination.
+    pub supplied: HashMap<TokenId, Shares>,
+    // margin trading related
+    pub margin_positions: UnorderedMap<PosId, MarginTradingPosition>,
+    // Record the timestamp of the position initiating the swap action.
+    pub position_latest_actions: HashMap<PosId, U64>,
+    // margin stops
+    pub stops: HashMap<PosId, MarginStop>,
+    /// Tracks changes in storage usage by persistent collections in this account.
+    #[borsh_skip]
+    pub storage_tracker: StorageTracker,
+}
+
+impl From<MarginAccountV2> for MarginAccount {
+    fn from(a: MarginAccountV2) -> Self {
+        let MarginAccountV2 { 
+            account_id, 
+            supplied, 
+            margin_positions,
+            position_latest_actions,
+            stops,
+            storage_tracker,
+        } = a;
+        Self {
+            account_id, 
+            supplied, 
+            margin_positions,
+            position_latest_actions,
+            stops,
+            farms: HashMap::new(),
+            affected_farms: HashSet::new(),
+            unclaimed_farm_rewards: HashMap::new(),
             storage_tracker,
         }
     }
@@ -1615,6 +1740,8 @@ impl From<AssetFarmRewardV0> for AssetFarmReward {
             remaining_rewards, 
             boosted_shares, 
             reward_per_share,
+            start_timestamp: None,
+            end_timestamp: None,
         }
     }
 }
@@ -1639,6 +1766,89 @@ impl From<AssetFarmV0> for AssetFarm {
             block_timestamp, 
             rewards: rewards.into_iter().map(|(k, v)| (k, v.into())).collect(), 
             inactive_rewards,
+            ended_rewards: vec![],
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct AssetFarmRewardV1 {
+    /// The amount of reward distributed per day.
+    pub reward_per_day: Balance,
+    /// The log base for the booster. Used to compute boosted shares per account.
+    /// Including decimals of the booster.
+    pub booster_log_bases: HashMap<TokenId, U128>,
+    /// The amount of rewards remaining to distribute.
+    pub remaining_rewards: Balance,
+    /// The total number of boosted shares.
+    pub boosted_shares: Balance,
+    pub reward_per_share: BigDecimal,
+}
+
+impl From<AssetFarmRewardV1> for AssetFarmReward {
+    fn from(a: AssetFarmRewardV1) -> Self {
+        let AssetFarmRewardV1 { 
+            reward_per_day, 
+            booster_log_bases, 
+            remaining_rewards, 
+            boosted_shares, 
+            reward_per_share,
+        } = a;
+        Self {
+            reward_per_day, 
+            booster_log_bases, 
+            remaining_rewards, 
+            boosted_shares, 
+            reward_per_share,
+            start_timestamp: None,
+            end_timestamp: None,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AssetFarmV1 {
+    pub block_timestamp: Timestamp,
+    /// Active rewards for the farm
+    pub rewards: HashMap<TokenId, AssetFarmRewardV1>,
+    /// Inactive rewards
+    pub inactive_rewards: LookupMap<TokenId, VAssetFarmReward>,
+}
+
+impl From<AssetFarmV1> for AssetFarm {
+    fn from(a: AssetFarmV1) -> Self {
+        let AssetFarmV1 { 
+            block_timestamp, 
+            rewards, 
+            inactive_rewards,
+        } = a;
+        Self {
+            block_timestamp, 
+            rewards: rewards.into_iter().map(|(k, v)| (k, v.into())).collect(), 
+            inactive_rewards,
+            ended_rewards: vec![],
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct StorageV0 {
+    pub storage_balance: Balance,
+    pub used_bytes: StorageUsage,
+}
+
+impl From<StorageV0> for Storage {
+    fn from(a: StorageV0) -> Self {
+        let StorageV0 {
+            storage_balance,
+            used_bytes,
+        } = a;
+        Self {
+            storage_balance,
+            used_bytes,
+            sponsor_id: None,
+            sponsored_balance: 0,
+            storage_tracker: Default::default(),
         }
     }
 }
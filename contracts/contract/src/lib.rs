@@ -25,12 +25,15 @@ mod shadow_actions;
 mod position;
 mod margin_position;
 mod margin_accounts;
+mod margin_account_farm;
 mod margin_actions;
 mod margin_trading;
 mod margin_config;
 mod margin_pyth;
 mod margin_base_token_limit;
 mod margin_stop_service_fee;
+mod margin_position_transfer;
+mod margin_split_swap;
 mod pyth;
 mod actions_pyth;
 mod protocol_debts;
@@ -38,6 +41,21 @@ mod storage_keys;
 mod client_echo;
 mod reliable_liquidator;
 mod booster_tokens;
+mod reward_vesting;
+mod auto_compound;
+mod booster_delegation;
+mod gauge;
+mod booster_early_unstake;
+mod storage_sponsor;
+mod account_close;
+mod admin_proposal;
+mod pause_registry;
+mod roles;
+mod reserve_swap;
+mod state_migration;
+mod beneficiary_distribution;
+mod reserve_contributions;
+mod blocklist;
 
 pub use crate::account::*;
 pub use crate::account_asset::*;
@@ -68,12 +86,28 @@ pub use crate::margin_trading::*;
 pub use crate::margin_config::*;
 pub use crate::margin_base_token_limit::*;
 pub use crate::margin_stop_service_fee::*;
+pub use crate::margin_position_transfer::*;
+pub use crate::margin_split_swap::*;
 pub use crate::pyth::*;
 pub use crate::protocol_debts::*;
 pub use crate::storage_keys::*;
 pub use crate::client_echo::*;
 pub use crate::reliable_liquidator::*;
 pub use crate::booster_tokens::*;
+pub use crate::reward_vesting::*;
+pub use crate::auto_compound::*;
+pub use crate::booster_delegation::*;
+pub use crate::gauge::*;
+pub use crate::booster_early_unstake::*;
+pub use crate::storage_sponsor::*;
+pub use crate::admin_proposal::*;
+pub use crate::pause_registry::*;
+pub use crate::roles::*;
+pub use crate::reserve_swap::*;
+pub use crate::state_migration::*;
+pub use crate::beneficiary_distribution::*;
+pub use crate::reserve_contributions::*;
+pub use crate::blocklist::*;
 #[cfg(test)]
 pub use crate::unit_env::*;
 
@@ -83,6 +117,7 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::{I64, U64, U128};
 use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::json;
 use near_sdk::PromiseError;
 use near_sdk::{
     assert_one_yocto, env, ext_contract, log, near_bindgen, AccountId, Balance, BorshStorageKey,
@@ -142,6 +177,7 @@ impl Contract {
     #[init]
     pub fn new(config: Config) -> Self {
         config.assert_valid();
+        write_state_schema_fingerprints_to_storage(current_state_schema_fingerprints());
         Self {
             accounts: UnorderedMap::new(StorageKey::Accounts),
             storage: LookupMap::new(StorageKey::Storage),
@@ -181,6 +217,7 @@ impl Contract {
     pub fn extend_guardians(&mut self, guardians: Vec<AccountId>) {
         assert_one_yocto();
         self.assert_owner();
+        events::emit::settings_updated("extend_guardians", &env::predecessor_account_id(), json!({"guardians": guardians}));
         for guardian in guardians {
             self.guardians.insert(&guardian);
         }
@@ -192,6 +229,7 @@ impl Contract {
     pub fn remove_guardians(&mut self, guardians: Vec<AccountId>) {
         assert_one_yocto();
         self.assert_owner();
+        events::emit::settings_updated("remove_guardians", &env::predecessor_account_id(), json!({"guardians": guardians}));
         for guardian in guardians {
             let is_success = self.guardians.remove(&guardian);
             assert!(is_success, "Invalid guardian");
@@ -210,6 +248,7 @@ impl Contract {
         assert_one_yocto();
         self.assert_owner();
         assert!(!self.token_pyth_info.contains_key(&token_id), "Already exist");
+        events::emit::settings_updated("add_token_pyth_info", &env::predecessor_account_id(), json!({"token_id": token_id, "token_pyth_info": token_pyth_info}));
         self.token_pyth_info.insert(token_id, token_pyth_info);
     }
 
@@ -220,6 +259,7 @@ impl Contract {
         assert_one_yocto();
         self.assert_owner();
         assert!(self.token_pyth_info.contains_key(&token_id), "Invalid token_id");
+        events::emit::settings_updated("update_token_pyth_info", &env::predecessor_account_id(), json!({"token_id": token_id, "token_pyth_info": token_pyth_info}));
         self.token_pyth_info.insert(token_id, token_pyth_info);
     }
 
@@ -239,6 +279,7 @@ impl Contract {
     pub fn extend_blacklist_of_farmers(&mut self, farmers: Vec<AccountId>) {
         assert_one_yocto();
         self.assert_owner_or_guardians();
+        events::emit::settings_updated("extend_blacklist_of_farmers", &env::predecessor_account_id(), json!({"farmers": farmers}));
         for farmer in farmers {
             self.blacklist_of_farmers.insert(&farmer);
             let mut account = self.internal_unwrap_account(&farmer);
@@ -256,6 +297,7 @@ impl Contract {
     pub fn remove_blacklist_of_farmers(&mut self, farmers: Vec<AccountId>) {
         assert_one_yocto();
         self.assert_owner();
+        events::emit::settings_updated("remove_blacklist_of_farmers", &env::predecessor_account_id(), json!({"farmers": farmers}));
         for farmer in farmers {
             let is_success = self.blacklist_of_farmers.remove(&farmer);
             assert!(is_success, "Invalid farmer");
@@ -292,8 +334,10 @@ impl Contract {
         if let Ok(U128(price)) = price_result {
             self.update_staking_token_price_record(&token_id, price, "The return value is out of the valid range".to_string());
             log!(format!("sync {token_id} price Successful: {price}"));
+            events::emit::sync_staking_token_price(&token_id, Some(price));
         } else {
             log!(format!("sync {token_id} price failed"));
+            events::emit::sync_staking_token_price(&token_id, None);
         }
     }
 
@@ -618,12 +662,55 @@ mod unit_env {
             self.contract.add_asset_farm_reward(farm_id, reward_token_id, new_reward_per_day.into(), HashMap::from([(booster_token_id(), U128(new_booster_log_base))]), reward_amount.into());
         }
 
+        pub fn schedule_farm(&mut self,
+            farm_id: FarmId,
+            reward_token_id: AccountId,
+            new_reward_per_day: Balance,
+            new_booster_log_base: Balance,
+            reward_amount: Balance,
+            start_timestamp: Timestamp,
+            end_timestamp: Option<Timestamp>,
+        ){
+            testing_env!(self.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+            self.contract.schedule_asset_farm_reward(farm_id, reward_token_id, AssetFarmRewardSchedule {
+                new_reward_per_day: new_reward_per_day.into(),
+                new_booster_log_bases: HashMap::from([(booster_token_id(), U128(new_booster_log_base))]),
+                reward_amount: reward_amount.into(),
+                start_timestamp: start_timestamp.into(),
+                end_timestamp: end_timestamp.map(|v| v.into()),
+            });
+        }
+
         pub fn account_farm_claim_all(&mut self, account_id: AccountId){
             clean_assets_cache();
             clean_assets_farm_cache();
             self.contract.account_farm_claim_all(Some(account_id));
         }
 
+        pub fn set_reward_vesting_config(&mut self, token_id: AccountId, reward_vesting_config: RewardVestingConfig){
+            testing_env!(self.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+            self.contract.set_reward_vesting_config(token_id, reward_vesting_config);
+        }
+
+        pub fn claim_vested_rewards(&mut self, account_id: AccountId, token_ids: Option<Vec<AccountId>>){
+            clean_assets_cache();
+            clean_assets_farm_cache();
+            testing_env!(self.context.predecessor_account_id(account_id).attached_deposit(0).build());
+            self.contract.claim_vested_rewards(token_ids);
+        }
+
+        pub fn set_auto_compound(&mut self, account_id: AccountId, auto_compound: Option<AutoCompound>){
+            testing_env!(self.context.predecessor_account_id(account_id).attached_deposit(1).build());
+            self.contract.set_auto_compound(auto_compound);
+        }
+
+        pub fn exit_reward_vesting(&mut self, account_id: AccountId, token_id: AccountId){
+            clean_assets_cache();
+            clean_assets_farm_cache();
+            testing_env!(self.context.predecessor_account_id(account_id).attached_deposit(1).build());
+            self.contract.exit_reward_vesting(token_id);
+        }
+
         pub fn skip_time_to_by_ms(&mut self, ms: u64){
             testing_env!(self.context.block_timestamp(ms * 10u64.pow(6)).build());
         }
@@ -828,6 +915,7 @@ mod unit_env {
 mod basic {
     use super::*;
     use unit_env::*;
+    use near_contract_standards::storage_management::StorageManagement;
 
     #[test]
     #[ignore]
@@ -1042,11 +1130,1066 @@ mod basic {
         test_env.contract.is_reliable_liquidator_context = in_reliable_liquidator_whitelist("bob");
         assert!(!test_env.contract.is_reliable_liquidator_context);
 
-        // Test 5: Verify multiple patterns work
-        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
-        test_env.contract.append_reliable_liquidator_whitelist(vec!["*.exchange".to_string()]);
-        assert!(in_reliable_liquidator_whitelist("test.exchange"));
-        assert!(!in_reliable_liquidator_whitelist("exchange"));
+        // Test 5: Verify multiple patterns work
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.append_reliable_liquidator_whitelist(vec!["*.exchange".to_string()]);
+        assert!(in_reliable_liquidator_whitelist("test.exchange"));
+        assert!(!in_reliable_liquidator_whitelist("exchange"));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_storage_sponsor() {
+        let mut test_env = init_unit_env();
+        let sponsor_id = AccountId::new_unchecked("sponsor".to_string());
+        let dave = AccountId::new_unchecked("dave".to_string());
+        let eve = AccountId::new_unchecked("eve".to_string());
+        let min_balance = test_env.contract.storage_balance_bounds().min.0;
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.append_storage_sponsor_whitelist(vec![sponsor_id.clone()]);
+        testing_env!(test_env.context.predecessor_account_id(sponsor_id.clone()).attached_deposit(min_balance * 2).build());
+        test_env.contract.storage_sponsor_pool_deposit();
+        testing_env!(test_env.context.predecessor_account_id(sponsor_id.clone()).attached_deposit(1).build());
+        test_env.contract.set_storage_sponsor_min_deposits(HashMap::from([(neth_token_id(), U128(d(1, 18)))]));
+
+        // The first deposit of an unregistered account draws the storage balance from the pool.
+        test_env.deposit(neth_token_id(), dave.clone(), d(1, 18));
+        let detail = test_env.contract.get_storage_balance_of_detail(dave.clone()).unwrap();
+        assert_eq!(detail.sponsor_id, Some(sponsor_id.clone()));
+        assert_eq!(detail.sponsored_amount.0, min_balance);
+        assert_eq!(test_env.contract.storage_balance_of(dave.clone()).unwrap().available.0, 0);
+        assert_eq!(test_env.contract.get_storage_sponsor_pools()[&sponsor_id].0, min_balance);
+
+        // Pre-funding a third-party account.
+        testing_env!(test_env.context.predecessor_account_id(sponsor_id.clone()).attached_deposit(min_balance).build());
+        test_env.contract.storage_sponsor_deposit(eve.clone());
+        testing_env!(test_env.context.predecessor_account_id(eve.clone()).attached_deposit(min_balance).build());
+        test_env.contract.storage_deposit(None, None);
+        assert_eq!(test_env.contract.storage_balance_of(eve.clone()).unwrap().available.0, min_balance);
+
+        // The sponsored part is refunded to the sponsor on unregister.
+        testing_env!(test_env.context.predecessor_account_id(eve.clone()).attached_deposit(1).build());
+        assert!(test_env.contract.storage_unregister(None));
+        let transfers: Vec<(AccountId, Balance)> = near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver_id = receipt.receiver_id.clone();
+                receipt.actions.into_iter().filter_map(move |action| match action {
+                    near_sdk::mock::VmAction::Transfer { deposit } => Some((receiver_id.clone(), deposit)),
+                    _ => None,
+                })
+            })
+            .collect();
+        assert_eq!(transfers, vec![(sponsor_id.clone(), min_balance), (eve.clone(), min_balance)]);
+
+        testing_env!(test_env.context.predecessor_account_id(sponsor_id.clone()).attached_deposit(1).build());
+        assert_eq!(test_env.contract.storage_sponsor_pool_withdraw(None).0, 0);
+        assert!(test_env.contract.get_storage_sponsor_pools().is_empty());
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "Account is not registered")]
+    fn test_storage_sponsor_below_min_deposit() {
+        let mut test_env = init_unit_env();
+        let sponsor_id = AccountId::new_unchecked("sponsor".to_string());
+        let min_balance = test_env.contract.storage_balance_bounds().min.0;
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.append_storage_sponsor_whitelist(vec![sponsor_id.clone()]);
+        testing_env!(test_env.context.predecessor_account_id(sponsor_id.clone()).attached_deposit(min_balance).build());
+        test_env.contract.storage_sponsor_pool_deposit();
+        testing_env!(test_env.context.predecessor_account_id(sponsor_id).attached_deposit(1).build());
+        test_env.contract.set_storage_sponsor_min_deposits(HashMap::from([(neth_token_id(), U128(d(1, 18)))]));
+
+        test_env.deposit(neth_token_id(), AccountId::new_unchecked("dave".to_string()), d(1, 18) - 1);
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "The account is sponsored by another sponsor or funds its own storage")]
+    fn test_storage_sponsor_deposit_self_funded_account() {
+        let mut test_env = init_unit_env();
+        let sponsor_id = AccountId::new_unchecked("sponsor".to_string());
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.append_storage_sponsor_whitelist(vec![sponsor_id.clone()]);
+        testing_env!(test_env.context.predecessor_account_id(sponsor_id).attached_deposit(1).build());
+        test_env.contract.storage_sponsor_deposit(alice());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_close_account() {
+        let mut test_env = init_unit_env();
+        let dust = 5;
+        let reserved = test_env.get_asset(nusdt_token_id()).reserved;
+
+        test_env.deposit(nusdt_token_id(), alice(), d(10, 6));
+        test_env.deposit(nusdt_token_id(), charlie(), d(10, 6));
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.execute(vec![Action::Withdraw(AssetAmount {
+            token_id: nusdt_token_id(),
+            amount: Some((d(10, 18) - dust).into()),
+            max_amount: None,
+        })]);
+
+        // The dust is moved into the reserve and the storage balance is refunded.
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.close_account();
+        assert!(test_env.contract.get_account(alice()).is_none());
+        assert!(test_env.contract.get_margin_account(alice()).is_none());
+        assert!(test_env.contract.storage_balance_of(alice()).is_none());
+        assert_eq!(test_env.get_asset(nusdt_token_id()).reserved, reserved + dust);
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, alice());
+
+        // Only accounts holding nothing at all are cleaned up.
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        assert_eq!(test_env.contract.cleanup_abandoned_accounts(None, None), 2);
+        assert_eq!(test_env.contract.get_num_accounts(), 1);
+        assert!(test_env.contract.get_account(charlie()).is_some());
+        assert!(test_env.contract.storage_balance_of(bob()).is_none());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_admin_proposal() {
+        let mut test_env = init_unit_env();
+        let contract_id = AccountId::new_unchecked("burrow".to_string());
+        let delay_sec = 86400;
+        testing_env!(test_env.context.current_account_id(contract_id.clone()).predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_admin_method_delay("update_max_leverage_rate".to_string(), delay_sec);
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        let args = r#"{"max_leverage_rate": 5}"#.to_string();
+        let id = test_env.contract.submit_admin_proposal("update_max_leverage_rate".to_string(), args.clone());
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        let cancelled_id = test_env.contract.submit_admin_proposal("update_max_leverage_rate".to_string(), args.clone());
+        let proposals = test_env.contract.get_admin_proposals();
+        assert_eq!(proposals.len(), 2);
+        assert_eq!(proposals[0].id, id);
+        assert_eq!(proposals[0].eta, sec_to_nano(delay_sec));
+
+        // Guardians can cancel pending proposals.
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.extend_guardians(vec![bob()]);
+        testing_env!(test_env.context.predecessor_account_id(bob()).attached_deposit(1).build());
+        test_env.contract.cancel_admin_proposal(cancelled_id);
+        assert_eq!(test_env.contract.get_admin_proposals().len(), 1);
+
+        test_env.skip_time_to_by_sec(delay_sec);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.execute_admin_proposal(id);
+        assert!(test_env.contract.get_admin_proposals().is_empty());
+
+        // The contract calls itself on behalf of the owner.
+        testing_env!(test_env.context.predecessor_account_id(contract_id.clone()).attached_deposit(1).build());
+        test_env.contract.update_max_leverage_rate(5);
+        assert_eq!(test_env.contract.get_margin_config().max_leverage_rate, 5);
+        assert!(!test_env.contract.is_admin_proposal_call("update_max_num_assets"));
+
+        testing_env!(
+            test_env.context.predecessor_account_id(contract_id).attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![near_sdk::PromiseResult::Successful(vec![])]
+        );
+        assert!(test_env.contract.after_admin_proposal_executed(id));
+        assert!(!test_env.contract.is_admin_proposal_call("update_max_leverage_rate"));
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "Not allowed")]
+    fn test_admin_self_call_without_proposal() {
+        let mut test_env = init_unit_env();
+        let contract_id = AccountId::new_unchecked("burrow".to_string());
+        testing_env!(test_env.context.current_account_id(contract_id.clone()).predecessor_account_id(contract_id).attached_deposit(1).build());
+        test_env.contract.update_max_leverage_rate(5);
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "update_max_leverage_rate requires an admin proposal")]
+    fn test_admin_timelock_direct_call() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_admin_method_delay("update_max_leverage_rate".to_string(), 86400);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.update_max_leverage_rate(12);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_admin_timelock_tightening() {
+        let mut test_env = init_unit_env();
+        for method_name in ["update_max_leverage_rate", "update_min_safety_buffer", "update_asset_limit", "set_margin_base_token_limit"] {
+            testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+            test_env.contract.set_admin_method_delay(method_name.to_string(), 86400);
+        }
+
+        // Tightening a limit takes effect immediately.
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.update_max_leverage_rate(5);
+        let min_safety_buffer = test_env.contract.get_margin_config().min_safety_buffer;
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.update_min_safety_buffer(min_safety_buffer + 100);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.update_asset_limit(ndai_token_id(), Some(U128(d(1000, 18))), Some(U128(d(1000, 18))), None);
+        let mut mbtl = test_env.contract.internal_unwrap_margin_base_token_limit_or_default(&ndai_token_id());
+        mbtl.max_leverage_rate = 3;
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_margin_base_token_limit(ndai_token_id(), mbtl);
+        let margin_config = test_env.contract.get_margin_config();
+        assert_eq!(margin_config.max_leverage_rate, 5);
+        assert_eq!(margin_config.min_safety_buffer, min_safety_buffer + 100);
+        assert_eq!(test_env.get_asset(ndai_token_id()).config.supplied_limit, Some(U128(d(1000, 18))));
+        assert_eq!(test_env.contract.internal_unwrap_margin_base_token_limit_or_default(&ndai_token_id()).max_leverage_rate, 3);
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "update_asset_limit requires an admin proposal")]
+    fn test_admin_timelock_loosening_limit() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_admin_method_delay("update_asset_limit".to_string(), 86400);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.update_asset_limit(ndai_token_id(), Some(U128(d(1000, 18))), Some(U128(d(1000, 18))), None);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.update_asset_limit(ndai_token_id(), Some(U128(d(2000, 18))), Some(U128(d(1000, 18))), None);
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "Execute is paused for nusdt_token_id")]
+    fn test_pause_registry() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.extend_guardians(vec![bob()]);
+        testing_env!(test_env.context.predecessor_account_id(bob()).attached_deposit(1).build());
+        test_env.contract.pause(PauseKind::Execute, Some(nusdt_token_id().to_string()));
+        testing_env!(test_env.context.predecessor_account_id(bob()).attached_deposit(1).build());
+        test_env.contract.pause(PauseKind::MarginTrading, None);
+
+        let pause_state = test_env.contract.get_pause_state();
+        assert_eq!(pause_state.paused, vec![
+            PauseKey { kind: PauseKind::Execute, target: Some(nusdt_token_id().to_string()) },
+            PauseKey { kind: PauseKind::MarginTrading, target: None },
+        ]);
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.unpause(PauseKind::MarginTrading, None);
+        assert_eq!(test_env.contract.get_pause_state().paused.len(), 1);
+
+        test_env.deposit(ndai_token_id(), alice(), d(100, 18));
+        test_env.deposit(nusdt_token_id(), alice(), d(100, 6));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_roles() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.grant_role(Role::RiskAdmin, vec![bob()]);
+        assert_eq!(test_env.contract.get_account_roles(bob()), vec![Role::RiskAdmin]);
+
+        testing_env!(test_env.context.predecessor_account_id(bob()).attached_deposit(1).build());
+        test_env.contract.update_max_num_assets(20);
+        assert_eq!(test_env.contract.get_config().max_num_assets, 20);
+
+        test_env.deposit(ndai_token_id(), alice(), d(100, 18));
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_owner_id(alice());
+        assert_eq!(test_env.contract.get_pending_owner_id(), Some(alice()));
+        assert_eq!(test_env.contract.get_config().owner_id, owner_id());
+
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.accept_owner_id();
+        assert_eq!(test_env.contract.get_config().owner_id, alice());
+        assert_eq!(test_env.contract.get_pending_owner_id(), None);
+
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.revoke_role(Role::RiskAdmin, vec![bob()]);
+        assert!(test_env.contract.get_roles().is_empty());
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "Not allowed")]
+    fn test_role_required() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.grant_role(Role::OracleAdmin, vec![bob()]);
+        testing_env!(test_env.context.predecessor_account_id(bob()).attached_deposit(1).build());
+        test_env.contract.update_max_num_assets(20);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_socialize_protocol_debt() {
+        let mut test_env = init_unit_env();
+        test_env.supply_to_collateral(wnear_token_id(), alice(), d(100, 24));
+        test_env.deposit(ndai_token_id(), bob(), d(1000, 18));
+        test_env.borrow(alice(), ndai_token_id(), d(200, 18), unit_price_data(0, Some(100000), None));
+        test_env.skip_time_to_by_sec(365 * 24 * 3600);
+
+        // The suppliers can lose at most the interest above 1:1 of their shares here,
+        // since the liquidity that isn't lent out covers the reserve part and the haircut.
+        let asset = test_env.get_asset(ndai_token_id());
+        let max_haircut_amount = asset.supplied.balance - asset.supplied.shares.0;
+        assert!(max_haircut_amount > 0);
+        assert!(max_haircut_amount < asset.supplied.balance - asset.borrowed.balance);
+        let extra_debt = d(1, 18);
+        let mut protocol_debts = HashMap::new();
+        protocol_debts.insert(ndai_token_id(), asset.reserved + max_haircut_amount + extra_debt);
+        write_protocol_debts_to_storage(protocol_debts);
+
+        let projected = test_env.contract.get_projected_protocol_debt_haircuts(None);
+        assert_eq!(projected.len(), 1);
+        assert_eq!(projected[0].reserve_amount, asset.reserved);
+        assert_eq!(projected[0].haircut_amount, max_haircut_amount);
+        assert_eq!(projected[0].remaining_debt, extra_debt);
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        let resolution = test_env.contract.socialize_protocol_debt(ndai_token_id());
+        assert_eq!(resolution.haircut_amount, max_haircut_amount);
+        let asset = test_env.get_asset(ndai_token_id());
+        assert_eq!(asset.reserved, 0);
+        assert_eq!(asset.supplied.balance, asset.supplied.shares.0);
+        assert_eq!(test_env.contract.get_all_protocol_debts().get(&ndai_token_id()), Some(&U128(extra_debt)));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_socialize_protocol_debt_lent_out_limit() {
+        let mut test_env = init_unit_env();
+        test_env.supply_to_collateral(wnear_token_id(), alice(), d(100, 24));
+        test_env.borrow(alice(), ndai_token_id(), d(200, 18), unit_price_data(0, Some(100000), None));
+        test_env.skip_time_to_by_sec(365 * 24 * 3600);
+
+        // The lent out liquidity is still owed to the suppliers, so it isn't haircut.
+        let mut asset = test_env.contract.internal_unwrap_asset(&ndai_token_id());
+        asset.supplied.balance = asset.borrowed.balance + asset.margin_debt.balance + d(1, 18);
+        asset.supplied.shares = U128(d(1, 18));
+        asset.reserved = 0;
+        asset.prot_fee = 0;
+        test_env.contract.internal_set_asset(&ndai_token_id(), asset);
+        let mut protocol_debts = HashMap::new();
+        protocol_debts.insert(ndai_token_id(), d(10, 18));
+        write_protocol_debts_to_storage(protocol_debts);
+
+        let projected = test_env.contract.get_projected_protocol_debt_haircuts(None);
+        assert_eq!(projected[0].haircut_amount, d(1, 18));
+        assert_eq!(projected[0].remaining_debt, d(9, 18));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_swap_reserve() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(ref_exchange_id(), 1);
+        let mut protocol_debts = HashMap::new();
+        protocol_debts.insert(nusdt_token_id(), d(3, 18));
+        write_protocol_debts_to_storage(protocol_debts);
+        let ndai_reserved = test_env.get_asset(ndai_token_id()).reserved;
+        let nusdt_reserved = test_env.get_asset(nusdt_token_id()).reserved;
+
+        let swap = ReserveSwap {
+            token_in_id: ndai_token_id(),
+            amount_in: U128(d(10, 18)),
+            token_out_id: nusdt_token_id(),
+            min_amount_out: U128(d(99, 17)),
+            swap_indication: SwapIndication {
+                splits: vec![],
+                dex_id: ref_exchange_id(),
+                swap_action_text: near_sdk::serde_json::to_string(&RefV1TokenReceiverMessage::Execute {
+                    referral_id: None,
+                    client_echo: None,
+                    skip_degen_price_sync: None,
+                    actions: vec![RefV1Action::Swap(RefV1SwapAction {
+                        pool_id: 0,
+                        token_in: ndai_token_id(),
+                        amount_in: Some(U128(d(10, 18))),
+                        token_out: nusdt_token_id(),
+                        min_amount_out: U128(d(99, 5)),
+                    })],
+                }).unwrap(),
+            },
+        };
+        let msg = near_sdk::serde_json::to_string(&PriceReceiverMsg::SwapReserve { swap }).unwrap();
+        test_env.contract_oracle_call(owner_id(), unit_price_data(0, None, None), msg);
+        assert_eq!(test_env.get_asset(ndai_token_id()).reserved, ndai_reserved - d(10, 18));
+
+        let swap_ref = SwapReference {
+            leg: None,
+            account_id: owner_id(),
+            pos_id: "0".to_string(),
+            amount_in: U128(d(10, 18)),
+            action_ts: U64(0),
+            op: RESERVE_SWAP_OP.to_string(),
+            liquidator_id: None,
+        };
+        test_env.contract_ft_transfer_call(nusdt_token_id(), ref_exchange_id(), U128(d(10, 6)), swap_ref.to_msg_string());
+        assert!(test_env.contract.get_all_protocol_debts().is_empty());
+        assert_eq!(test_env.get_asset(nusdt_token_id()).reserved, nusdt_reserved + d(7, 18));
+    }
+
+    /// Swaps 10 DAI for at least 9.9 USDT on ref-v1.
+    fn ndai_to_nusdt_swap_indication() -> SwapIndication {
+        SwapIndication {
+            splits: vec![],
+            dex_id: ref_exchange_id(),
+            swap_action_text: near_sdk::serde_json::to_string(&RefV1TokenReceiverMessage::Execute {
+                referral_id: None,
+                client_echo: None,
+                skip_degen_price_sync: None,
+                actions: vec![RefV1Action::Swap(RefV1SwapAction {
+                    pool_id: 0,
+                    token_in: ndai_token_id(),
+                    amount_in: Some(U128(d(10, 18))),
+                    token_out: nusdt_token_id(),
+                    min_amount_out: U128(d(99, 5)),
+                })],
+            }).unwrap(),
+        }
+    }
+
+    fn ndai_to_nusdt_reserve_swap() -> ReserveSwap {
+        ReserveSwap {
+            token_in_id: ndai_token_id(),
+            amount_in: U128(d(10, 18)),
+            token_out_id: nusdt_token_id(),
+            min_amount_out: U128(d(99, 17)),
+            swap_indication: ndai_to_nusdt_swap_indication(),
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn test_stale_pending_swap() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(ref_exchange_id(), 1);
+        let msg = near_sdk::serde_json::to_string(&PriceReceiverMsg::SwapReserve { swap: ndai_to_nusdt_reserve_swap() }).unwrap();
+        test_env.contract_oracle_call(owner_id(), unit_price_data(0, None, None), msg.clone());
+        test_env.contract_oracle_call(owner_id(), unit_price_data(0, None, None), msg);
+        let nusdt_reserved = test_env.get_asset(nusdt_token_id()).reserved;
+        let wait_sec = test_env.contract.get_margin_config().max_position_action_wait_sec;
+        test_env.skip_time_to_by_sec(wait_sec);
+
+        // The owner returns the output the dex kept.
+        let swap_ref = SwapReference {
+            leg: None,
+            account_id: owner_id(),
+            pos_id: "0".to_string(),
+            amount_in: U128(d(10, 18)),
+            action_ts: U64(0),
+            op: RESERVE_SWAP_OP.to_string(),
+            liquidator_id: None,
+        };
+        test_env.contract_ft_transfer_call(nusdt_token_id(), owner_id(), U128(d(10, 6)), swap_ref.to_msg_string());
+        assert!(pending_swaps().get(&0).is_none());
+        assert_eq!(test_env.get_asset(nusdt_token_id()).reserved, nusdt_reserved + d(10, 18));
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.expire_pending_swap(U64(1));
+        assert!(pending_swaps().get(&1).is_none());
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "Please wait for the pending swap")]
+    fn test_expire_pending_swap_too_early() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(ref_exchange_id(), 1);
+        let msg = near_sdk::serde_json::to_string(&PriceReceiverMsg::SwapReserve { swap: ndai_to_nusdt_reserve_swap() }).unwrap();
+        test_env.contract_oracle_call(owner_id(), unit_price_data(0, None, None), msg);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.expire_pending_swap(U64(0));
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "There is no pending swap")]
+    fn test_swap_reserve_return_without_pending_swap() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(ref_exchange_id(), 1);
+
+        let swap_ref = SwapReference {
+            leg: None,
+            account_id: owner_id(),
+            pos_id: "0".to_string(),
+            amount_in: U128(d(10, 18)),
+            action_ts: U64(0),
+            op: RESERVE_SWAP_OP.to_string(),
+            liquidator_id: None,
+        };
+        test_env.contract_ft_transfer_call(nusdt_token_id(), ref_exchange_id(), U128(d(10, 6)), swap_ref.to_msg_string());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_distribute_beneficiary_fees() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(ref_exchange_id(), 1);
+        test_env.contract.set_beneficiary_payout_config(owner_id(), Some(BeneficiaryPayoutConfig {
+            min_payouts: HashMap::new(),
+            payout_token_id: Some(nusdt_token_id()),
+        }));
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.set_beneficiary_payout_config(alice(), Some(BeneficiaryPayoutConfig {
+            min_payouts: HashMap::from([(ndai_token_id(), U128(d(10, 18)))]),
+            payout_token_id: None,
+        }));
+        let mut asset = test_env.contract.internal_unwrap_asset(&ndai_token_id());
+        asset.beneficiary_fees.insert(alice(), U128(d(3, 18)));
+        asset.beneficiary_fees.insert(bob(), U128(d(5, 18)));
+        asset.beneficiary_fees.insert(owner_id(), U128(d(10, 18)));
+        test_env.contract.internal_set_asset(&ndai_token_id(), asset);
+
+        testing_env!(test_env.context.predecessor_account_id(bob()).attached_deposit(0).build());
+        assert_eq!(test_env.contract.distribute_beneficiary_fees(vec![ndai_token_id()]), 1);
+        let beneficiary_fees = test_env.contract.internal_unwrap_asset(&ndai_token_id()).beneficiary_fees;
+        assert_eq!(beneficiary_fees.get(&alice()), Some(&U128(d(3, 18))));
+        assert_eq!(beneficiary_fees.get(&bob()), None);
+        assert_eq!(beneficiary_fees.get(&owner_id()), Some(&U128(d(10, 18))));
+
+        // Anyone can swap the fee into the payout token.
+        let swap = BeneficiaryFeeSwap {
+            beneficiary_id: owner_id(),
+            token_in_id: ndai_token_id(),
+            amount_in: U128(d(10, 18)),
+            min_amount_out: U128(d(99, 17)),
+            swap_indication: ndai_to_nusdt_swap_indication(),
+        };
+        let msg = near_sdk::serde_json::to_string(&PriceReceiverMsg::SwapBeneficiaryFee { swap }).unwrap();
+        test_env.contract_oracle_call(bob(), unit_price_data(0, None, None), msg);
+        assert_eq!(test_env.contract.internal_unwrap_asset(&ndai_token_id()).beneficiary_fees.get(&owner_id()), None);
+
+        let swap_ref = SwapReference {
+            leg: None,
+            account_id: owner_id(),
+            pos_id: "0".to_string(),
+            amount_in: U128(d(10, 18)),
+            action_ts: U64(0),
+            op: BENEFICIARY_FEE_SWAP_OP.to_string(),
+            liquidator_id: None,
+        };
+        test_env.contract_ft_transfer_call(nusdt_token_id(), ref_exchange_id(), U128(d(10, 6)), swap_ref.to_msg_string());
+        assert!(pending_swaps().get(&0).is_none());
+        let beneficiary_fees = test_env.contract.internal_unwrap_asset(&nusdt_token_id()).beneficiary_fees;
+        assert_eq!(beneficiary_fees.get(&owner_id()), Some(&U128(d(10, 18))));
+        assert_eq!(test_env.contract.distribute_beneficiary_fees(vec![nusdt_token_id()]), 1);
+        assert_eq!(test_env.contract.internal_unwrap_asset(&nusdt_token_id()).beneficiary_fees.get(&owner_id()), None);
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "The amount in is below the minimum payout")]
+    fn test_swap_beneficiary_fee_below_min_payout() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(ref_exchange_id(), 1);
+        test_env.contract.set_beneficiary_payout_config(alice(), Some(BeneficiaryPayoutConfig {
+            min_payouts: HashMap::from([(ndai_token_id(), U128(d(20, 18)))]),
+            payout_token_id: Some(nusdt_token_id()),
+        }));
+        let mut asset = test_env.contract.internal_unwrap_asset(&ndai_token_id());
+        asset.beneficiary_fees.insert(alice(), U128(d(10, 18)));
+        test_env.contract.internal_set_asset(&ndai_token_id(), asset);
+
+        let swap = BeneficiaryFeeSwap {
+            beneficiary_id: alice(),
+            token_in_id: ndai_token_id(),
+            amount_in: U128(d(10, 18)),
+            min_amount_out: U128(d(99, 17)),
+            swap_indication: ndai_to_nusdt_swap_indication(),
+        };
+        let msg = near_sdk::serde_json::to_string(&PriceReceiverMsg::SwapBeneficiaryFee { swap }).unwrap();
+        test_env.contract_oracle_call(bob(), unit_price_data(0, None, None), msg);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_margin_split_swap() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(ref_exchange_id(), 1);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(dcl_id(), 2);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_token(ndai_token_id(), 1);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_token(nusdt_token_id(), 2);
+        test_env.deposit(ndai_token_id(), bob(), d(1000, 18));
+        let msg = serde_json::to_string(&TokenReceiverMsg::MarginExecute { actions: vec![] }).unwrap();
+        test_env.contract_ft_transfer_call(ndai_token_id(), alice(), U128(d(100, 18)), msg);
+
+        // 120 DAI are traded on ref-v1 and 80 DAI on DCL, for 196 USDT altogether.
+        let swap_indication = SwapIndication {
+            dex_id: ref_exchange_id(),
+            swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute {
+                referral_id: None,
+                client_echo: None,
+                skip_degen_price_sync: None,
+                actions: vec![RefV1Action::Swap(RefV1SwapAction {
+                    pool_id: 0,
+                    token_in: ndai_token_id(),
+                    amount_in: Some(U128(d(120, 18))),
+                    token_out: nusdt_token_id(),
+                    min_amount_out: U128(d(1176, 5)),
+                })],
+            }).unwrap(),
+            splits: vec![SwapSplit {
+                dex_id: dcl_id(),
+                amount_in: U128(d(80, 18)),
+                swap_action_text: serde_json::to_string(&RefV2TokenReceiverMessage::Swap {
+                    pool_ids: vec![format!("{}|{}|100", ndai_token_id(), nusdt_token_id())],
+                    output_token: nusdt_token_id(),
+                    min_output_amount: U128(d(784, 5)),
+                    skip_unwrap_near: Some(true),
+                    client_echo: None,
+                }).unwrap(),
+            }],
+        };
+        let msg = serde_json::to_string(&PriceReceiverMsg::MarginExecute {
+            actions: vec![MarginAction::OpenPosition {
+                token_c_id: ndai_token_id(),
+                token_c_amount: U128(d(100, 18)),
+                token_d_id: ndai_token_id(),
+                token_d_amount: U128(d(200, 18)),
+                token_p_id: nusdt_token_id(),
+                min_token_p_amount: U128(d(196, 18)),
+                swap_indication,
+                stop_profit: None,
+                stop_loss: None,
+            }],
+        }).unwrap();
+        test_env.contract_oracle_call(alice(), unit_price_data(0, None, None), msg);
+        let pos_id = test_env.contract.get_margin_account(alice()).unwrap().margin_positions.into_keys().next().unwrap();
+        assert!(pending_split_swaps().get(&pos_id).is_some());
+        assert_eq!(test_env.contract.internal_unwrap_asset(&ndai_token_id()).margin_pending_debt, d(200, 18));
+
+        // The ref-v1 leg returns its output, while the DCL leg fails and refunds the DAI.
+        let swap_ref = SwapReference {
+            account_id: alice(),
+            pos_id: pos_id.clone(),
+            amount_in: U128(d(200, 18)),
+            action_ts: U64(0),
+            op: "open".to_string(),
+            liquidator_id: None,
+            leg: Some(0),
+        };
+        test_env.contract_ft_transfer_call(nusdt_token_id(), ref_exchange_id(), U128(d(118, 6)), swap_ref.to_msg_string());
+        assert!(pending_split_swaps().get(&pos_id).is_some());
+        testing_env!(
+            test_env.context.predecessor_account_id(env::current_account_id()).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![
+                near_sdk::PromiseResult::Successful(serde_json::to_vec(&U128(d(120, 18))).unwrap()),
+                near_sdk::PromiseResult::Successful(serde_json::to_vec(&U128(0)).unwrap()),
+            ]
+        );
+        test_env.contract.callback_dex_split_trade(alice(), pos_id.clone());
+        assert!(pending_split_swaps().get(&pos_id).is_none());
+
+        let margin_account = test_env.contract.get_margin_account(alice()).unwrap();
+        assert!(margin_account.position_latest_actions.is_empty());
+        let position = &margin_account.margin_positions[&pos_id];
+        assert!(!position.is_locking);
+        assert_eq!(position.token_p_amount, d(118, 18));
+        let asset_d = test_env.contract.internal_unwrap_asset(&ndai_token_id());
+        assert_eq!(asset_d.margin_pending_debt, 0);
+        assert_eq!(asset_d.margin_debt.balance, d(120, 18));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_margin_split_swap_decrease_from_collateral() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(ref_exchange_id(), 1);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_dex(dcl_id(), 2);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_token(ndai_token_id(), 1);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.register_margin_token(nusdt_token_id(), 2);
+        test_env.deposit(ndai_token_id(), bob(), d(1000, 18));
+        let msg = serde_json::to_string(&TokenReceiverMsg::MarginExecute { actions: vec![] }).unwrap();
+        test_env.contract_ft_transfer_call(nusdt_token_id(), alice(), U128(d(100, 6)), msg);
+
+        // A short position of 198 USDT, with USDT as the collateral.
+        let msg = serde_json::to_string(&PriceReceiverMsg::MarginExecute {
+            actions: vec![MarginAction::OpenPosition {
+                token_c_id: nusdt_token_id(),
+                token_c_amount: U128(d(100, 18)),
+                token_d_id: ndai_token_id(),
+                token_d_amount: U128(d(200, 18)),
+                token_p_id: nusdt_token_id(),
+                min_token_p_amount: U128(d(196, 18)),
+                swap_indication: SwapIndication {
+                    dex_id: ref_exchange_id(),
+                    swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute {
+                        referral_id: None,
+                        client_echo: None,
+                        skip_degen_price_sync: None,
+                        actions: vec![RefV1Action::Swap(RefV1SwapAction {
+                            pool_id: 0,
+                            token_in: ndai_token_id(),
+                            amount_in: Some(U128(d(200, 18))),
+                            token_out: nusdt_token_id(),
+                            min_amount_out: U128(d(196, 6)),
+                        })],
+                    }).unwrap(),
+                    splits: vec![],
+                },
+                stop_profit: None,
+                stop_loss: None,
+            }],
+        }).unwrap();
+        test_env.contract_oracle_call(alice(), unit_price_data(0, None, None), msg);
+        let pos_id = test_env.contract.get_margin_account(alice()).unwrap().margin_positions.into_keys().next().unwrap();
+        let swap_ref = SwapReference {
+            account_id: alice(),
+            pos_id: pos_id.clone(),
+            amount_in: U128(d(200, 18)),
+            action_ts: U64(0),
+            op: "open".to_string(),
+            liquidator_id: None,
+            leg: None,
+        };
+        test_env.contract_ft_transfer_call(nusdt_token_id(), ref_exchange_id(), U128(d(198, 6)), swap_ref.to_msg_string());
+        let position = test_env.contract.internal_unwrap_margin_account(&alice()).margin_positions.get(&pos_id).unwrap();
+        assert_eq!(position.token_p_amount, d(198, 18));
+        let token_c_shares = position.token_c_shares.0;
+
+        // 210 USDT are traded, 12 of them drawn from the collateral:
+        // 10 USDT on ref-v1 and 200 USDT on DCL.
+        let swap_indication = SwapIndication {
+            dex_id: ref_exchange_id(),
+            swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute {
+                referral_id: None,
+                client_echo: None,
+                skip_degen_price_sync: None,
+                actions: vec![RefV1Action::Swap(RefV1SwapAction {
+                    pool_id: 0,
+                    token_in: nusdt_token_id(),
+                    amount_in: Some(U128(d(10, 6))),
+                    token_out: ndai_token_id(),
+                    min_amount_out: U128(d(98, 17)),
+                })],
+            }).unwrap(),
+            splits: vec![SwapSplit {
+                dex_id: dcl_id(),
+                amount_in: U128(d(200, 6)),
+                swap_action_text: serde_json::to_string(&RefV2TokenReceiverMessage::Swap {
+                    pool_ids: vec![format!("{}|{}|100", nusdt_token_id(), ndai_token_id())],
+                    output_token: ndai_token_id(),
+                    min_output_amount: U128(d(196, 18)),
+                    skip_unwrap_near: Some(true),
+                    client_echo: None,
+                }).unwrap(),
+            }],
+        };
+        let msg = serde_json::to_string(&PriceReceiverMsg::MarginExecute {
+            actions: vec![MarginAction::DecreaseMTPosition {
+                pos_id: pos_id.clone(),
+                token_p_amount: U128(d(210, 18)),
+                min_token_d_amount: U128(d(2058, 17)),
+                swap_indication,
+            }],
+        }).unwrap();
+        test_env.contract_oracle_call(alice(), unit_price_data(0, None, None), msg);
+        let position = test_env.contract.internal_unwrap_margin_account(&alice()).margin_positions.get(&pos_id).unwrap();
+        assert_eq!(position.token_p_amount, 0);
+        assert_eq!(position.token_c_shares.0, token_c_shares - d(12, 18));
+
+        // The ref-v1 leg returns its output, while the DCL leg fails and refunds the 200 USDT.
+        let swap_ref = SwapReference {
+            amount_in: U128(d(210, 18)),
+            op: "decrease".to_string(),
+            leg: Some(0),
+            ..swap_ref
+        };
+        test_env.contract_ft_transfer_call(ndai_token_id(), ref_exchange_id(), U128(d(98, 17)), swap_ref.to_msg_string());
+        testing_env!(
+            test_env.context.predecessor_account_id(env::current_account_id()).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![
+                near_sdk::PromiseResult::Successful(serde_json::to_vec(&U128(d(10, 6))).unwrap()),
+                near_sdk::PromiseResult::Successful(serde_json::to_vec(&U128(0)).unwrap()),
+            ]
+        );
+        test_env.contract.callback_dex_split_trade(alice(), pos_id.clone());
+        assert!(pending_split_swaps().get(&pos_id).is_none());
+
+        // The position gets back its 198 USDT, and the other 2 USDT go back to the collateral.
+        let position = test_env.contract.internal_unwrap_margin_account(&alice()).margin_positions.get(&pos_id).unwrap();
+        assert!(!position.is_locking);
+        assert_eq!(position.token_p_amount, d(198, 18));
+        assert_eq!(position.token_c_shares.0, token_c_shares - d(10, 18));
+        assert_eq!(test_env.contract.internal_unwrap_asset(&nusdt_token_id()).margin_position, d(198, 18));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_return_reserve_contributions() {
+        let mut test_env = init_unit_env();
+        let reserved = test_env.contract.internal_unwrap_asset(&ndai_token_id()).reserved;
+        let msg = serde_json::to_string(&TokenReceiverMsg::DepositToReserveFor {
+            purpose: ReserveContributionPurpose::BadDebtBackstop,
+        }).unwrap();
+        test_env.contract_ft_transfer_call(ndai_token_id(), alice(), U128(d(30, 18)), msg.clone());
+        test_env.contract_ft_transfer_call(ndai_token_id(), bob(), U128(d(10, 18)), msg);
+        test_env.deposit_to_reserve(ndai_token_id(), alice(), d(5, 18));
+        test_env.deposit_to_reserve(ndai_token_id(), "unregistered.near".parse().unwrap(), d(5, 18));
+        assert_eq!(test_env.contract.internal_unwrap_asset(&ndai_token_id()).reserved, reserved + d(50, 18));
+        assert_eq!(
+            test_env.contract.get_reserve_contributions(ndai_token_id(), ReserveContributionPurpose::BadDebtBackstop, None, None),
+            vec![
+                ReserveContribution {
+                    token_id: ndai_token_id(),
+                    purpose: ReserveContributionPurpose::BadDebtBackstop,
+                    contributor_id: alice(),
+                    amount: U128(d(30, 18)),
+                },
+                ReserveContribution {
+                    token_id: ndai_token_id(),
+                    purpose: ReserveContributionPurpose::BadDebtBackstop,
+                    contributor_id: bob(),
+                    amount: U128(d(10, 18)),
+                },
+            ]
+        );
+        assert_eq!(
+            test_env.contract.get_reserve_contribution(ndai_token_id(), ReserveContributionPurpose::General, alice()),
+            U128(d(5, 18))
+        );
+        assert_eq!(
+            test_env.contract.get_reserve_contributions_total(ndai_token_id(), ReserveContributionPurpose::BadDebtBackstop),
+            U128(d(40, 18))
+        );
+
+        // The contributions are returned one contributor at a time.
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        assert!(!test_env.contract.return_reserve_contributions(
+            ndai_token_id(),
+            ReserveContributionPurpose::BadDebtBackstop,
+            Some(U128(d(20, 18))),
+            Some(1),
+        ));
+        assert_eq!(test_env.contract.get_account(alice()).unwrap().supplied[0].balance, d(15, 18));
+        let msg = serde_json::to_string(&TokenReceiverMsg::DepositToReserveFor {
+            purpose: ReserveContributionPurpose::BadDebtBackstop,
+        }).unwrap();
+        test_env.contract_ft_transfer_call(ndai_token_id(), alice(), U128(d(10, 18)), msg);
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        assert!(test_env.contract.return_reserve_contributions(
+            ndai_token_id(),
+            ReserveContributionPurpose::BadDebtBackstop,
+            None,
+            Some(1),
+        ));
+        assert_eq!(test_env.contract.internal_unwrap_asset(&ndai_token_id()).reserved, reserved + d(40, 18));
+        assert_eq!(test_env.contract.get_account(alice()).unwrap().supplied[0].balance, d(15, 18));
+        assert_eq!(test_env.contract.get_account(bob()).unwrap().supplied[0].balance, d(5, 18));
+        assert!(test_env.contract
+            .get_reserve_contributions(ndai_token_id(), ReserveContributionPurpose::BadDebtBackstop, None, None)
+            .is_empty());
+        assert_eq!(
+            test_env.contract.get_reserve_contributions_total(ndai_token_id(), ReserveContributionPurpose::BadDebtBackstop),
+            U128(0)
+        );
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_reserve_contribution_min_amount(ndai_token_id(), U128(d(10, 18)));
+        test_env.deposit_to_reserve(ndai_token_id(), bob(), d(5, 18));
+        assert_eq!(test_env.contract.get_reserve_contributions(ndai_token_id(), ReserveContributionPurpose::General, None, None).len(), 1);
+        assert_eq!(
+            test_env.contract.get_reserve_contributions_total(ndai_token_id(), ReserveContributionPurpose::General),
+            U128(d(5, 18))
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_deposit_to_reserve_without_storage() {
+        let mut test_env = init_unit_env();
+        let mut storage = test_env.contract.internal_unwrap_storage(&alice());
+        storage.storage_balance = Balance::from(storage.used_bytes) * env::storage_byte_cost();
+        test_env.contract.internal_set_storage(&alice(), storage);
+        let reserved = test_env.contract.internal_unwrap_asset(&ndai_token_id()).reserved;
+
+        test_env.deposit_to_reserve(ndai_token_id(), alice(), d(5, 18));
+        assert_eq!(test_env.contract.internal_unwrap_asset(&ndai_token_id()).reserved, reserved + d(5, 18));
+        assert_eq!(
+            test_env.contract.get_reserve_contribution(ndai_token_id(), ReserveContributionPurpose::General, alice()),
+            U128(0)
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_blocklist() {
+        let mut test_env = init_unit_env();
+        test_env.supply_to_collateral(wnear_token_id(), alice(), d(100, 24));
+        test_env.borrow(alice(), ndai_token_id(), d(200, 18), unit_price_data(0, Some(100000), None));
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.extend_blocklist(vec![alice()]);
+        assert_eq!(test_env.contract.get_blocklist(None, None), vec![alice()]);
+        assert!(test_env.contract.is_blocked(alice()));
+
+        test_env.contract_ft_transfer_call(ndai_token_id(), alice(), U128(d(50, 18)), "\"OnlyRepay\"".to_string());
+        assert_eq!(test_env.contract.get_account(alice()).unwrap().borrowed[0].balance, d(150, 18));
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.execute(vec![Action::Withdraw(AssetAmount {
+            token_id: ndai_token_id(),
+            amount: Some(U128(d(100, 18))),
+            max_amount: None,
+        })]);
+        assert_eq!(test_env.contract.get_account(alice()).unwrap().supplied[0].balance, d(100, 18));
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.remove_blocklist(vec![alice()]);
+        test_env.borrow(alice(), ndai_token_id(), d(10, 18), unit_price_data(0, Some(100000), None));
+        assert_eq!(test_env.contract.get_account(alice()).unwrap().borrowed[0].balance, d(160, 18));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_blocklist_with_margin_base_token_limit() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        let mbtl = test_env.contract.get_default_margin_base_token_limit();
+        test_env.contract.set_margin_base_token_limit(ndai_token_id(), mbtl);
+        test_env.deposit(ndai_token_id(), alice(), d(100, 18));
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.extend_blocklist(vec![bob()]);
+        test_env.deposit(ndai_token_id(), alice(), d(100, 18));
+        assert_eq!(test_env.contract.get_blocklist(None, None), vec![bob()]);
+        assert_eq!(test_env.contract.get_margin_base_token_limit_paged(None, None).len(), 1);
+        assert!(test_env.contract.is_blocked(bob()));
+        assert!(!test_env.contract.is_blocked(alice()));
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "alice is blocked from Deposit")]
+    fn test_blocklist_deposit() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.extend_blocklist(vec![alice()]);
+        test_env.deposit(ndai_token_id(), alice(), d(100, 18));
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "alice is blocked from Borrow")]
+    fn test_blocklist_borrow() {
+        let mut test_env = init_unit_env();
+        test_env.supply_to_collateral(wnear_token_id(), alice(), d(100, 24));
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.extend_blocklist(vec![alice()]);
+        test_env.borrow(alice(), ndai_token_id(), d(200, 18), unit_price_data(0, Some(100000), None));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_check_state_migration() {
+        let mut test_env = init_unit_env();
+        test_env.supply_to_collateral(wnear_token_id(), alice(), d(100, 24));
+        let report = test_env.contract.check_state_migration(None, None);
+        assert!(report.failures.is_empty());
+        assert!(report.incompatible_schemas.is_empty());
+        assert_eq!(report.num_accounts, test_env.contract.accounts.len());
+        assert_eq!(report.num_assets, test_env.contract.asset_ids.len());
+        assert_eq!(test_env.contract.get_state_schema_fingerprints(), current_state_schema_fingerprints());
+        // The fingerprint covers the nested structs, e.g. BoosterDelegation of Account.
+        let account_schema = STATE_SCHEMAS.iter().find(|schema| schema.name == "Account").unwrap();
+        let account_fields = account_schema.fields.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert_ne!(account_schema.fingerprint().fingerprint, hex::encode(env::sha256(account_fields.as_bytes())));
+
+        let index = test_env.contract.accounts.keys_as_vector().iter().position(|account_id| account_id == alice()).unwrap() as u64;
+        let key = [near_sdk::IntoStorageKey::into_storage_key(StorageKey::Accounts), b"v".to_vec(), index.to_le_bytes().to_vec()].concat();
+        let mut raw = env::storage_read(&key).unwrap();
+        raw.push(0);
+        env::storage_write(&key, &raw);
+        let report = test_env.contract.check_state_migration(None, None);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].collection, "accounts");
+        assert_eq!(report.failures[0].key, alice().to_string());
+
+        let mut fingerprints = current_state_schema_fingerprints();
+        fingerprints.get_mut("Account").unwrap().fingerprint = "".to_string();
+        write_state_schema_fingerprints_to_storage(fingerprints.clone());
+        assert_eq!(incompatible_state_schemas(), vec!["Account".to_string()]);
+        fingerprints.get_mut("Account").unwrap().version -= 1;
+        write_state_schema_fingerprints_to_storage(fingerprints);
+        assert!(incompatible_state_schemas().is_empty());
+        internal_migrate_state_schemas();
+        assert_eq!(test_env.contract.get_state_schema_fingerprints(), current_state_schema_fingerprints());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_migrate_accounts() {
+        let mut test_env = init_unit_env();
+        test_env.supply_to_collateral(wnear_token_id(), alice(), d(100, 24));
+        let account = test_env.contract.internal_unwrap_account(&alice());
+        test_env.contract.accounts.insert(&alice(), &VAccount::V4(AccountV4 {
+            account_id: alice(),
+            supplied: account.supplied.clone(),
+            positions: account.positions.clone(),
+            farms: account.farms.clone(),
+            affected_farms: Default::default(),
+            storage_tracker: Default::default(),
+            booster_staking: None,
+            booster_stakings: HashMap::new(),
+            is_locked: false,
+        }));
+        let report = test_env.contract.check_state_migration(None, Some(100));
+        assert_eq!(report.num_legacy_accounts, 1);
+        assert!(report.failures.is_empty());
+
+        // The account is skipped while its storage balance doesn't cover the storage increase.
+        let mut storage = test_env.contract.internal_unwrap_storage(&alice());
+        let storage_balance = storage.storage_balance;
+        storage.storage_balance = Balance::from(storage.used_bytes) * env::storage_byte_cost();
+        test_env.contract.internal_set_storage(&alice(), storage);
+        let skipped = test_env.contract.migrate_accounts(None, Some(100));
+        assert_eq!(skipped.num_migrated_accounts, 0);
+        assert_eq!(skipped.num_skipped_accounts, 1);
+        assert_eq!(test_env.contract.check_state_migration(None, Some(100)).num_legacy_accounts, 1);
+        let mut storage = test_env.contract.internal_unwrap_storage(&alice());
+        storage.storage_balance = storage_balance;
+        let used_bytes = storage.used_bytes;
+        test_env.contract.internal_set_storage(&alice(), storage);
+
+        let first_page = test_env.contract.migrate_accounts(Some(0), Some(1));
+        assert_eq!(first_page.next_index, Some(1));
+        let rest = test_env.contract.migrate_accounts(first_page.next_index, Some(100));
+        assert_eq!(first_page.num_migrated_accounts + rest.num_migrated_accounts, 1);
+        assert_eq!(first_page.num_migrated_assets + rest.num_migrated_assets, 0);
+        assert_eq!(rest.next_index, None);
+        let report = test_env.contract.check_state_migration(None, Some(100));
+        assert_eq!(report.num_legacy_accounts, 0);
+        assert!(test_env.contract.internal_unwrap_storage(&alice()).used_bytes > used_bytes);
+        assert_eq!(
+            test_env.contract.internal_unwrap_account(&alice()).positions.try_to_vec().unwrap(),
+            account.positions.try_to_vec().unwrap()
+        );
     }
 
 }
@@ -1457,6 +2600,430 @@ mod farms {
         clean_assets_farm_cache();
     }
 
+    #[test]
+    #[ignore]
+    fn test_farm_supplied_scheduled() {
+        let mut test_env = init_unit_env();
+        test_env.skip_time_to_by_sec(10);
+
+        let reward_per_day = d(100, 18);
+        let total_reward = d(3000, 18);
+        let sec_to_ts = |sec: u32| sec as u64 * 10u64.pow(9);
+
+        let farm_id = FarmId::Supplied(ndai_token_id());
+        let reserved = test_env.get_asset(booster_token_id()).reserved;
+        test_env.schedule_farm(
+            farm_id.clone(), booster_token_id(), reward_per_day, d(100, 18), total_reward,
+            sec_to_ts(10 + ONE_DAY_SEC), Some(sec_to_ts(10 + ONE_DAY_SEC * 3))
+        );
+        let programs = test_env.contract.get_asset_farm_reward_programs(farm_id.clone());
+        assert_eq!(programs.len(), 1);
+        assert_eq!(programs[0].status, AssetFarmRewardStatus::Scheduled);
+
+        let amount = d(100, 18);
+        test_env.deposit(ndai_token_id(), alice(), amount);
+
+        // Nothing is distributed before start_timestamp.
+        test_env.skip_time_to_by_sec(10 + ONE_DAY_SEC);
+        clean_assets_farm_cache();
+        let account = test_env.contract.get_account(alice()).unwrap();
+        assert_eq!(account.farms[0].rewards[0].unclaimed_amount, 0);
+        let programs = test_env.contract.get_asset_farm_reward_programs(farm_id.clone());
+        assert_eq!(programs[0].status, AssetFarmRewardStatus::Active);
+
+        test_env.skip_time_to_by_sec(10 + ONE_DAY_SEC * 2);
+        clean_assets_farm_cache();
+        let account = test_env.contract.get_account(alice()).unwrap();
+        assert_eq!(account.farms[0].rewards[0].unclaimed_amount, reward_per_day);
+
+        // Nothing is distributed after end_timestamp.
+        test_env.skip_time_to_by_sec(10 + ONE_DAY_SEC * 5);
+        clean_assets_farm_cache();
+        let account = test_env.contract.get_account(alice()).unwrap();
+        assert_eq!(account.farms[0].rewards[0].unclaimed_amount, reward_per_day * 2);
+        let programs = test_env.contract.get_asset_farm_reward_programs(farm_id.clone());
+        assert_eq!(programs[0].status, AssetFarmRewardStatus::Finished);
+        assert_eq!(programs[0].asset_farm_reward.remaining_rewards, total_reward - reward_per_day * 2);
+
+        test_env.account_farm_claim_all(alice());
+        let asset = test_env.get_asset(booster_token_id());
+        assert_eq!(asset.supplied.balance, reward_per_day * 2);
+
+        // The undistributed rewards are returned to the reserve.
+        assert_eq!(asset.reserved, reserved - reward_per_day * 2);
+        clean_assets_farm_cache();
+        let programs = test_env.contract.get_asset_farm_reward_programs(farm_id);
+        assert_eq!(programs.len(), 1);
+        assert_eq!(programs[0].status, AssetFarmRewardStatus::Finished);
+        assert_eq!(programs[0].asset_farm_reward.remaining_rewards, 0);
+        clean_assets_cache();
+        clean_assets_farm_cache();
+    }
+
+    #[test]
+    #[ignore]
+    fn test_farm_reward_vesting() {
+        let mut test_env = init_unit_env();
+        test_env.skip_time_to_by_sec(10);
+
+        let reward_per_day = d(100, 18);
+        let total_reward = d(3000, 18);
+
+        let farm_id = FarmId::Supplied(ndai_token_id());
+        test_env.add_farm(farm_id, booster_token_id(), reward_per_day, d(100, 18), total_reward);
+        test_env.set_reward_vesting_config(booster_token_id(), RewardVestingConfig {
+            vesting_duration_sec: ONE_DAY_SEC * 2,
+            early_exit_penalty_rate: 5000,
+            penalty_receiver: VestingPenaltyReceiver::Reserve,
+        });
+
+        let amount = d(100, 18);
+        test_env.deposit(ndai_token_id(), alice(), amount);
+
+        // Claimed rewards enter the vesting instead of the supplied balance.
+        test_env.skip_time_to_by_sec(10 + ONE_DAY_SEC);
+        test_env.account_farm_claim_all(alice());
+        let asset = test_env.get_asset(booster_token_id());
+        assert_eq!(asset.supplied.balance, 0);
+        let vestings = test_env.contract.get_reward_vestings(alice());
+        assert_eq!(vestings.len(), 1);
+        assert_eq!(vestings[0].unvested_amount, reward_per_day);
+        assert_eq!(vestings[0].claimable_amount, 0);
+
+        test_env.skip_time_to_by_sec(10 + ONE_DAY_SEC * 2);
+        let vestings = test_env.contract.get_reward_vestings(alice());
+        assert_eq!(vestings[0].vested_amount, reward_per_day / 2);
+        assert_eq!(vestings[0].unvested_amount, reward_per_day / 2);
+        assert_eq!(vestings[0].claimable_amount, reward_per_day / 2);
+
+        test_env.claim_vested_rewards(alice(), None);
+        let asset = test_env.get_asset(booster_token_id());
+        assert_eq!(asset.supplied.balance, reward_per_day / 2);
+        let vestings = test_env.contract.get_reward_vestings(alice());
+        assert_eq!(vestings[0].claimable_amount, 0);
+
+        // Half of the unvested amount goes to the reserve on an early exit.
+        let reserved = asset.reserved;
+        test_env.exit_reward_vesting(alice(), booster_token_id());
+        let asset = test_env.get_asset(booster_token_id());
+        assert_eq!(asset.supplied.balance, reward_per_day / 2 + reward_per_day / 4);
+        assert_eq!(asset.reserved, reserved + reward_per_day / 4);
+        assert!(test_env.contract.get_reward_vestings(alice()).is_empty());
+        clean_assets_cache();
+        clean_assets_farm_cache();
+    }
+
+    #[test]
+    #[ignore]
+    fn test_farm_auto_compound_collateral() {
+        let mut test_env = init_unit_env();
+        test_env.skip_time_to_by_sec(10);
+
+        let reward_per_day = d(100, 18);
+        let total_reward = d(3000, 18);
+
+        let farm_id = FarmId::Supplied(ndai_token_id());
+        test_env.deposit_to_reserve(ndai_token_id(), owner_id(), total_reward);
+        test_env.add_farm(farm_id.clone(), ndai_token_id(), reward_per_day, d(100, 18), total_reward);
+        test_env.add_farm(farm_id, booster_token_id(), reward_per_day, d(100, 18), total_reward);
+
+        let amount = d(100, 18);
+        test_env.deposit(ndai_token_id(), alice(), amount);
+        test_env.set_auto_compound(alice(), Some(AutoCompound::Collateral));
+        assert_eq!(test_env.contract.get_auto_compound(alice()), Some(AutoCompound::Collateral));
+
+        test_env.skip_time_to_by_sec(10 + ONE_DAY_SEC);
+        test_env.account_farm_claim_all(alice());
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        let account = test_env.contract.get_account(alice()).unwrap();
+        // The booster token can't be used as a collateral, so it stays supplied.
+        assert_balances(&account.supplied, &[av(ndai_token_id(), amount), av(booster_token_id(), reward_per_day)]);
+        assert_balances(&account.collateral, &[av(ndai_token_id(), reward_per_day)]);
+
+        // The reward that would exceed the supply limit is kept supplied.
+        let supplied_balance = test_env.get_asset(ndai_token_id()).supplied.balance;
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.update_asset_limit(ndai_token_id(), Some(U128(supplied_balance)), Some(U128(supplied_balance)), None);
+        test_env.skip_time_to_by_sec(10 + ONE_DAY_SEC * 2);
+        test_env.account_farm_claim_all(alice());
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        let account = test_env.contract.get_account(alice()).unwrap();
+        assert_eq!(find_asset(&account.supplied, &ndai_token_id()).balance, amount + reward_per_day);
+        assert_balances(&account.collateral, &[av(ndai_token_id(), reward_per_day)]);
+        clean_assets_cache();
+        clean_assets_farm_cache();
+    }
+
+    #[test]
+    #[ignore]
+    fn test_farm_supplied_xbooster_delegation() {
+        let mut test_env = init_unit_env();
+        test_env.skip_time_to_by_sec(10);
+
+        let reward_per_day = d(100, 18);
+        let total_reward = d(40000, 18);
+        let booster_base = d(20, 18);
+
+        test_env.deposit_to_reserve(nusdc_token_id(), owner_id(), d(40000, 6));
+        let farm_id = FarmId::Supplied(ndai_token_id());
+        test_env.add_farm(farm_id, nusdc_token_id(), reward_per_day, booster_base, total_reward);
+
+        let booster_amount = d(5, 18);
+        test_env.deposit(booster_token_id(), alice(), booster_amount);
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.account_stake_booster(booster_token_id(), Some(booster_amount.into()), MAX_DURATION_SEC);
+
+        let amount = d(100, 18);
+        test_env.deposit(ndai_token_id(), bob(), amount);
+        let account = test_env.contract.get_account(bob()).unwrap();
+        let shares = find_asset(&account.supplied, &ndai_token_id()).shares.0;
+        assert_eq!(account.farms[0].rewards[0].boosted_shares, shares);
+
+        // The whole xBooster of alice boosts farms of bob 2X.
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.delegate_booster(booster_token_id(), HashMap::from([(bob(), 10000)]));
+        let account = test_env.contract.get_account(bob()).unwrap();
+        assert_eq!(account.farms[0].rewards[0].boosted_shares, shares);
+        assert!(test_env.contract.get_account_booster_delegations(bob()).unwrap().received.is_empty());
+        testing_env!(test_env.context.predecessor_account_id(bob()).attached_deposit(1).build());
+        test_env.contract.accept_booster_delegation(booster_token_id(), alice());
+        let account = test_env.contract.get_account(bob()).unwrap();
+        assert_eq!(account.farms[0].rewards[0].boosted_shares, shares * 2);
+
+        let delegations = test_env.contract.get_account_booster_delegations(alice()).unwrap();
+        assert_eq!(delegations.delegated.len(), 1);
+        assert_eq!(delegations.delegated[0].delegate_id, bob());
+        assert_eq!(delegations.delegated[0].x_booster_amount, booster_amount * 4);
+        let delegations = test_env.contract.get_account_booster_delegations(bob()).unwrap();
+        assert_eq!(delegations.received.len(), 1);
+        assert_eq!(delegations.received[0].delegator_id, alice());
+
+        // Unstaking the xBooster of alice refreshes the farms of bob.
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        test_env.skip_time_to_by_sec(MAX_DURATION_SEC + 20);
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.account_unstake_booster(Some(booster_token_id()), None);
+        let account = test_env.contract.get_account(bob()).unwrap();
+        assert_eq!(account.farms[0].rewards[0].boosted_shares, shares);
+
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.revoke_booster_delegation(booster_token_id(), bob());
+        let account = test_env.contract.get_account(bob()).unwrap();
+        assert_eq!(account.farms[0].rewards[0].boosted_shares, shares);
+        assert!(test_env.contract.get_account_booster_delegations(bob()).unwrap().received.is_empty());
+        clean_assets_cache();
+        clean_assets_farm_cache();
+    }
+
+    #[test]
+    #[ignore]
+    fn test_gauge_voting() {
+        let mut test_env = init_unit_env();
+        test_env.skip_time_to_by_sec(10);
+
+        let epoch_duration_sec = ONE_DAY_SEC * 7;
+        let reward_amount_per_epoch = d(700, 18);
+        test_env.deposit_to_reserve(nusdc_token_id(), owner_id(), reward_amount_per_epoch);
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_gauge_config(GaugeConfig {
+            booster_token_id: booster_token_id(),
+            reward_token_id: nusdc_token_id(),
+            reward_amount_per_epoch: reward_amount_per_epoch.into(),
+            booster_log_bases: HashMap::new(),
+            farm_ids: vec![FarmId::Supplied(ndai_token_id()), FarmId::Supplied(nusdc_token_id())],
+            epoch_duration_sec,
+            start_timestamp: 0.into(),
+        });
+
+        let booster_amount = d(5, 18);
+        test_env.deposit(booster_token_id(), alice(), booster_amount);
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.account_stake_booster(booster_token_id(), Some(booster_amount.into()), MAX_DURATION_SEC);
+        assert_eq!(test_env.contract.get_account_voting_power(alice()).0, booster_amount);
+
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.vote_gauges(vec![
+            (FarmId::Supplied(ndai_token_id()), 7500),
+            (FarmId::Supplied(nusdc_token_id()), 2500),
+        ]);
+        let gauge_epoch = test_env.contract.get_gauge_epoch();
+        assert_eq!(gauge_epoch.epoch, 0);
+        let total_votes: Balance = gauge_epoch.votes.iter().map(|v| v.voting_power).sum();
+        assert_eq!(total_votes, booster_amount);
+
+        test_env.skip_time_to_by_sec(10 + epoch_duration_sec);
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        test_env.contract.apply_gauge_epoch();
+        let asset_farm = test_env.get_asset_farm(FarmId::Supplied(ndai_token_id()));
+        let reward = asset_farm.rewards.get(&nusdc_token_id()).unwrap();
+        assert_eq!(reward.remaining_rewards, reward_amount_per_epoch * 3 / 4);
+        assert_eq!(reward.reward_per_day, reward_amount_per_epoch * 3 / 4 / 7);
+        let asset_farm = test_env.get_asset_farm(FarmId::Supplied(nusdc_token_id()));
+        let reward = asset_farm.rewards.get(&nusdc_token_id()).unwrap();
+        assert_eq!(reward.remaining_rewards, reward_amount_per_epoch / 4);
+        let gauge_epoch = test_env.contract.get_gauge_epoch();
+        assert_eq!(gauge_epoch.epoch, 1);
+        assert!(gauge_epoch.votes.is_empty());
+
+        // The voting power decays linearly toward unlock.
+        test_env.skip_time_to_by_sec(10 + MAX_DURATION_SEC / 2);
+        assert_eq!(test_env.contract.get_account_voting_power(alice()).0, booster_amount / 2);
+
+        // The vote is removed from the epoch by an early unstake.
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_booster_early_unstake_config(booster_token_id(), Some(BoosterEarlyUnstakeConfig {
+            max_penalty_rate: 5000,
+            penalty_sink: BoosterPenaltySink::Reserve,
+        }));
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(0).build());
+        test_env.contract.apply_gauge_epoch();
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.vote_gauges(vec![(FarmId::Supplied(ndai_token_id()), 10000)]);
+        assert_eq!(test_env.contract.get_gauge_epoch().votes.len(), 1);
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.account_early_unstake_booster(booster_token_id());
+        assert!(test_env.contract.get_gauge_epoch().votes.is_empty());
+        assert!(test_env.contract.get_account_gauge_vote(alice()).is_none());
+        clean_assets_cache();
+        clean_assets_farm_cache();
+    }
+
+    #[test]
+    #[ignore]
+    #[should_panic(expected = "The reward is managed by the gauge")]
+    fn test_gauge_reward_not_added_manually() {
+        let mut test_env = init_unit_env();
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_gauge_config(GaugeConfig {
+            booster_token_id: booster_token_id(),
+            reward_token_id: nusdc_token_id(),
+            reward_amount_per_epoch: U128(d(700, 18)),
+            booster_log_bases: HashMap::new(),
+            farm_ids: vec![FarmId::Supplied(ndai_token_id())],
+            epoch_duration_sec: ONE_DAY_SEC * 7,
+            start_timestamp: 0.into(),
+        });
+        test_env.add_farm(FarmId::Supplied(ndai_token_id()), nusdc_token_id(), d(100, 18), d(20, 18), 0);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_farm_supplied_xbooster_early_unstake() {
+        let mut test_env = init_unit_env();
+        test_env.skip_time_to_by_sec(10);
+
+        let reward_per_day = d(1, 18);
+        let total_reward = d(3000, 18);
+        let booster_base = d(20, 18);
+
+        let farm_id = FarmId::Supplied(ndai_token_id());
+        test_env.add_farm(farm_id, nusdc_token_id(), reward_per_day, booster_base, total_reward);
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_booster_early_unstake_config(booster_token_id(), Some(BoosterEarlyUnstakeConfig {
+            max_penalty_rate: 5000,
+            penalty_sink: BoosterPenaltySink::Stakers,
+        }));
+
+        let booster_amount = d(4, 18);
+        for user in [alice(), bob()] {
+            test_env.deposit(booster_token_id(), user.clone(), booster_amount);
+            testing_env!(test_env.context.predecessor_account_id(user).attached_deposit(1).build());
+            test_env.contract.account_stake_booster(booster_token_id(), Some(booster_amount.into()), MAX_DURATION_SEC);
+        }
+        assert_eq!(test_env.contract.get_booster_early_unstake_penalty(alice(), booster_token_id()).0, booster_amount / 2);
+
+        let amount = d(100, 18);
+        test_env.deposit(ndai_token_id(), alice(), amount);
+        let account = test_env.contract.get_account(alice()).unwrap();
+        let shares = find_asset(&account.supplied, &ndai_token_id()).shares.0;
+        assert!(account.farms[0].rewards[0].boosted_shares > shares);
+
+        // The penalty is 25% of the staked amount in the middle of the lock.
+        test_env.skip_time_to_by_sec(10 + MAX_DURATION_SEC / 2);
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.account_early_unstake_booster(booster_token_id());
+        let account = test_env.contract.get_account(alice()).unwrap();
+        assert!(account.booster_stakings.is_empty());
+        assert_eq!(find_asset(&account.supplied, &booster_token_id()).balance, booster_amount * 3 / 4);
+        assert_eq!(account.farms[0].rewards[0].boosted_shares, shares);
+        let booster_token_info = test_env.contract.get_booster_tokens().remove(&booster_token_id()).unwrap();
+        assert_eq!(booster_token_info.total_stake_amount, booster_amount);
+
+        // The penalty is shared by the remaining stakers.
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        testing_env!(test_env.context.predecessor_account_id(bob()).attached_deposit(0).build());
+        test_env.contract.account_claim_booster_penalty_rewards(booster_token_id());
+        let account = test_env.contract.get_account(bob()).unwrap();
+        assert_eq!(find_asset(&account.supplied, &booster_token_id()).balance, booster_amount / 4);
+        clean_assets_cache();
+        clean_assets_farm_cache();
+    }
+
+    #[test]
+    #[ignore]
+    fn test_early_unstake_burn_and_delegates() {
+        let mut test_env = init_unit_env();
+        test_env.skip_time_to_by_sec(10);
+
+        let farm_id = FarmId::Supplied(ndai_token_id());
+        test_env.add_farm(farm_id, nusdc_token_id(), d(1, 18), d(20, 18), d(3000, 18));
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.set_booster_early_unstake_config(booster_token_id(), Some(BoosterEarlyUnstakeConfig {
+            max_penalty_rate: 5000,
+            penalty_sink: BoosterPenaltySink::Burn,
+        }));
+
+        let booster_amount = d(4, 18);
+        test_env.deposit(booster_token_id(), alice(), booster_amount);
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.account_stake_booster(booster_token_id(), Some(booster_amount.into()), MAX_DURATION_SEC);
+
+        test_env.deposit(ndai_token_id(), bob(), d(100, 18));
+        let account = test_env.contract.get_account(bob()).unwrap();
+        let shares = find_asset(&account.supplied, &ndai_token_id()).shares.0;
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.delegate_booster(booster_token_id(), HashMap::from([(bob(), 10000)]));
+        testing_env!(test_env.context.predecessor_account_id(bob()).attached_deposit(1).build());
+        test_env.contract.accept_booster_delegation(booster_token_id(), alice());
+        let account = test_env.contract.get_account(bob()).unwrap();
+        assert!(account.farms[0].rewards[0].boosted_shares > shares);
+
+        // The farms of bob no longer use the xBooster of alice, and the penalty is burned.
+        let reserved = test_env.get_asset(booster_token_id()).reserved;
+        test_env.skip_time_to_by_sec(10 + MAX_DURATION_SEC / 2);
+        clean_assets_cache();
+        clean_assets_farm_cache();
+        testing_env!(test_env.context.predecessor_account_id(alice()).attached_deposit(1).build());
+        test_env.contract.account_early_unstake_booster(booster_token_id());
+        let account = test_env.contract.get_account(bob()).unwrap();
+        assert_eq!(account.farms[0].rewards[0].boosted_shares, shares);
+        assert_eq!(test_env.get_asset(booster_token_id()).reserved, reserved);
+        assert_eq!(
+            test_env.contract.get_booster_burned_penalties(),
+            HashMap::from([(booster_token_id(), U128(booster_amount / 4))])
+        );
+        clean_assets_cache();
+        clean_assets_farm_cache();
+    }
+
     #[test]
     #[ignore]
     fn test_has_potential_farms() {
@@ -2726,6 +4293,25 @@ mod liquidation {
         );
     }
 
+    #[test]
+    #[ignore]
+    fn test_liquidation_while_execute_paused() {
+        let mut test_env = init_unit_env();
+        test_env.skip_time_to_by_sec(10);
+        test_env.supply_to_collateral(nusdc_token_id(), alice(), (d(1000, 18) / d(1, 12)).into());
+        test_env.borrow_and_withdraw(alice(), wnear_token_id(), d(50, 24), unit_price_data(10, Some(100000), None));
+        test_env.deposit(wnear_token_id(), bob(), d(100, 24));
+
+        testing_env!(test_env.context.predecessor_account_id(owner_id()).attached_deposit(1).build());
+        test_env.contract.pause(PauseKind::Execute, None);
+        test_env.liquidate(
+            bob(), alice(), unit_price_data(10, Some(120000), None),
+            vec![asset_amount(wnear_token_id(), d(49, 23))], vec![asset_amount(nusdc_token_id(), d(60, 18))],
+        );
+        let account = test_env.contract.get_account(alice()).unwrap();
+        assert_balances(&account.borrowed, &[av(wnear_token_id(), d(50, 24) - d(49, 23))]);
+    }
+
     /// Bob attemps to liquidate Alice which decreases health factor.
     #[test]
     #[ignore]
@@ -2931,7 +4517,7 @@ mod liquidation {
             booster_staking: None,
         }));
         let account = test_env.contract.internal_unwrap_account(&AccountId::new_unchecked("storage".to_string()));
-        test_env.contract.storage.insert(&AccountId::new_unchecked("storage".to_string()), &VStorage::Current(Storage { storage_balance: 10u128.pow(25), used_bytes: 1000, storage_tracker: Default::default() }));
+        test_env.contract.storage.insert(&AccountId::new_unchecked("storage".to_string()), &VStorage::Current(Storage { storage_balance: 10u128.pow(25), used_bytes: 1000, sponsor_id: None, sponsored_balance: 0, storage_tracker: Default::default() }));
         let _tmp_account = account.clone();
         test_env.contract.internal_set_account(&AccountId::new_unchecked("storage".to_string()), account);
     }
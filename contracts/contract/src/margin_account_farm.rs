@@ -0,0 +1,180 @@
+use crate::*;
+
+impl MarginAccount {
+    pub fn add_affected_farm(&mut self, farm_id: FarmId) -> bool {
+        self.affected_farms.insert(farm_id)
+    }
+
+    /// Marks margin farms of the given position as affected.
+    pub fn add_affected_position_farms(&mut self, mt: &MarginTradingPosition) {
+        self.add_affected_farm(FarmId::MarginDebt(mt.token_d_id.clone()));
+        self.add_affected_farm(FarmId::MarginPosition(mt.token_p_id.clone()));
+    }
+
+    /// Returns all margin farms that can be potentially farmed.
+    pub fn get_all_potential_farms(&self) -> HashSet<FarmId> {
+        let mut potential_farms: HashSet<FarmId> = self.farms.keys().cloned().collect();
+        for mt in self.margin_positions.values() {
+            potential_farms.insert(FarmId::MarginDebt(mt.token_d_id.clone()));
+            potential_farms.insert(FarmId::MarginPosition(mt.token_p_id.clone()));
+        }
+        potential_farms
+    }
+
+    /// Returns farm shares of the given margin farm,
+    /// which are margin debt shares for MarginDebt and position amount for MarginPosition.
+    pub fn get_margin_farm_shares(&self, farm_id: &FarmId) -> Balance {
+        match farm_id {
+            FarmId::MarginDebt(token_id) => self
+                .margin_positions
+                .values()
+                .filter(|mt| &mt.token_d_id == token_id)
+                .map(|mt| mt.token_d_shares.0)
+                .sum(),
+            FarmId::MarginPosition(token_id) => self
+                .margin_positions
+                .values()
+                .filter(|mt| &mt.token_p_id == token_id)
+                .map(|mt| mt.token_p_amount)
+                .sum(),
+            _ => 0,
+        }
+    }
+}
+
+impl Contract {
+    /// Claims affected margin farms and updates their shares.
+    /// Rewards are kept in unclaimed_farm_rewards until `margin_account_farm_claim_all`,
+    /// as the regular account might be held by the caller at this moment.
+    pub fn internal_margin_account_apply_affected_farms(&mut self, margin_account: &mut MarginAccount) {
+        if margin_account.affected_farms.is_empty() {
+            return;
+        }
+        let mut farms = vec![];
+        let farm_ids: Vec<FarmId> = margin_account.affected_farms.drain().collect();
+        for farm_id in farm_ids {
+            if let Some(asset_farm) = self.internal_get_asset_farm(&farm_id, false) {
+                let (account_farm, new_rewards, inactive_rewards) =
+                    self.internal_farm_claim(margin_account.farms.get(&farm_id), &asset_farm);
+                for (token_id, amount) in new_rewards {
+                    *margin_account.unclaimed_farm_rewards.entry(token_id).or_default() += amount;
+                }
+                farms.push((farm_id, account_farm, asset_farm, inactive_rewards));
+            }
+        }
+        if farms.is_empty() {
+            return;
+        }
+
+        // Margin farms are boosted by the booster stakings of the regular account.
+        let booster_tokens = read_booster_tokens_from_storage();
+        let mut account = self.internal_get_account(&margin_account.account_id, true)
+            .expect("Account is not registered");
+        account.sync_booster_policy(&booster_tokens);
+        self.internal_sync_received_x_booster_amounts(&mut account, &booster_tokens);
+
+        for (farm_id, mut account_farm, mut asset_farm, inactive_rewards) in farms {
+            let shares = if self.blacklist_of_farmers.contains(&margin_account.account_id) {
+                0
+            } else {
+                margin_account.get_margin_farm_shares(&farm_id)
+            };
+            for (token_id, asset_farm_reward) in asset_farm.rewards.iter_mut() {
+                let account_farm_reward = account_farm.rewards.get_mut(token_id).unwrap();
+                asset_farm_reward.boosted_shares -= account_farm_reward.boosted_shares;
+                if shares > 0 {
+                    let extra_shares = get_booster_extra_shares(&account, shares, asset_farm_reward, &booster_tokens);
+                    account_farm_reward.boosted_shares = shares + extra_shares;
+                    asset_farm_reward.boosted_shares += account_farm_reward.boosted_shares;
+                }
+            }
+            for (token_id, boosted_shares) in inactive_rewards {
+                let mut asset_farm_reward = asset_farm
+                    .internal_get_inactive_asset_farm_reward(&token_id)
+                    .unwrap();
+                asset_farm_reward.boosted_shares -= boosted_shares;
+                asset_farm.internal_set_inactive_asset_farm_reward(&token_id, asset_farm_reward);
+            }
+            self.internal_set_asset_farm(&farm_id, asset_farm);
+            if shares > 0 {
+                margin_account.farms.insert(farm_id, account_farm);
+            } else {
+                margin_account.farms.remove(&farm_id);
+            }
+        }
+    }
+
+    pub fn margin_account_farms_into_view(&self, margin_account: &MarginAccount) -> Vec<AccountFarmView> {
+        margin_account
+            .farms
+            .iter()
+            .map(|(farm_id, account_farm)| {
+                let mut asset_farm = self.internal_unwrap_asset_farm(farm_id, true);
+                let (account_farm, new_rewards, inactive_rewards) =
+                    self.internal_farm_claim(Some(account_farm), &asset_farm);
+                AccountFarmView {
+                    farm_id: farm_id.clone(),
+                    rewards: account_farm
+                        .rewards
+                        .into_iter()
+                        .map(|(token_id, AccountFarmReward { boosted_shares, .. })| {
+                            (token_id, boosted_shares)
+                        })
+                        .chain(inactive_rewards)
+                        .map(|(reward_token_id, boosted_shares)| {
+                            let asset_farm_reward = asset_farm
+                                .rewards
+                                .remove(&reward_token_id)
+                                .or_else(|| {
+                                    asset_farm
+                                        .internal_get_inactive_asset_farm_reward(&reward_token_id)
+                                })
+                                .unwrap();
+                            let unclaimed_amount = new_rewards
+                                .iter()
+                                .find(|(token_id, _)| token_id == &reward_token_id)
+                                .map(|(_, amount)| *amount)
+                                .unwrap_or(0);
+                            AccountFarmRewardView {
+                                reward_token_id,
+                                asset_farm_reward,
+                                boosted_shares,
+                                unclaimed_amount,
+                            }
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Claims all unclaimed margin farm rewards into the supplied balance of the regular account
+    /// and starts farming new margin farms.
+    /// If the account_id is given, then it claims farms for the given account_id or uses
+    /// predecessor_account_id otherwise.
+    pub fn margin_account_farm_claim_all(&mut self, account_id: Option<AccountId>) {
+        assert_not_paused(&read_pause_registry_from_storage(), PauseKind::FarmClaim, &[]);
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let mut margin_account = self.internal_unwrap_margin_account(&account_id);
+        let potential_farms = margin_account.get_all_potential_farms();
+        margin_account.affected_farms.extend(potential_farms);
+        self.internal_margin_account_apply_affected_farms(&mut margin_account);
+        let rewards = std::mem::take(&mut margin_account.unclaimed_farm_rewards);
+        self.internal_set_margin_account(&account_id, margin_account);
+
+        if !rewards.is_empty() {
+            let mut account = self.internal_unwrap_account(&account_id);
+            let reward_vesting_configs = read_reward_vesting_configs_from_storage();
+            for (token_id, amount) in rewards {
+                self.internal_deposit_farm_reward(&mut account, &reward_vesting_configs, &token_id, amount);
+                account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+                account.add_affected_farm(FarmId::TokenNetBalance(token_id));
+            }
+            self.internal_account_apply_affected_farms(&mut account);
+            self.internal_set_account(&account_id, account);
+        }
+    }
+}
@@ -25,6 +25,12 @@ pub struct MarginAccount {
     pub position_latest_actions: HashMap<PosId, U64>,
     // margin stops
     pub stops: HashMap<PosId, MarginStop>,
+    /// Keeping track of data required for margin farms for this account.
+    pub farms: HashMap<FarmId, AccountFarm>,
+    #[borsh_skip]
+    pub affected_farms: HashSet<FarmId>,
+    /// Farm rewards collected from margin farms, waiting to be claimed into the regular account.
+    pub unclaimed_farm_rewards: HashMap<TokenId, Balance>,
 
     /// Tracks changes in storage usage by persistent collections in this account.
     #[borsh_skip]
@@ -35,6 +41,7 @@ pub struct MarginAccount {
 pub enum VMarginAccount {
     V0(MarginAccountV0),
     V1(MarginAccountV1),
+    V2(MarginAccountV2),
     Current(MarginAccount),
 }
 
@@ -49,6 +56,7 @@ impl From<VMarginAccount> for MarginAccount {
         match c {
             VMarginAccount::V0(c) => c.into(),
             VMarginAccount::V1(c) => c.into(),
+            VMarginAccount::V2(c) => c.into(),
             VMarginAccount::Current(c) => c,
         }
     }
@@ -64,6 +72,9 @@ impl MarginAccount {
             }),
             position_latest_actions: HashMap::new(),
             stops: HashMap::new(),
+            farms: HashMap::new(),
+            affected_farms: HashSet::new(),
+            unclaimed_farm_rewards: HashMap::new(),
             storage_tracker: Default::default(),
         }
     }
@@ -109,6 +120,7 @@ impl Contract {
     }
 
     pub(crate) fn internal_set_margin_account(&mut self, account_id: &AccountId, mut account: MarginAccount) {
+        self.internal_margin_account_apply_affected_farms(&mut account);
         let mut storage = self.internal_unwrap_storage(account_id);
         storage
             .storage_tracker
@@ -122,6 +134,7 @@ impl Contract {
     /// Force set margin account state without storage coverage check.
     /// Used in critical async callbacks to prevent permanent state inconsistency.
     pub(crate) fn internal_force_set_margin_account(&mut self, account_id: &AccountId, mut account: MarginAccount) {
+        self.internal_margin_account_apply_affected_farms(&mut account);
         let mut storage = self.internal_unwrap_storage(account_id);
         storage
             .storage_tracker
@@ -143,6 +156,10 @@ pub struct MarginAccountDetailedView {
     pub margin_positions: HashMap<PosId, MarginTradingPositionView>,
     pub position_latest_actions: HashMap<PosId, U64>,
     pub stops: HashMap<PosId, MarginStop>,
+    /// Margin farms
+    pub farms: Vec<AccountFarmView>,
+    /// Margin farm rewards waiting to be claimed into the regular account.
+    pub unclaimed_farm_rewards: HashMap<TokenId, U128>,
 }
 
 #[derive(Serialize)]
@@ -169,8 +186,33 @@ pub struct MarginTradingPositionView {
     pub is_locking: bool,
 }
 
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarginTradingPositionHealthView {
+    /// (debt + hp_fee) / collateral, the same rate limited by max_leverage_rate.
+    /// None if the position has no collateral or no debt.
+    pub leverage_rate: Option<BigDecimal>,
+    /// Position value minus debt and hp_fee, in token_d with inner decimals.
+    #[serde(with = "u128_dec_format")]
+    pub unrealised_pnl: Balance,
+    pub is_pnl_negative: bool,
+    /// Accrued holding-position fee, in token_d with inner decimals.
+    #[serde(with = "u128_dec_format")]
+    pub hp_fee: Balance,
+    /// The drop of collateral and position total value that would make the position liquidatable, in BPS.
+    pub liquidation_distance: u32,
+    /// The price of token_p at which the position turns liquidatable, other prices unchanged.
+    pub liquidation_price: Option<Price>,
+    pub is_liquidatable: bool,
+    pub is_forcecloseable: bool,
+    /// "stop_loss" or "stop_profit" if the stop settings of the position are triggered.
+    pub stop_triggered: Option<String>,
+}
+
 impl Contract {
     pub fn margin_account_into_detailed_view(&self, account: MarginAccount) -> MarginAccountDetailedView {
+        let farms = self.margin_account_farms_into_view(&account);
         MarginAccountDetailedView {
             account_id: account.account_id.clone(),
             supplied: account
@@ -185,6 +227,12 @@ impl Contract {
                 .collect(),
             position_latest_actions: account.position_latest_actions.clone(),
             stops: account.stops.clone(),
+            farms,
+            unclaimed_farm_rewards: account
+                .unclaimed_farm_rewards
+                .iter()
+                .map(|(token_id, amount)| (token_id.clone(), U128(*amount)))
+                .collect(),
         }
     }
 
@@ -240,4 +288,26 @@ impl Contract {
     pub fn get_num_margin_accounts(&self) -> u32 {
         self.margin_accounts.len() as _
     }
+    /// Returns health metrics of the given margin position, evaluated with the given prices.
+    pub fn get_margin_position_health(&self, account_id: AccountId, pos_id: PosId, prices: Prices) -> Option<MarginTradingPositionHealthView> {
+        self.internal_get_margin_account(&account_id).and_then(|ma| {
+            ma.margin_positions.get(&pos_id)
+                .map(|mtp| self.get_mtp_health(&mtp, ma.stops.get(&pos_id), &prices))
+        })
+    }
+
+    /// Returns health metrics of all margin positions of the given account, evaluated with the given prices.
+    pub fn get_margin_account_health(&self, account_id: AccountId, prices: Prices) -> HashMap<PosId, MarginTradingPositionHealthView> {
+        self.internal_get_margin_account(&account_id)
+            .map(|ma| {
+                ma.margin_positions
+                    .iter()
+                    .map(|(pos_id, mtp)| {
+                        let health = self.get_mtp_health(&mtp, ma.stops.get(&pos_id), &prices);
+                        (pos_id, health)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
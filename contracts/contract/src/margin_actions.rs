@@ -27,6 +27,12 @@ pub enum MarginAction {
         stop_profit: Option<u32>,
         stop_loss: Option<u32>,
     },
+    IncreaseMTPosition {
+        pos_id: PosId,
+        token_d_amount: U128,
+        min_token_p_amount: U128,
+        swap_indication: SwapIndication,
+    },
     DecreaseMTPosition {
         pos_id: PosId,
         token_p_amount: U128,
@@ -39,6 +45,11 @@ pub enum MarginAction {
         min_token_d_amount: U128,
         swap_indication: SwapIndication,
     },
+    /// Repays all debt of the position from margin supplied debt token,
+    /// and claims collateral and position tokens back to margin supplied.
+    RepayAndClaimMTPosition {
+        pos_id: PosId,
+    },
     LiquidateMTPosition {
         pos_owner_id: AccountId,
         pos_id: PosId,
@@ -78,9 +89,17 @@ impl Contract {
         // Set reliable liquidator context if signer is in whitelist
         self.is_reliable_liquidator_context = in_reliable_liquidator_whitelist(&env::signer_account_id().to_string());
 
+        // Each action only checks its own kind, so margin liquidations stay independent of MarginTrading.
+        let pause_registry = read_pause_registry_from_storage();
+
         self.internal_set_prices(&prices);
         let ts = env::block_timestamp();
         for action in actions {
+            let (pause_kind, pause_targets) = action.pause_kind_and_targets();
+            assert_not_paused(&pause_registry, pause_kind, &pause_targets);
+            if let Some(operation) = action.blocked_operation() {
+                assert_not_blocked(account_id, operation);
+            }
             match action {
                 MarginAction::OpenPosition {
                     token_c_id: margin_asset_id,
@@ -111,7 +130,13 @@ impl Contract {
                 }
                 MarginAction::IncreaseCollateral { pos_id, amount } => {
                     let (token_id, actual_amount) = self.internal_margin_increase_collateral(account, &pos_id, amount.into());
-                    events::emit::increase_collateral(&account_id, actual_amount, &token_id, &pos_id);
+                    events::emit::increase_collateral(
+                        account_id,
+                        actual_amount,
+                        &token_id,
+                        &pos_id,
+                        self.internal_unwrap_asset(&token_id).config.extra_decimals,
+                    );
                 }
                 MarginAction::DecreaseCollateral { pos_id, amount } => {
                     let token_id = self.internal_margin_decrease_collateral(
@@ -120,7 +145,29 @@ impl Contract {
                         amount.into(),
                         &prices,
                     );
-                    events::emit::decrease_collateral(&account_id, amount.0, &token_id, &pos_id);
+                    events::emit::decrease_collateral(
+                        account_id,
+                        amount.0,
+                        &token_id,
+                        &pos_id,
+                        self.internal_unwrap_asset(&token_id).config.extra_decimals,
+                    );
+                }
+                MarginAction::IncreaseMTPosition {
+                    pos_id,
+                    token_d_amount: debt_amount,
+                    min_token_p_amount: min_position_amount,
+                    swap_indication,
+                } => {
+                    let event = self.process_increase_margin_position(
+                        account,
+                        &pos_id,
+                        debt_amount.into(),
+                        min_position_amount.into(),
+                        &swap_indication,
+                        &prices,
+                    );
+                    events::emit::margin_increase_started(event);
                 }
                 MarginAction::DecreaseMTPosition {
                     pos_id,
@@ -158,6 +205,9 @@ impl Contract {
                     );
                     events::emit::margin_decrease_started("margin_close_started", event);
                 }
+                MarginAction::RepayAndClaimMTPosition { pos_id } => {
+                    self.process_repay_and_claim_margin_position(account, &pos_id);
+                }
                 MarginAction::LiquidateMTPosition {
                     pos_owner_id,
                     pos_id,
@@ -260,9 +310,9 @@ impl Contract {
                         );
                         if ft_amount > 0 {
                             self.internal_ft_transfer(account_id, &token_id, amount, ft_amount, true, account_id);
-                            events::emit::margin_asset_withdraw_started(&account_id, amount, &token_id);
+                            events::emit::margin_asset_withdraw_started(account_id, amount, &token_id, asset.config.extra_decimals);
                         } else {
-                            events::emit::margin_asset_withdraw_succeeded(&account_id, amount, &token_id);
+                            events::emit::margin_asset_withdraw_succeeded(account_id, amount, &token_id, asset.config.extra_decimals);
                         }
                     }
                 }
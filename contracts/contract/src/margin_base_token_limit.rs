@@ -204,6 +204,23 @@ impl MarginBaseTokenLimit {
         );
     }
 
+    /// Whether every limit is the same or stricter than the other one,
+    /// with the same liquidation benefits.
+    pub fn is_stricter_than(&self, other: &MarginBaseTokenLimit) -> bool {
+        self.min_safety_buffer >= other.min_safety_buffer
+            && self.max_leverage_rate <= other.max_leverage_rate
+            && self.max_common_slippage_rate <= other.max_common_slippage_rate
+            && self.max_forceclose_slippage_rate <= other.max_forceclose_slippage_rate
+            && self.liq_benefit_protocol_rate == other.liq_benefit_protocol_rate
+            && self.liq_benefit_liquidator_rate == other.liq_benefit_liquidator_rate
+            && self.min_base_token_short_position.0 >= other.min_base_token_short_position.0
+            && self.min_base_token_long_position.0 >= other.min_base_token_long_position.0
+            && self.max_base_token_short_position.0 <= other.max_base_token_short_position.0
+            && self.max_base_token_long_position.0 <= other.max_base_token_long_position.0
+            && self.total_base_token_available_short.0 <= other.total_base_token_available_short.0
+            && self.total_base_token_available_long.0 <= other.total_base_token_available_long.0
+    }
+
     pub fn assert_base_token_amount_valid(
         &self,
         base_token_amount: u128,
@@ -405,22 +422,27 @@ impl Contract {
         mbtlg: MarginBaseTokenLimitGur,
     ) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(AdminCaller::Owner, "set_margin_base_token_limit_gur");
+        events::emit::settings_updated("set_margin_base_token_limit_gur", &env::predecessor_account_id(), json!({"token_id": token_id, "mbtlg": mbtlg}));
         self.internal_set_margin_base_token_limit_gur(&token_id, mbtlg);
     }
 
     #[payable]
     pub fn remove_margin_base_token_limit_gur(&mut self, token_id: TokenId) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(AdminCaller::Owner, "remove_margin_base_token_limit_gur");
         self.internal_remove_margin_base_token_limit_gur(&token_id);
+        events::emit::settings_updated("remove_margin_base_token_limit_gur", &env::predecessor_account_id(), json!({"token_id": token_id}));
     }
 
     #[payable]
     pub fn set_margin_base_token_limit(&mut self, token_id: TokenId, mbtl: MarginBaseTokenLimit) {
         assert_one_yocto();
-        self.assert_owner_or_guardians();
-        if env::predecessor_account_id() != self.internal_config().owner_id {
+        // Others than the owner are bounded by the guardian ranges instead of the timelock.
+        let is_loosening = self.is_owner_predecessor()
+            && !mbtl.is_stricter_than(&self.internal_unwrap_margin_base_token_limit_or_default(&token_id));
+        self.assert_admin_change(AdminCaller::RoleOrGuardians(Role::RiskAdmin), "set_margin_base_token_limit", is_loosening);
+        if !self.is_owner_predecessor() && !self.is_admin_proposal_call("set_margin_base_token_limit") {
             let mbtlg = self.internal_unwrap_margin_base_token_limit_gur(&token_id);
             mbtl.assert_validate(mbtlg);
         }
@@ -443,14 +465,16 @@ impl Contract {
                 MAX_RATIO
             )
         );
+        events::emit::settings_updated("set_margin_base_token_limit", &env::predecessor_account_id(), json!({"token_id": token_id, "mbtl": mbtl}));
         self.internal_set_margin_base_token_limit(&token_id, mbtl);
     }
 
     #[payable]
     pub fn remove_margin_base_token_limit(&mut self, token_id: TokenId) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(AdminCaller::Owner, "remove_margin_base_token_limit");
         self.internal_remove_margin_base_token_limit(&token_id);
+        events::emit::settings_updated("remove_margin_base_token_limit", &env::predecessor_account_id(), json!({"token_id": token_id}));
     }
 
     pub fn list_margin_base_token_limit_gur(
@@ -81,157 +81,172 @@ impl Contract {
     #[payable]
     pub fn update_max_leverage_rate(&mut self, max_leverage_rate: u8) {
         assert_one_yocto();
-        self.assert_owner();
-        assert!(max_leverage_rate > 1, "Invalid max_leverage_rate");
         let mut mc = self.internal_margin_config();
+        self.assert_admin_change(Role::RiskAdmin, "update_max_leverage_rate", max_leverage_rate > mc.max_leverage_rate);
+        assert!(max_leverage_rate > 1, "Invalid max_leverage_rate");
         mc.max_leverage_rate = max_leverage_rate;
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_max_leverage_rate", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_pending_debt_scale(&mut self, pending_debt_scale: u32) {
         assert_one_yocto();
-        self.assert_owner();
-        assert!(pending_debt_scale < MAX_RATIO, "Invalid pending_debt_scale");
         let mut mc = self.internal_margin_config();
+        self.assert_admin_change(Role::RiskAdmin, "update_pending_debt_scale", pending_debt_scale > mc.pending_debt_scale);
+        assert!(pending_debt_scale < MAX_RATIO, "Invalid pending_debt_scale");
         mc.pending_debt_scale = pending_debt_scale;
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_pending_debt_scale", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_max_slippage_rate(&mut self, max_slippage_rate: u32) {
         assert_one_yocto();
-        self.assert_owner();
-        assert!(max_slippage_rate < MAX_RATIO, "Invalid max_slippage_rate");
         let mut mc = self.internal_margin_config();
+        self.assert_admin_change(Role::RiskAdmin, "update_max_slippage_rate", max_slippage_rate > mc.max_slippage_rate);
+        assert!(max_slippage_rate < MAX_RATIO, "Invalid max_slippage_rate");
         mc.max_slippage_rate = max_slippage_rate;
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_max_slippage_rate", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_min_safety_buffer(&mut self, min_safety_buffer: u32) {
         assert_one_yocto();
-        self.assert_owner();
-        assert!(min_safety_buffer < MAX_RATIO, "Invalid min_safety_buffer");
         let mut mc = self.internal_margin_config();
+        self.assert_admin_change(Role::RiskAdmin, "update_min_safety_buffer", min_safety_buffer < mc.min_safety_buffer);
+        assert!(min_safety_buffer < MAX_RATIO, "Invalid min_safety_buffer");
         mc.min_safety_buffer = min_safety_buffer;
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_min_safety_buffer", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_margin_debt_discount_rate(&mut self, margin_debt_discount_rate: u32) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "update_margin_debt_discount_rate");
         // The debt interest rate for margin positions may be higher than that of regular positions.
         assert!(margin_debt_discount_rate <= 3 * MAX_RATIO, "Invalid margin_debt_discount_rate");
         let mut mc = self.internal_margin_config();
         mc.margin_debt_discount_rate = margin_debt_discount_rate;
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_margin_debt_discount_rate", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_open_position_fee_rate(&mut self, open_position_fee_rate: u32) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "update_open_position_fee_rate");
         assert!(open_position_fee_rate < MAX_RATIO, "Invalid open_position_fee_rate");
         let mut mc = self.internal_margin_config();
         mc.open_position_fee_rate = open_position_fee_rate;
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_open_position_fee_rate", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn register_margin_dex(&mut self, dex_id: AccountId, dex_version: u8) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::MarginDexManager, "register_margin_dex");
         let mut mc = self.internal_margin_config();
         if mc.registered_dexes.insert(dex_id, dex_version).is_some() {
             env::panic_str("margin dex already exists.");
         }
         self.margin_config.set(&mc);
+        events::emit::settings_updated("register_margin_dex", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_margin_dex(&mut self, dex_id: AccountId, dex_version: u8) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::MarginDexManager, "update_margin_dex");
         let mut mc = self.internal_margin_config();
         if mc.registered_dexes.insert(dex_id, dex_version).is_none() {
             env::panic_str("margin dex does NOT exist.");
         }
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_margin_dex", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn unregister_margin_dex(&mut self, dex_id: AccountId) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::MarginDexManager);
         let mut mc = self.internal_margin_config();
         if mc.registered_dexes.remove(&dex_id).is_none() {
             env::panic_str("margin dex does NOT exist.");
         }
         self.margin_config.set(&mc);
+        events::emit::settings_updated("unregister_margin_dex", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn register_margin_token(&mut self, token_id: AccountId, token_party: u8) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::MarginDexManager, "register_margin_token");
         let mut mc = self.internal_margin_config();
         if mc.registered_tokens.insert(token_id, token_party).is_some() {
             env::panic_str("margin token already exists.");
         }
         self.margin_config.set(&mc);
+        events::emit::settings_updated("register_margin_token", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_margin_token(&mut self, token_id: AccountId, token_party: u8) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::MarginDexManager, "update_margin_token");
         let mut mc = self.internal_margin_config();
         if mc.registered_tokens.insert(token_id, token_party).is_none() {
             env::panic_str("margin token does NOT exist.");
         }
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_margin_token", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn unregister_margin_token(&mut self, token_id: AccountId) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::MarginDexManager);
         let mut mc = self.internal_margin_config();
         if mc.registered_tokens.remove(&token_id).is_none() {
             env::panic_str("margin token does NOT exist.");
         }
         self.margin_config.set(&mc);
+        events::emit::settings_updated("unregister_margin_token", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_max_active_user_margin_position(&mut self, max_active_user_margin_position: u8) {
         assert_one_yocto();
-        self.assert_owner();
         let mut mc = self.internal_margin_config();
+        self.assert_admin_change(Role::RiskAdmin, "update_max_active_user_margin_position", max_active_user_margin_position > mc.max_active_user_margin_position);
         mc.max_active_user_margin_position = max_active_user_margin_position;
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_max_active_user_margin_position", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_liquidation_benefits_rates(&mut self, liq_benefit_protocol_rate: u32, liq_benefit_liquidator_rate: u32) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "update_liquidation_benefits_rates");
         let mut mc = self.internal_margin_config();
         assert!(liq_benefit_protocol_rate + liq_benefit_liquidator_rate <= MAX_RATIO, "require: liq_benefit_protocol_rate + liq_benefit_liquidator_rate <= {}", MAX_RATIO);
         mc.liq_benefit_protocol_rate = liq_benefit_protocol_rate;
         mc.liq_benefit_liquidator_rate = liq_benefit_liquidator_rate;
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_liquidation_benefits_rates", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 
     #[payable]
     pub fn update_max_position_action_wait_sec(&mut self, max_position_action_wait_sec: u32) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_admin(Role::RiskAdmin, "update_max_position_action_wait_sec");
         require!(max_position_action_wait_sec >= 3600, "Invalid max_position_action_wait_sec, require >= 3600");
         let mut mc = self.internal_margin_config();
         mc.max_position_action_wait_sec = max_position_action_wait_sec;
         self.margin_config.set(&mc);
+        events::emit::settings_updated("update_max_position_action_wait_sec", &env::predecessor_account_id(), json!({"margin_config": mc}));
     }
 }
@@ -1,6 +1,5 @@
-use crate::{*, events::emit::{EventDataMarginOpen, EventDataMarginDecrease}};
+use crate::{*, events::emit::{EventDataMarginOpen, EventDataMarginIncrease, EventDataMarginDecrease, EventDataMarginDecreaseResult}};
 use near_sdk::{promise_result_as_success, serde_json};
-use near_contract_standards::fungible_token::core::ext_ft_core;
 
 
 pub const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(100 * Gas::ONE_TERA.0);
@@ -298,6 +297,108 @@ impl Contract {
             ))
         }
     }
+
+    /// The total value of collateral and position at which the position turns liquidatable,
+    /// that is the smallest cap satisfying cap * (1 - safety_buffer_rate) >= debt + hp_fee.
+    pub(crate) fn get_mtp_liquidation_cap_value(
+        &self,
+        mt: &MarginTradingPosition,
+        prices: &Prices,
+        safety_buffer_rate: u32,
+    ) -> BigDecimal {
+        (self.get_mtp_debt_value(mt, prices) + self.get_mtp_hp_fee_value(mt, prices))
+            .div_ratio(MAX_RATIO - safety_buffer_rate)
+    }
+
+    /// Estimates the price of token_p at which the position turns liquidatable,
+    /// assuming prices of all other tokens stay unchanged.
+    /// Returns None if the position can't be liquidated by a drop of token_p price alone.
+    pub(crate) fn get_mtp_liquidation_price(
+        &self,
+        mt: &MarginTradingPosition,
+        prices: &Prices,
+        safety_buffer_rate: u32,
+    ) -> Option<Price> {
+        let value_liq_cap = self.get_mtp_liquidation_cap_value(mt, prices, safety_buffer_rate);
+        let value_position = self.get_mtp_position_value(mt, prices);
+        let value_collateral = self.get_mtp_collateral_value(mt, prices);
+        let price_p = prices.get_unwrap(&mt.token_p_id);
+        if mt.token_c_id == mt.token_p_id {
+            compute_liquidation_price(
+                price_p,
+                value_position + value_collateral,
+                BigDecimal::zero(),
+                value_liq_cap,
+            )
+        } else {
+            compute_liquidation_price(price_p, value_position, value_collateral, value_liq_cap)
+        }
+    }
+
+    pub(crate) fn get_mtp_health(
+        &self,
+        mt: &MarginTradingPosition,
+        stop: Option<&MarginStop>,
+        prices: &Prices,
+    ) -> MarginTradingPositionHealthView {
+        let pd = PositionDirection::new(&mt.token_c_id, &mt.token_d_id, &mt.token_p_id);
+        let mbtl = self.internal_unwrap_margin_base_token_limit_or_default(pd.get_base_token_id());
+        let asset_d = self.internal_unwrap_asset(&mt.token_d_id);
+        let price_d = prices.get_unwrap(&mt.token_d_id);
+
+        let value_position = self.get_mtp_position_value(mt, prices);
+        let value_debt = self.get_mtp_debt_value(mt, prices);
+        let value_hp_fee = self.get_mtp_hp_fee_value(mt, prices);
+        let value_cap = self.get_mtp_collateral_value(mt, prices) + value_position;
+        let value_liq_cap = self.get_mtp_liquidation_cap_value(mt, prices, mbtl.min_safety_buffer);
+
+        let (unrealised_pnl, is_pnl_negative) = if value_position >= value_debt + value_hp_fee {
+            ((value_position - value_debt - value_hp_fee).to_balance_in_price(price_d, asset_d.config.extra_decimals), false)
+        } else {
+            ((value_debt + value_hp_fee - value_position).to_balance_in_price(price_d, asset_d.config.extra_decimals), true)
+        };
+        let liquidation_distance = if value_cap > value_liq_cap {
+            ((value_cap - value_liq_cap) / value_cap).round_mul_u128(MAX_RATIO as u128) as u32
+        } else {
+            0
+        };
+
+        MarginTradingPositionHealthView {
+            leverage_rate: self.get_mtp_lr(mt, prices),
+            unrealised_pnl,
+            is_pnl_negative,
+            hp_fee: u128_ratio(
+                mt.debt_cap,
+                asset_d.unit_acc_hp_interest - mt.uahpi_at_open,
+                UNIT,
+            ),
+            liquidation_distance,
+            liquidation_price: self.get_mtp_liquidation_price(mt, prices, mbtl.min_safety_buffer),
+            is_liquidatable: self.is_mt_liquidatable(mt, prices, mbtl.min_safety_buffer),
+            is_forcecloseable: self.is_mt_forcecloseable(mt, prices),
+            stop_triggered: stop
+                .and_then(|stop| self.is_stop_active(mt, prices, stop, 0))
+                .map(|v| v.to_string()),
+        }
+    }
+}
+
+/// Solves (value_p_exposure * k + value_fixed) = value_liq_cap for k,
+/// and scales the current token_p price by k.
+fn compute_liquidation_price(
+    price_p: &Price,
+    value_p_exposure: BigDecimal,
+    value_fixed: BigDecimal,
+    value_liq_cap: BigDecimal,
+) -> Option<Price> {
+    if value_p_exposure == BigDecimal::zero() || value_liq_cap <= value_fixed {
+        None
+    } else {
+        Some(Price {
+            multiplier: ((value_liq_cap - value_fixed) / value_p_exposure).round_mul_u128(price_p.multiplier),
+            decimals: price_p.decimals,
+        })
+    }
 }
 
 impl Contract {
@@ -358,17 +459,17 @@ impl Contract {
 
         // check legitimacy: assets legal; swap_indication matches;
         margin_config.check_pair(&token_d_id, &token_p_id, &token_c_id);
-        let mut swap_detail = self.parse_swap_indication(swap_indication);
         let ft_d_amount = token_d_amount / 10u128.pow(asset_d.config.extra_decimals as u32);
+        let swap_route = self.parse_swap_indication(swap_indication, ft_d_amount);
         assert!(token_d_amount >= asset_d.config.min_borrowed_amount.expect("Missing min_borrowed_amount").0, "The debt amount is too low");
         assert!(
-            swap_detail.verify_token_in(token_d_id, ft_d_amount),
+            swap_route.verify_token_in(token_d_id, ft_d_amount),
             "token_in check failed"
         );
         let ft_p_amount =
             min_token_p_amount / 10u128.pow(asset_p.config.extra_decimals as u32);
         assert!(
-            swap_detail.verify_token_out(token_p_id, ft_p_amount),
+            swap_route.verify_token_out(token_p_id, ft_p_amount),
             "token_out check failed"
         );
 
@@ -488,31 +589,170 @@ impl Contract {
             action_ts: ts.into(),
             op: format!("open"),
             liquidator_id: None,
+            leg: None,
         };
-        swap_detail.set_client_echo(&swap_ref.to_msg_string());
-        let swap_msg = swap_detail.to_msg_string();
-        ext_ft_core::ext(token_d_id.clone())
-            .with_attached_deposit(1)
-            .with_static_gas(GAS_FOR_FT_TRANSFER_CALL)
-            .ft_transfer_call(
-                swap_indication.dex_id.clone(),
-                U128(ft_d_amount),
-                None,
-                swap_msg,
-            )
-            .then(
-                Self::ext(env::current_account_id())
-                    .with_static_gas(GAS_FOR_FT_TRANSFER_CALL_CALLBACK)
-                    .with_unused_gas_weight(0)
-                    .callback_dex_trade(
-                        account.account_id.clone(),
-                        pos_id.clone(),
-                        token_d_amount.into(),
-                        U128(0),
-                        format!("open"),
-                    ),
-            );
-            event
+        self.internal_margin_swap(account, swap_route, swap_ref, token_d_id, 0);
+        event
+    }
+
+    /// actual process for increasing margin position
+    pub(crate) fn process_increase_margin_position(
+        &mut self,
+        account: &mut MarginAccount,
+        pos_id: &String,
+        token_d_amount: Balance,
+        min_token_p_amount: Balance,
+        swap_indication: &SwapIndication,
+        prices: &Prices,
+    ) -> EventDataMarginIncrease {
+        let margin_config = self.internal_margin_config();
+        let mut mt = account
+            .margin_positions
+            .get(pos_id)
+            .expect("Position not exist");
+        assert!(
+            !mt.is_locking,
+            "Position is currently waiting for a trading result."
+        );
+        let pd = PositionDirection::new(&mt.token_c_id, &mt.token_d_id, &mt.token_p_id);
+        let mbtl = self.internal_unwrap_margin_base_token_limit_or_default(pd.get_base_token_id());
+        let asset_c = self.internal_unwrap_asset(&mt.token_c_id);
+        let asset_p = self.internal_unwrap_asset(&mt.token_p_id);
+        let mut asset_d = self.internal_unwrap_asset(&mt.token_d_id);
+        assert!(asset_d.config.can_borrow, "This asset can't be used borrowed");
+
+        // check if borrow limit has hit, then need panic here
+        if !self.is_reliable_liquidator_context {
+            if let Some(borrowed_limit) = asset_d.config.borrowed_limit {
+                assert!(
+                    asset_d.borrowed.balance + asset_d.margin_debt.balance + asset_d.margin_pending_debt + token_d_amount <= borrowed_limit.0, 
+                    "Asset {} has hit borrow limit, use it as debt for margin position is not allowed", &mt.token_d_id
+                );
+            }
+        }
+
+        let total_debt_amount = asset_d
+            .margin_debt
+            .shares_to_amount(mt.token_d_shares, true);
+        let hp_fee = u128_ratio(
+            mt.debt_cap,
+            asset_d.unit_acc_hp_interest - mt.uahpi_at_open,
+            UNIT,
+        );
+        let (base_token_amount, total_base_token_amount) = if pd.get_base_token_id() == &mt.token_d_id {
+            (total_debt_amount + token_d_amount, asset_d.margin_debt.balance + asset_d.margin_pending_debt + token_d_amount)
+        } else {
+            (mt.token_p_amount + min_token_p_amount, asset_p.margin_position + min_token_p_amount)
+        };
+        mbtl.assert_base_token_amount_valid(base_token_amount, total_base_token_amount, &pd);
+
+        // check legitimacy: swap_indication matches;
+        let ft_d_amount = token_d_amount / 10u128.pow(asset_d.config.extra_decimals as u32);
+        let swap_route = self.parse_swap_indication(swap_indication, ft_d_amount);
+        assert!(token_d_amount >= asset_d.config.min_borrowed_amount.expect("Missing min_borrowed_amount").0, "The debt amount is too low");
+        assert!(
+            swap_route.verify_token_in(&mt.token_d_id, ft_d_amount),
+            "token_in check failed"
+        );
+        let ft_p_amount =
+            min_token_p_amount / 10u128.pow(asset_p.config.extra_decimals as u32);
+        assert!(
+            swap_route.verify_token_out(&mt.token_p_id, ft_p_amount),
+            "token_out check failed"
+        );
+
+        // check safety of the position after increase,
+        // the accrued holding-position fee is counted as debt.
+        assert!(
+            is_min_amount_out_reasonable(
+                token_d_amount,
+                &asset_d,
+                prices.get_unwrap(&mt.token_d_id),
+                &asset_p,
+                prices.get_unwrap(&mt.token_p_id),
+                min_token_p_amount,
+                mbtl.max_common_slippage_rate,
+            ),
+            "min_position_amount is too low"
+        );
+        let token_c_amount = asset_c.supplied.shares_to_amount(mt.token_c_shares, false);
+        let new_debt_amount = total_debt_amount + hp_fee + token_d_amount;
+        let new_token_p_amount = mt.token_p_amount + min_token_p_amount;
+        assert!(
+            !self.is_open_position_liquidatable(
+                token_c_amount,
+                prices.get_unwrap(&mt.token_c_id),
+                asset_c.config.extra_decimals,
+                new_debt_amount,
+                prices.get_unwrap(&mt.token_d_id),
+                asset_d.config.extra_decimals,
+                new_token_p_amount,
+                prices.get_unwrap(&mt.token_p_id),
+                asset_p.config.extra_decimals,
+                mbtl.min_safety_buffer,
+            ),
+            "Debt is too much"
+        );
+        assert!(
+            !self.is_open_position_forcecloseable(
+                token_c_amount,
+                prices.get_unwrap(&mt.token_c_id),
+                asset_c.config.extra_decimals,
+                new_debt_amount,
+                prices.get_unwrap(&mt.token_d_id),
+                asset_d.config.extra_decimals,
+                new_token_p_amount,
+                prices.get_unwrap(&mt.token_p_id),
+                asset_p.config.extra_decimals,
+            ),
+            "Debt is too much"
+        );
+        //   leverage rate less than max leverage rate
+        assert!(
+            self.get_open_position_lr(
+                token_c_amount,
+                prices.get_unwrap(&mt.token_c_id),
+                asset_c.config.extra_decimals,
+                new_debt_amount,
+                prices.get_unwrap(&mt.token_d_id),
+                asset_d.config.extra_decimals,
+            ).expect("Position has no collateral")
+                <= BigDecimal::from(mbtl.max_leverage_rate as u32),
+            "Leverage rate is too high"
+        );
+
+        // passes all check, start to increase
+        let pre_token_p_amount = mt.token_p_amount;
+        asset_d.increase_margin_pending_debt(token_d_amount, margin_config.pending_debt_scale);
+        self.internal_set_asset(&mt.token_d_id, asset_d);
+        mt.is_locking = true;
+        // Update existing margin_position storage
+        account.margin_positions.insert(pos_id, &mt);
+        let ts = env::block_timestamp();
+        account.position_latest_actions.insert(pos_id.clone(), ts.into());
+
+        let event = EventDataMarginIncrease {
+            account_id: account.account_id.clone(),
+            pos_id: pos_id.clone(),
+            token_d_id: mt.token_d_id.clone(),
+            token_d_amount,
+            token_p_id: mt.token_p_id.clone(),
+            token_p_amount: min_token_p_amount,
+        };
+
+        // call dex to trade and wait for callback
+        // organize swap action
+        let swap_ref = SwapReference {
+            account_id: account.account_id.clone(),
+            pos_id: pos_id.clone(),
+            amount_in: token_d_amount.into(),
+            action_ts: ts.into(),
+            op: "increase".to_string(),
+            liquidator_id: None,
+            leg: None,
+        };
+        self.internal_margin_swap(account, swap_route, swap_ref, &mt.token_d_id, pre_token_p_amount);
+        event
     }
 
     /// actual process for decreasing margin position
@@ -542,11 +782,11 @@ impl Contract {
         let asset_d = self.internal_unwrap_asset(&mt.token_d_id);
 
         //   check swap_indication
-        let mut swap_detail = self.parse_swap_indication(swap_indication);
         let ft_p_amount =
             token_p_amount / 10u128.pow(asset_p.config.extra_decimals as u32);
+        let swap_route = self.parse_swap_indication(swap_indication, ft_p_amount);
         assert!(
-            swap_detail.verify_token_in(&mt.token_p_id, ft_p_amount),
+            swap_route.verify_token_in(&mt.token_p_id, ft_p_amount),
             "token_in check failed"
         );
         let ft_d_amount = min_token_d_amount / 10u128.pow(asset_d.config.extra_decimals as u32);
@@ -564,7 +804,7 @@ impl Contract {
             }
         }
         assert!(
-            swap_detail.verify_token_out(&mt.token_d_id, ft_d_amount),
+            swap_route.verify_token_out(&mt.token_d_id, ft_d_amount),
             "token_out check failed"
         );
 
@@ -695,33 +935,104 @@ impl Contract {
             action_ts: ts.into(),
             op,
             liquidator_id,
+            leg: None,
         };
-        swap_detail.set_client_echo(&swap_ref.to_msg_string());
-        let swap_msg = swap_detail.to_msg_string();
-        ext_ft_core::ext(mt.token_p_id.clone())
-            .with_attached_deposit(1)
-            .with_static_gas(GAS_FOR_FT_TRANSFER_CALL)
-            .ft_transfer_call(
-                swap_indication.dex_id.clone(),
-                U128(ft_p_amount),
-                None,
-                swap_msg,
-            )
-            .then(
-                Self::ext(env::current_account_id())
-                    .with_static_gas(GAS_FOR_FT_TRANSFER_CALL_CALLBACK)
-                    .with_unused_gas_weight(0)
-                    .callback_dex_trade(
-                        account.account_id.clone(),
-                        pos_id.clone(),
-                        token_p_amount.into(),
-                        pre_token_p_amount.into(),
-                        format!("decrease"),
-                    ),
-            );
+        self.internal_margin_swap(account, swap_route, swap_ref, &mt.token_p_id, pre_token_p_amount);
         event
     }
 
+    /// Closes the position by repaying all debt and holding-position fee
+    /// from the owner's margin supplied debt token, no swap involved.
+    /// Collateral and position tokens are returned to the owner's margin supplied in kind.
+    pub(crate) fn process_repay_and_claim_margin_position(
+        &mut self,
+        account: &mut MarginAccount,
+        pos_id: &String,
+    ) {
+        let mut mt = account
+            .margin_positions
+            .get(pos_id)
+            .expect("Position not exist");
+        assert!(
+            !mt.is_locking,
+            "Position is currently waiting for a trading result."
+        );
+        let mut asset_d = self.internal_unwrap_asset(&mt.token_d_id);
+        let mut asset_p = self.internal_unwrap_asset(&mt.token_p_id);
+
+        let total_debt_amount = asset_d
+            .margin_debt
+            .shares_to_amount(mt.token_d_shares, true);
+        let hp_fee = u128_ratio(
+            mt.debt_cap,
+            asset_d.unit_acc_hp_interest - mt.uahpi_at_open,
+            UNIT,
+        );
+        let repay_amount = total_debt_amount + hp_fee;
+        let repay_supplied_shares = asset_d.supplied.amount_to_shares(repay_amount, true);
+        assert!(
+            account.supplied.get(&mt.token_d_id).map(|s| s.0).unwrap_or(0) >= repay_supplied_shares.0,
+            "Not enough {} in margin supplied to repay the debt", mt.token_d_id
+        );
+        events::emit::margin_decrease_started(
+            "margin_repay_and_claim_started",
+            EventDataMarginDecrease {
+                account_id: account.account_id.clone(),
+                pos_id: pos_id.clone(),
+                liquidator_id: None,
+                token_p_id: mt.token_p_id.clone(),
+                token_p_amount: mt.token_p_amount,
+                token_d_id: mt.token_d_id.clone(),
+                token_d_amount: repay_amount,
+            },
+        );
+
+        account.withdraw_supply_shares(&mt.token_d_id, &repay_supplied_shares);
+        asset_d.supplied.withdraw(repay_supplied_shares, repay_amount);
+        asset_d.margin_debt.withdraw(mt.token_d_shares, total_debt_amount);
+        asset_d.prot_fee += hp_fee;
+        mt.token_d_shares = U128(0);
+        mt.debt_cap = 0;
+
+        let mut benefits = SettlementBenefits::default();
+        let stop_fee_info = self.settle_closed_position(
+            account,
+            &mt,
+            &mut asset_p,
+            pos_id,
+            &mut benefits,
+        );
+        account.position_latest_actions.remove(pos_id);
+        self.internal_set_asset(&mt.token_d_id, asset_d);
+        self.internal_set_asset(&mt.token_p_id, asset_p);
+
+        deposit_benefit_to_account(account, &mt.token_c_id, benefits.collateral_shares);
+        deposit_benefit_to_account(account, &mt.token_p_id, benefits.position_token_shares);
+        events::emit::margin_benefits(&account.account_id, &benefits.to_margin_updates(&mt));
+
+        // Refund the pending stop service fee to the owner
+        if let Some(fee_info) = stop_fee_info {
+            self.internal_margin_deposit(account, &fee_info.token_id, fee_info.amount);
+        }
+
+        events::emit::margin_decrease_succeeded(
+            "repay_and_claim",
+            EventDataMarginDecreaseResult {
+                account_id: account.account_id.clone(),
+                pos_id: pos_id.clone(),
+                liquidator_id: None,
+                token_c_id: mt.token_c_id.clone(),
+                token_c_shares: mt.token_c_shares,
+                token_d_id: mt.token_d_id.clone(),
+                token_d_shares: mt.token_d_shares,
+                token_p_id: mt.token_p_id.clone(),
+                token_p_amount: mt.token_p_amount,
+                holding_fee: hp_fee,
+                fully_closed: true,
+            },
+        );
+    }
+
     pub(crate) fn process_margin_liquidate_direct(
         &mut self,
         pos_owner_id: &AccountId,
@@ -734,6 +1045,7 @@ impl Contract {
             .margin_positions
             .remove(pos_id)
             .expect("Position not exist");
+        pos_owner.add_affected_position_farms(&mt);
         assert!(
             !mt.is_locking,
             "Position is currently waiting for a trading result."
@@ -905,46 +1217,68 @@ impl Contract {
             // trading failed, revert margin operation
             let mut account = self.internal_unwrap_margin_account(&account_id);
             account.position_latest_actions.remove(&pos_id);
-            if op == "open" {
-                let mt = account.margin_positions.get(&pos_id).unwrap().clone();
-                let mut asset_d = self.internal_unwrap_asset(&mt.token_d_id);
-                asset_d.margin_pending_debt -= amount_in.0;
-                self.internal_set_asset(&mt.token_d_id, asset_d);
-                account.deposit_supply_shares(&mt.token_c_id, &mt.token_c_shares);
-                // Remove margin_position storage
-                account.storage_tracker.start();
-                account.margin_positions.remove(&pos_id);
-                account.storage_tracker.stop();
-                // Remove pos_id from stop and refund to user's supply if necessary
-                if let Some(margin_stop) = account.stops.remove(&pos_id) {
-                    self.internal_margin_deposit(&mut account, &margin_stop.service_token_id, margin_stop.service_token_amount.into());
-                }
-                events::emit::margin_open_failed(&account_id, &pos_id);
-                
-            } else if op == "decrease" {
-                let mut mt = account.margin_positions.get(&pos_id).unwrap();
-                let mut asset_p = self.internal_unwrap_asset(&mt.token_p_id);
-                let amount_in: Balance = amount_in.into();
-                let pre_token_p_amount: Balance = pre_token_p_amount.into();
-                if amount_in > pre_token_p_amount {
-                    asset_p.margin_position += pre_token_p_amount;
-                    // re-deposit those gap to supply as margin
-                    let gap = amount_in - pre_token_p_amount;
-                    let gap_shares = asset_p.supplied.amount_to_shares(gap, false);
-                    asset_p.supplied.deposit(gap_shares, gap);
-                    mt.token_c_shares.0 += gap_shares.0;
-                } else {
-                    asset_p.margin_position += amount_in;
-                }
-                self.internal_set_asset(&mt.token_p_id, asset_p);
-                mt.is_locking = false;
-                mt.token_p_amount = pre_token_p_amount;
-                // Update existing margin_position storage
-                account.margin_positions.insert(&pos_id, &mt);
-                events::emit::margin_decrease_failed(&account_id, &pos_id);
+            self.internal_revert_dex_trade(account, &pos_id, amount_in.0, pre_token_p_amount.0, &op);
+        }
+    }
+}
+
+impl Contract {
+    /// Reverts the margin operation whose trade on the dex failed,
+    /// amount_in being the amount of token_in that was sent to the dex.
+    pub(crate) fn internal_revert_dex_trade(
+        &mut self,
+        mut account: MarginAccount,
+        pos_id: &PosId,
+        amount_in: Balance,
+        pre_token_p_amount: Balance,
+        op: &str,
+    ) {
+        let account_id = account.account_id.clone();
+        if op == "open" {
+            let mt = account.margin_positions.get(pos_id).unwrap().clone();
+            let mut asset_d = self.internal_unwrap_asset(&mt.token_d_id);
+            asset_d.margin_pending_debt -= amount_in;
+            self.internal_set_asset(&mt.token_d_id, asset_d);
+            account.deposit_supply_shares(&mt.token_c_id, &mt.token_c_shares);
+            // Remove margin_position storage
+            account.storage_tracker.start();
+            account.margin_positions.remove(pos_id);
+            account.storage_tracker.stop();
+            // Remove pos_id from stop and refund to user's supply if necessary
+            if let Some(margin_stop) = account.stops.remove(pos_id) {
+                self.internal_margin_deposit(&mut account, &margin_stop.service_token_id, margin_stop.service_token_amount.into());
             }
-            self.internal_force_set_margin_account(&account_id, account);
+            events::emit::margin_open_failed(&account_id, pos_id);
+        } else if op == "increase" {
+            let mut mt = account.margin_positions.get(pos_id).unwrap();
+            let mut asset_d = self.internal_unwrap_asset(&mt.token_d_id);
+            asset_d.margin_pending_debt -= amount_in;
+            self.internal_set_asset(&mt.token_d_id, asset_d);
+            mt.is_locking = false;
+            // Update existing margin_position storage
+            account.margin_positions.insert(pos_id, &mt);
+            events::emit::margin_increase_failed(&account_id, pos_id);
+        } else if op == "decrease" {
+            let mut mt = account.margin_positions.get(pos_id).unwrap();
+            let mut asset_p = self.internal_unwrap_asset(&mt.token_p_id);
+            if amount_in > pre_token_p_amount {
+                asset_p.margin_position += pre_token_p_amount;
+                // re-deposit those gap to supply as margin
+                let gap = amount_in - pre_token_p_amount;
+                let gap_shares = asset_p.supplied.amount_to_shares(gap, false);
+                asset_p.supplied.deposit(gap_shares, gap);
+                mt.token_c_shares.0 += gap_shares.0;
+            } else {
+                asset_p.margin_position += amount_in;
+            }
+            self.internal_set_asset(&mt.token_p_id, asset_p);
+            mt.is_locking = false;
+            mt.token_p_amount = pre_token_p_amount;
+            // Update existing margin_position storage
+            account.margin_positions.insert(pos_id, &mt);
+            events::emit::margin_decrease_failed(&account_id, pos_id);
         }
+        self.internal_force_set_margin_account(&account_id, account);
     }
 }
 
@@ -1038,4 +1372,51 @@ mod tests {
     fn test_validate_stop_settings_invalid_stop_profit_zero() {
         validate_stop_settings(&Some(0), &None);
     }
+
+    // ============= compute_liquidation_price tests =============
+
+    fn price(multiplier: u128) -> Price {
+        Price { multiplier, decimals: 8 }
+    }
+
+    #[test]
+    fn test_compute_liquidation_price_scales_current_price() {
+        let liq_price = compute_liquidation_price(
+            &price(10000),
+            BigDecimal::from(10u128),
+            BigDecimal::from(2u128),
+            BigDecimal::from(7u128),
+        ).unwrap();
+        assert_eq!(liq_price.multiplier, 5000);
+        assert_eq!(liq_price.decimals, 8);
+    }
+
+    #[test]
+    fn test_compute_liquidation_price_without_fixed_value() {
+        let liq_price = compute_liquidation_price(
+            &price(10000),
+            BigDecimal::from(8u128),
+            BigDecimal::zero(),
+            BigDecimal::from(6u128),
+        ).unwrap();
+        assert_eq!(liq_price.multiplier, 7500);
+    }
+
+    #[test]
+    fn test_compute_liquidation_price_unreachable() {
+        // collateral alone covers the liquidation threshold
+        assert!(compute_liquidation_price(
+            &price(10000),
+            BigDecimal::from(10u128),
+            BigDecimal::from(7u128),
+            BigDecimal::from(7u128),
+        ).is_none());
+        // no position token exposure
+        assert!(compute_liquidation_price(
+            &price(10000),
+            BigDecimal::zero(),
+            BigDecimal::from(2u128),
+            BigDecimal::from(7u128),
+        ).is_none());
+    }
 }
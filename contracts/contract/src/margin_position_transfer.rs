@@ -0,0 +1,147 @@
+use crate::*;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarginPositionTransfer {
+    /// Current owner of the position.
+    pub owner_id: AccountId,
+    /// The account that is allowed to accept the position.
+    pub receiver_id: AccountId,
+}
+
+pub fn read_margin_position_transfers_from_storage() -> UnorderedMap<PosId, MarginPositionTransfer> {
+    if let Some(content) = env::storage_read(MARGIN_POSITION_TRANSFERS_KEY.as_bytes()) {
+        UnorderedMap::try_from_slice(&content).expect("deserialize margin position transfers failed.")
+    } else {
+        UnorderedMap::new(MARGIN_POSITION_TRANSFERS_KEY.as_bytes())
+    }
+}
+
+pub fn write_margin_position_transfers_to_storage(data: UnorderedMap<PosId, MarginPositionTransfer>) {
+    env::storage_write(MARGIN_POSITION_TRANSFERS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+impl MarginAccount {
+    /// Removes the pending transfer of the position of this account
+    /// and releases its storage to the account.
+    pub fn remove_margin_position_transfer(&mut self, pos_id: &PosId) -> Option<MarginPositionTransfer> {
+        let mut transfers = read_margin_position_transfers_from_storage();
+        let transfer = transfers.get(pos_id).filter(|transfer| transfer.owner_id == self.account_id)?;
+        self.storage_tracker.start();
+        transfers.remove(pos_id);
+        write_margin_position_transfers_to_storage(transfers);
+        self.storage_tracker.stop();
+        Some(transfer)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Proposes to transfer the given margin position of the predecessor to receiver_id.
+    /// The position is moved only after the receiver calls `accept_margin_position`.
+    /// Proposing again replaces the previous receiver.
+    /// The storage of the pending transfer is paid by the predecessor until the transfer
+    /// is accepted or cancelled, or the position is closed.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn transfer_margin_position(&mut self, pos_id: PosId, receiver_id: AccountId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        require!(account_id != receiver_id, "Can't transfer position to self");
+        require!(self.internal_get_account(&receiver_id, false).is_some(), "Receiver is not registered");
+        let mut account = self.internal_unwrap_margin_account(&account_id);
+        let mt = account.margin_positions.get(&pos_id).expect("Position not exist");
+        require!(!mt.is_locking, "Position is currently waiting for a trading result.");
+        let mut transfers = read_margin_position_transfers_from_storage();
+        account.storage_tracker.start();
+        transfers.insert(&pos_id, &MarginPositionTransfer {
+            owner_id: account_id.clone(),
+            receiver_id: receiver_id.clone(),
+        });
+        write_margin_position_transfers_to_storage(transfers);
+        account.storage_tracker.stop();
+        self.internal_set_margin_account(&account_id, account);
+        events::emit::margin_position_transfer_proposed(&account_id, &receiver_id, &pos_id);
+    }
+
+    /// Cancels a pending transfer of the given margin position.
+    /// Can be called by either the owner or the receiver.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn cancel_margin_position_transfer(&mut self, pos_id: PosId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let transfer = read_margin_position_transfers_from_storage().get(&pos_id).expect("Position transfer not exist");
+        require!(account_id == transfer.owner_id || account_id == transfer.receiver_id, "Not allowed");
+        let mut owner = self.internal_unwrap_margin_account(&transfer.owner_id);
+        owner.remove_margin_position_transfer(&pos_id);
+        self.internal_set_margin_account(&transfer.owner_id, owner);
+        events::emit::margin_position_transfer_cancelled(&transfer.owner_id, &transfer.receiver_id, &pos_id);
+    }
+
+    /// Accepts a margin position proposed to the predecessor,
+    /// together with its stop settings and the escrowed stop service fee.
+    /// The receiver should have enough storage balance to hold the position
+    /// and stay within max_active_user_margin_position.
+    /// Accepting counts as opening a position for the pause registry and the blocklist.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn accept_margin_position(&mut self, pos_id: PosId) {
+        assert_one_yocto();
+        let receiver_id = env::predecessor_account_id();
+        let transfer = read_margin_position_transfers_from_storage().get(&pos_id).expect("Position transfer not exist");
+        require!(transfer.receiver_id == receiver_id, "Not allowed");
+        assert_not_blocked(&receiver_id, BlockedOperation::OpenMarginPosition);
+
+        let mut owner = self.internal_unwrap_margin_account(&transfer.owner_id);
+        owner.remove_margin_position_transfer(&pos_id);
+        let mt = owner.margin_positions.get(&pos_id).expect("Position not exist");
+        require!(!mt.is_locking, "Position is currently waiting for a trading result.");
+        assert_not_paused(
+            &read_pause_registry_from_storage(),
+            PauseKind::MarginTrading,
+            &[mt.token_c_id.to_string(), mt.token_d_id.to_string(), mt.token_p_id.to_string()],
+        );
+        owner.storage_tracker.start();
+        owner.margin_positions.remove(&pos_id);
+        owner.storage_tracker.stop();
+        owner.add_affected_position_farms(&mt);
+        let margin_stop = owner.stops.remove(&pos_id);
+        let latest_action = owner.position_latest_actions.remove(&pos_id);
+
+        let mut receiver = self.internal_unwrap_margin_account(&receiver_id);
+        require!(
+            receiver.margin_positions.len() < self.internal_margin_config().max_active_user_margin_position as u64,
+            "The number of margin positions exceeds the limit."
+        );
+        require!(receiver.margin_positions.get(&pos_id).is_none(), "Position already exists");
+        receiver.storage_tracker.start();
+        receiver.margin_positions.insert(&pos_id, &mt);
+        receiver.storage_tracker.stop();
+        receiver.add_affected_position_farms(&mt);
+        if let Some(margin_stop) = margin_stop {
+            receiver.stops.insert(pos_id.clone(), margin_stop);
+        }
+        if let Some(latest_action) = latest_action {
+            receiver.position_latest_actions.insert(pos_id.clone(), latest_action);
+        }
+
+        self.internal_set_margin_account(&transfer.owner_id, owner);
+        self.internal_set_margin_account(&receiver_id, receiver);
+        events::emit::margin_position_transferred(&transfer.owner_id, &receiver_id, &pos_id);
+    }
+
+    /// Returns the pending transfer of the given margin position.
+    pub fn get_margin_position_transfer(&self, pos_id: PosId) -> Option<MarginPositionTransfer> {
+        read_margin_position_transfers_from_storage().get(&pos_id)
+    }
+
+    /// Returns all pending transfers of margin positions to the given receiver.
+    pub fn list_margin_position_transfers_to(&self, receiver_id: AccountId) -> HashMap<PosId, MarginPositionTransfer> {
+        read_margin_position_transfers_from_storage()
+            .iter()
+            .filter(|(_, transfer)| transfer.receiver_id == receiver_id)
+            .collect()
+    }
+}
@@ -57,6 +57,9 @@ impl Contract {
                     tokens.insert(token_d_id.clone());
                     None
                 },
+                MarginAction::IncreaseMTPosition { pos_id, token_d_amount: _, min_token_p_amount: _, swap_indication: _ } => {
+                    Some(pos_id)
+                }
                 MarginAction::DecreaseMTPosition { pos_id, token_p_amount: _, min_token_d_amount: _, swap_indication: _ }=> {
                     Some(pos_id)
                 }
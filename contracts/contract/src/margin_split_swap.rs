@@ -0,0 +1,244 @@
+use crate::*;
+use near_sdk::{serde_json, PromiseResult};
+
+pub const GAS_FOR_SPLIT_TRADE_CALLBACK: Gas = Gas(30 * Gas::ONE_TERA.0);
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct MarginSwapLeg {
+    pub dex_id: AccountId,
+    /// The part of the amount in traded by the leg, in inner decimals.
+    pub amount_in: Balance,
+    /// The amount of token_out returned by the dex, in inner decimals.
+    pub amount_out: Balance,
+    pub is_returned: bool,
+    /// The dex didn't use the amount in, so it was refunded.
+    pub is_failed: bool,
+}
+
+/// A margin swap split across dexes, waiting for the results of its legs.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct PendingSplitSwap {
+    pub op: String,
+    pub liquidator_id: Option<AccountId>,
+    pub action_ts: Timestamp,
+    pub pre_token_p_amount: Balance,
+    pub legs: Vec<MarginSwapLeg>,
+    /// Whether the results of the transfers to the dexes are known.
+    pub is_resolved: bool,
+}
+
+impl PendingSplitSwap {
+    pub fn is_settleable(&self) -> bool {
+        self.is_resolved && self.legs.iter().all(|leg| leg.is_returned || leg.is_failed)
+    }
+}
+
+pub fn pending_split_swaps() -> LookupMap<PosId, PendingSplitSwap> {
+    LookupMap::new(MARGIN_SPLIT_SWAPS_KEY.as_bytes())
+}
+
+/// The op of `callback_dex_trade` for the op of the swap reference.
+fn dex_trade_callback_op(op: &str) -> &str {
+    match op {
+        "open" | "increase" => op,
+        _ => "decrease",
+    }
+}
+
+impl Contract {
+    /// Sends the legs of the route to their dexes and waits for the trading results.
+    /// A single leg is resolved by `callback_dex_trade` and its SwapReference return.
+    /// Legs of a split swap echo their index, and are settled together
+    /// once every leg either returned token_out or failed.
+    /// The pending split swap is stored at the cost of the position owner until then.
+    pub(crate) fn internal_margin_swap(
+        &mut self,
+        account: &mut MarginAccount,
+        route: SwapRoute,
+        swap_ref: SwapReference,
+        token_in_id: &TokenId,
+        pre_token_p_amount: Balance,
+    ) {
+        let callback_op = dex_trade_callback_op(&swap_ref.op).to_string();
+        if route.legs.len() == 1 {
+            let (swap_detail, ft_amount_in) = route.legs.into_iter().next().unwrap();
+            self.internal_send_swap(swap_detail, &swap_ref, token_in_id, ft_amount_in)
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(GAS_FOR_FT_TRANSFER_CALL_CALLBACK)
+                        .with_unused_gas_weight(0)
+                        .callback_dex_trade(
+                            account.account_id.clone(),
+                            swap_ref.pos_id.clone(),
+                            swap_ref.amount_in,
+                            pre_token_p_amount.into(),
+                            callback_op,
+                        ),
+                );
+            return;
+        }
+
+        let decimals = 10u128.pow(self.internal_unwrap_asset(token_in_id).config.extra_decimals as u32);
+        let mut legs = vec![];
+        let mut promise: Option<Promise> = None;
+        for (idx, (swap_detail, ft_amount_in)) in route.legs.into_iter().enumerate() {
+            legs.push(MarginSwapLeg {
+                dex_id: swap_detail.dex_id.clone(),
+                amount_in: ft_amount_in * decimals,
+                amount_out: 0,
+                is_returned: false,
+                is_failed: false,
+            });
+            let leg_ref = SwapReference {
+                leg: Some(idx as u8),
+                ..swap_ref.clone()
+            };
+            let leg_promise = self.internal_send_swap(swap_detail, &leg_ref, token_in_id, ft_amount_in);
+            promise = Some(match promise {
+                Some(promise) => promise.and(leg_promise),
+                None => leg_promise,
+            });
+        }
+        // The amount below the decimals of the token stays with the first leg.
+        legs[0].amount_in = swap_ref.amount_in.0 - legs[1..].iter().map(|leg| leg.amount_in).sum::<Balance>();
+
+        let mut split_swaps = pending_split_swaps();
+        account.storage_tracker.start();
+        split_swaps.insert(
+            &swap_ref.pos_id,
+            &PendingSplitSwap {
+                op: swap_ref.op.clone(),
+                liquidator_id: swap_ref.liquidator_id.clone(),
+                action_ts: swap_ref.action_ts.0,
+                pre_token_p_amount,
+                legs,
+                is_resolved: false,
+            },
+        );
+        account.storage_tracker.stop();
+        promise.unwrap().then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_SPLIT_TRADE_CALLBACK)
+                .with_unused_gas_weight(0)
+                .callback_dex_split_trade(account.account_id.clone(), swap_ref.pos_id.clone()),
+        );
+    }
+
+    /// Records the token_out returned by the dex of a leg of the split swap.
+    /// Like a single trade, only the dex of the leg, or the owner once the position
+    /// action has been pending for a while, can return it.
+    pub(crate) fn on_split_trade_leg_return(
+        &mut self,
+        sender_id: &AccountId,
+        amount: Balance,
+        sr: &SwapReference,
+        leg: u8,
+    ) {
+        let mut pending = pending_split_swaps()
+            .get(&sr.pos_id)
+            .expect("There is no action for the position");
+        let margin_config = self.internal_margin_config();
+        let swap_leg = pending.legs.get_mut(leg as usize).expect("Invalid swap leg");
+        require!(!swap_leg.is_returned && !swap_leg.is_failed, "The swap leg is already settled");
+        if sender_id == &self.internal_config().owner_id {
+            // only owner can resume blocked magin actions pending for a while
+            require!(env::block_timestamp() - pending.action_ts >= sec_to_nano(margin_config.max_position_action_wait_sec), "Please wait for the position action");
+        } else {
+            require!(sender_id == &swap_leg.dex_id, "Not allow");
+        }
+        swap_leg.is_returned = true;
+        swap_leg.amount_out = amount;
+        self.internal_advance_split_swap(&sr.account_id, &sr.pos_id, pending);
+    }
+
+    /// Stores the progress of the split swap, or settles it when all legs are done.
+    fn internal_advance_split_swap(&mut self, account_id: &AccountId, pos_id: &PosId, pending: PendingSplitSwap) {
+        if !pending.is_settleable() {
+            pending_split_swaps().insert(pos_id, &pending);
+            return;
+        }
+        let mut account = self.internal_unwrap_margin_account(account_id);
+        account.storage_tracker.start();
+        pending_split_swaps().remove(pos_id);
+        account.storage_tracker.stop();
+        account.position_latest_actions.remove(pos_id);
+
+        let amount_in: Balance = pending.legs.iter().map(|leg| leg.amount_in).sum();
+        let failed_amount_in: Balance = pending.legs.iter().filter(|leg| leg.is_failed).map(|leg| leg.amount_in).sum();
+        let amount_out: Balance = pending.legs.iter().map(|leg| leg.amount_out).sum();
+        if failed_amount_in == amount_in {
+            self.internal_revert_dex_trade(
+                account,
+                pos_id,
+                amount_in,
+                pending.pre_token_p_amount,
+                dex_trade_callback_op(&pending.op),
+            );
+            return;
+        }
+        if failed_amount_in > 0 {
+            // The refunded amount of the failed legs goes back to where it was taken from,
+            // and the operation continues with the legs that traded.
+            let mut mt = account.margin_positions.get(pos_id).unwrap();
+            if pending.op == "open" || pending.op == "increase" {
+                let mut asset_d = self.internal_unwrap_asset(&mt.token_d_id);
+                asset_d.margin_pending_debt -= failed_amount_in;
+                self.internal_set_asset_without_asset_basic_check(&mt.token_d_id, asset_d);
+            } else {
+                // Like `internal_revert_dex_trade`, the refund restores the position amount
+                // taken by the decrease first, and the rest was drawn from the collateral.
+                let mut asset_p = self.internal_unwrap_asset(&mt.token_p_id);
+                let position_amount = std::cmp::min(
+                    failed_amount_in,
+                    pending.pre_token_p_amount.saturating_sub(mt.token_p_amount),
+                );
+                asset_p.margin_position += position_amount;
+                mt.token_p_amount += position_amount;
+                if failed_amount_in > position_amount {
+                    // re-deposit those gap to supply as margin
+                    let gap = failed_amount_in - position_amount;
+                    let gap_shares = asset_p.supplied.amount_to_shares(gap, false);
+                    asset_p.supplied.deposit(gap_shares, gap);
+                    mt.token_c_shares.0 += gap_shares.0;
+                }
+                account.margin_positions.insert(pos_id, &mt);
+                self.internal_set_asset_without_asset_basic_check(&mt.token_p_id, asset_p);
+            }
+            events::emit::margin_swap_legs_failed(account_id, pos_id, failed_amount_in);
+        }
+        let sr = SwapReference {
+            account_id: account_id.clone(),
+            pos_id: pos_id.clone(),
+            amount_in: (amount_in - failed_amount_in).into(),
+            action_ts: pending.action_ts.into(),
+            op: pending.op,
+            liquidator_id: pending.liquidator_id,
+            leg: None,
+        };
+        self.internal_on_trade_return(account, amount_out, &sr);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Records which legs of the split swap failed, i.e. weren't used by their dexes.
+    #[private]
+    pub fn callback_dex_split_trade(&mut self, account_id: AccountId, pos_id: PosId) {
+        let mut pending = pending_split_swaps()
+            .get(&pos_id)
+            .expect("There is no action for the position");
+        for (idx, leg) in pending.legs.iter_mut().enumerate() {
+            let amount_in_used = match env::promise_result(idx as u64) {
+                PromiseResult::Successful(cross_call_result) => {
+                    serde_json::from_slice::<U128>(&cross_call_result).map_or(0, |amount| amount.0)
+                }
+                _ => 0,
+            };
+            if amount_in_used == 0 && !leg.is_returned {
+                leg.is_failed = true;
+            }
+        }
+        pending.is_resolved = true;
+        self.internal_advance_split_swap(&account_id, &pos_id, pending);
+    }
+}
@@ -30,6 +30,7 @@ impl Contract {
     pub fn set_mssf(&mut self, mssf: MarginStopServiceFee) {
         assert_one_yocto();
         self.assert_owner();
+        events::emit::settings_updated("set_mssf", &env::predecessor_account_id(), json!({"mssf": mssf}));
         write_mssf_to_storage(mssf);
     }
 
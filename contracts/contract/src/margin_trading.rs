@@ -1,6 +1,8 @@
-use crate::{*, events::emit::{EventDataMarginOpenResult,EventDataMarginDecreaseResult}};
+use crate::{*, events::emit::{EventDataMarginOpenResult,EventDataMarginIncreaseResult,EventDataMarginDecreaseResult}};
 use near_sdk::serde_json;
 
+pub const DCL_POOL_ID_BREAK: &str = "|";
+
 /// clients use this to indicate how to trading
 #[derive(Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
@@ -8,6 +10,21 @@ use near_sdk::serde_json;
 pub struct SwapIndication {
     pub dex_id: AccountId,
     pub swap_action_text: String,
+    /// Legs traded on the other registered dexes, each with its own part of the amount in.
+    /// The leg on dex_id trades the rest of the amount in.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub splits: Vec<SwapSplit>,
+}
+
+/// A leg of a swap split across dexes
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SwapSplit {
+    pub dex_id: AccountId,
+    /// The amount of token_in traded by this leg, in the decimals of the token.
+    pub amount_in: U128,
+    pub swap_action_text: String,
 }
 
 /// ref-v1 swap instruction
@@ -84,32 +101,63 @@ pub enum RefV1TokenReceiverMessage {
 }
 
 impl RefV1TokenReceiverMessage {
-    /// get token_in, amount_in
-    pub fn get_token_in(&self) -> (AccountId, Balance) {
+    /// Split actions into swap legs.
+    /// Each leg starts with an action carrying amount_in,
+    /// followed by hops (amount_in is None) chained on the previous token_out.
+    /// Return None if actions can't be split that way.
+    fn get_legs(&self) -> Option<Vec<&[RefV1Action]>> {
         let RefV1TokenReceiverMessage::Execute {
             referral_id: _,
             actions,
             client_echo: _,
             skip_degen_price_sync: _,
         } = self;
-        let action = actions.first().unwrap();
-        let token_in = action.get_token_in();
-        let amount_in = actions.iter().filter(|a| a.get_token_in() == token_in).map(|a| a.get_amount_in()).sum();
-        (token_in, amount_in)
+        let mut legs = vec![];
+        let mut leg_start = 0;
+        for (idx, action) in actions.iter().enumerate() {
+            let RefV1Action::Swap(swap_action) = action;
+            if swap_action.token_in == swap_action.token_out {
+                return None;
+            }
+            if swap_action.amount_in.is_some() {
+                if idx > 0 {
+                    legs.push(&actions[leg_start..idx]);
+                }
+                leg_start = idx;
+            } else if idx == 0 || actions[idx - 1].get_token_out() != swap_action.token_in {
+                return None;
+            }
+        }
+        if actions.is_empty() {
+            return None;
+        }
+        legs.push(&actions[leg_start..]);
+        Some(legs)
     }
 
-    // get token_out, min_amount_out
-    pub fn get_token_out(&self) -> (AccountId, Balance) {
-        let RefV1TokenReceiverMessage::Execute {
-            referral_id: _,
-            actions,
-            client_echo: _,
-            skip_degen_price_sync: _,
-        } = self;
-        let action = actions.last().unwrap();
-        let token_out = action.get_token_out();
-        let min_amount_out = actions.iter().filter(|a| a.get_token_out() == token_out).map(|a| a.get_min_amount_out()).sum();
-        (token_out, min_amount_out)
+    /// get token_in, amount_in
+    /// all legs should start from the same token_in.
+    pub fn get_token_in(&self) -> Option<(AccountId, Balance)> {
+        let legs = self.get_legs()?;
+        let token_in = legs[0][0].get_token_in();
+        if legs.iter().any(|leg| leg[0].get_token_in() != token_in) {
+            return None;
+        }
+        let amount_in = legs.iter().map(|leg| leg[0].get_amount_in()).sum();
+        Some((token_in, amount_in))
+    }
+
+    /// get token_out, min_amount_out
+    /// all legs should end with the same token_out,
+    /// and min_amount_out is aggregated from the last hop of each leg.
+    pub fn get_token_out(&self) -> Option<(AccountId, Balance)> {
+        let legs = self.get_legs()?;
+        let token_out = legs[0].last().unwrap().get_token_out();
+        if legs.iter().any(|leg| leg.last().unwrap().get_token_out() != token_out) {
+            return None;
+        }
+        let min_amount_out = legs.iter().map(|leg| leg.last().unwrap().get_min_amount_out()).sum();
+        Some((token_out, min_amount_out))
     }
 
     pub fn get_client_echo(&self) -> Option<String> {
@@ -175,6 +223,40 @@ impl RefV2TokenReceiverMessage {
         }
     }
 
+    /// Verify that pool_ids form a route from token_in to output_token,
+    /// where each pool_id is in the form of "token_x|token_y|fee".
+    pub fn verify_route(&self, token_in: &AccountId) -> bool {
+        if let RefV2TokenReceiverMessage::Swap {
+            pool_ids,
+            output_token,
+            min_output_amount: _,
+            skip_unwrap_near: _,
+            client_echo: _,
+        } = self
+        {
+            if pool_ids.is_empty() {
+                return false;
+            }
+            let mut current_token = token_in.to_string();
+            for pool_id in pool_ids {
+                let parts: Vec<&str> = pool_id.split(DCL_POOL_ID_BREAK).collect();
+                if parts.len() != 3 {
+                    return false;
+                }
+                current_token = if parts[0] == current_token {
+                    parts[1].to_string()
+                } else if parts[1] == current_token {
+                    parts[0].to_string()
+                } else {
+                    return false;
+                };
+            }
+            current_token == output_token.as_str()
+        } else {
+            false
+        }
+    }
+
     pub fn get_client_echo(&self) -> Option<String> {
         match self {
             RefV2TokenReceiverMessage::Swap {
@@ -224,26 +306,35 @@ impl SwapDetail {
     pub fn verify_token_in(&self, token_in: &AccountId, amount_in: Balance) -> bool {
         match &self.dex_msg {
             MsgToDex::RefV1(refv1_msg) => {
-                let (msg_token_in, msg_amount_in) = refv1_msg.get_token_in();
-                &msg_token_in == token_in && msg_amount_in == amount_in
+                if let Some((msg_token_in, msg_amount_in)) = refv1_msg.get_token_in() {
+                    &msg_token_in == token_in && msg_amount_in == amount_in
+                } else {
+                    false
+                }
             }
-            MsgToDex::RefV2(_refv2_msg) => true,
+            MsgToDex::RefV2(refv2_msg) => refv2_msg.verify_route(token_in),
         }
     }
 
     pub fn verify_token_out(&self, token_out: &AccountId, min_amount_out: Balance) -> bool {
+        self.get_token_out().map_or(false, |(msg_token_out, msg_min_amount_out)| {
+            &msg_token_out == token_out && msg_min_amount_out == min_amount_out
+        })
+    }
+
+    /// get token_out, min_amount_out
+    /// return None if the dex wouldn't send token_out back as it is.
+    pub fn get_token_out(&self) -> Option<(AccountId, Balance)> {
         match &self.dex_msg {
-            MsgToDex::RefV1(refv1_msg) => {
-                let (msg_token_out, msg_min_amount_out) = refv1_msg.get_token_out();
-                &msg_token_out == token_out && msg_min_amount_out == min_amount_out
-            }
+            MsgToDex::RefV1(refv1_msg) => refv1_msg.get_token_out(),
             MsgToDex::RefV2(refv2_msg) => {
                 let (msg_token_out, msg_min_amount_out, skip_unwrap_near) =
                     refv2_msg.get_token_out();
-                &msg_token_out == token_out
-                    && msg_min_amount_out == min_amount_out
-                    && skip_unwrap_near.is_some()
-                    && skip_unwrap_near.unwrap()
+                if skip_unwrap_near == Some(true) {
+                    Some((msg_token_out, msg_min_amount_out))
+                } else {
+                    None
+                }
             }
         }
     }
@@ -270,6 +361,38 @@ impl SwapDetail {
     }
 }
 
+/// A swap split into legs on distinct dexes,
+/// along with the amount of token_in each leg trades, in the decimals of the token.
+pub struct SwapRoute {
+    pub legs: Vec<(SwapDetail, Balance)>,
+}
+
+impl SwapRoute {
+    /// All legs should start from token_in and trade amount_in altogether.
+    pub fn verify_token_in(&self, token_in: &AccountId, amount_in: Balance) -> bool {
+        self.legs.iter().map(|(_, leg_amount_in)| leg_amount_in).sum::<Balance>() == amount_in
+            && self
+                .legs
+                .iter()
+                .all(|(swap_detail, leg_amount_in)| swap_detail.verify_token_in(token_in, *leg_amount_in))
+    }
+
+    /// All legs should end with token_out,
+    /// and min_amount_out is aggregated from the min_amount_out of each leg.
+    pub fn verify_token_out(&self, token_out: &AccountId, min_amount_out: Balance) -> bool {
+        let mut total_min_amount_out = 0;
+        for (swap_detail, _) in self.legs.iter() {
+            match swap_detail.get_token_out() {
+                Some((leg_token_out, leg_min_amount_out)) if &leg_token_out == token_out => {
+                    total_min_amount_out += leg_min_amount_out;
+                }
+                _ => return false,
+            }
+        }
+        total_min_amount_out == min_amount_out
+    }
+}
+
 /// Protocol set info to this structure along with swap msg to dex,
 /// and dex would echo back when transfer token_out back to protocol.
 #[derive(Serialize, Deserialize, Clone)]
@@ -282,6 +405,9 @@ pub struct SwapReference {
     pub action_ts: U64,
     pub op: String,
     pub liquidator_id: Option<AccountId>,
+    /// The index of the leg when the swap is split across dexes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leg: Option<u8>,
 }
 
 impl SwapReference {
@@ -393,28 +519,50 @@ pub struct StopServiceFeeInfo {
 }
 
 impl Contract {
-    pub(crate) fn parse_swap_indication(&self, swap_indication: &SwapIndication) -> SwapDetail {
+    /// Parses the legs of the swap indication, where the leg on dex_id trades
+    /// the part of ft_amount_in that isn't traded by the splits.
+    pub(crate) fn parse_swap_indication(&self, swap_indication: &SwapIndication, ft_amount_in: Balance) -> SwapRoute {
+        let mut main_amount_in = ft_amount_in;
+        let mut legs: Vec<(SwapDetail, Balance)> = vec![];
+        for split in swap_indication.splits.iter() {
+            assert!(split.amount_in.0 > 0, "Invalid split amount_in");
+            assert!(
+                split.dex_id != swap_indication.dex_id
+                    && legs.iter().all(|(swap_detail, _)| swap_detail.dex_id != split.dex_id),
+                "Each dex can only trade one leg"
+            );
+            main_amount_in = main_amount_in
+                .checked_sub(split.amount_in.0)
+                .expect("Split amounts exceed the amount in");
+            legs.push((self.parse_swap_detail(&split.dex_id, &split.swap_action_text), split.amount_in.0));
+        }
+        assert!(main_amount_in > 0, "Split amounts exceed the amount in");
+        legs.insert(0, (self.parse_swap_detail(&swap_indication.dex_id, &swap_indication.swap_action_text), main_amount_in));
+        SwapRoute { legs }
+    }
+
+    fn parse_swap_detail(&self, dex_id: &AccountId, swap_action_text: &str) -> SwapDetail {
         let margin_config = self.internal_margin_config();
         let ver = margin_config
             .registered_dexes
-            .get(&swap_indication.dex_id)
+            .get(dex_id)
             .expect("Unregistered dex");
         if ver == &1_u8 {
             let v1msg = serde_json::from_str::<RefV1TokenReceiverMessage>(
-                &swap_indication.swap_action_text,
+                swap_action_text,
             )
             .expect("Invalid swap_action_text");
             SwapDetail {
-                dex_id: swap_indication.dex_id.clone(),
+                dex_id: dex_id.clone(),
                 dex_msg: MsgToDex::RefV1(v1msg),
             }
         } else if ver == &2_u8 {
             let v2msg = serde_json::from_str::<RefV2TokenReceiverMessage>(
-                &swap_indication.swap_action_text,
+                swap_action_text,
             )
             .expect("Invalid swap_action_text");
             SwapDetail {
-                dex_id: swap_indication.dex_id.clone(),
+                dex_id: dex_id.clone(),
                 dex_msg: MsgToDex::RefV2(v2msg),
             }
         } else {
@@ -424,6 +572,24 @@ impl Contract {
 }
 
 impl Contract {
+    /// Applies the token_out amount returned by the dex to the margin operation of the swap reference.
+    pub(crate) fn internal_on_trade_return(&mut self, account: MarginAccount, amount: Balance, sr: &SwapReference) {
+        if sr.op == "open" {
+            self.on_open_trade_return(account, amount, sr);
+        } else if sr.op == "increase" {
+            self.on_increase_trade_return(account, amount, sr);
+        } else if sr.op == "decrease"
+            || sr.op == "close"
+            || sr.op == "liquidate"
+            || sr.op == "forceclose"
+            || sr.op == "stop_loss"
+            || sr.op == "stop_profit"
+        {
+            let event = self.on_decrease_trade_return(account, amount, sr);
+            events::emit::margin_decrease_succeeded(&sr.op, event);
+        }
+    }
+
     pub(crate) fn on_open_trade_return(
         &mut self,
         mut account: MarginAccount,
@@ -476,6 +642,7 @@ impl Contract {
         mt.is_locking = false;
         // Update existing margin_position storage
         account.margin_positions.insert(&sr.pos_id, &mt);
+        account.add_affected_position_farms(&mt);
 
         self.internal_set_asset_without_asset_basic_check(&mt.token_d_id, asset_debt);
         self.internal_set_asset_without_asset_basic_check(&mt.token_p_id, asset_position);
@@ -496,6 +663,57 @@ impl Contract {
         self.internal_force_set_margin_account(&account_id, account);
     }
 
+    pub(crate) fn on_increase_trade_return(
+        &mut self,
+        mut account: MarginAccount,
+        amount: Balance,
+        sr: &SwapReference,
+    ) {
+        let account_id = account.account_id.clone();
+        let mut mt = account.margin_positions.get(&sr.pos_id).unwrap().clone();
+        let mut asset_debt = self.internal_unwrap_asset(&mt.token_d_id);
+        let mut asset_position = self.internal_unwrap_asset(&mt.token_p_id);
+
+        asset_debt.margin_pending_debt -= sr.amount_in.0;
+        let debt_shares = asset_debt
+            .margin_debt
+            .amount_to_shares(sr.amount_in.0, true);
+        asset_debt.margin_debt.deposit(debt_shares, sr.amount_in.0);
+        asset_position.margin_position += amount;
+
+        let (debt_cap, uahpi_at_open) = rebase_hp_fee_position(
+            mt.debt_cap,
+            mt.uahpi_at_open,
+            asset_debt.unit_acc_hp_interest,
+            sr.amount_in.0,
+        );
+        mt.debt_cap = debt_cap;
+        mt.uahpi_at_open = uahpi_at_open;
+        mt.token_d_shares.0 += debt_shares.0;
+        mt.token_p_amount += amount;
+        mt.is_locking = false;
+        // Update existing margin_position storage
+        account.margin_positions.insert(&sr.pos_id, &mt);
+        account.add_affected_position_farms(&mt);
+
+        self.internal_set_asset_without_asset_basic_check(&mt.token_d_id, asset_debt);
+        self.internal_set_asset_without_asset_basic_check(&mt.token_p_id, asset_position);
+
+        let event = EventDataMarginIncreaseResult {
+            account_id: account.account_id.clone(),
+            pos_id: sr.pos_id.clone(),
+            token_d_id: mt.token_d_id.clone(),
+            token_d_amount: sr.amount_in.0,
+            token_d_shares: mt.token_d_shares,
+            token_p_id: mt.token_p_id.clone(),
+            token_p_amount: mt.token_p_amount,
+            debt_cap: mt.debt_cap,
+            uahpi_at_open: mt.uahpi_at_open,
+        };
+        events::emit::margin_increase_succeeded(event);
+        self.internal_force_set_margin_account(&account_id, account);
+    }
+
     /// Calculates debt repayment amounts including holding position fee
     ///
     /// Returns a DebtRepaymentResult containing:
@@ -608,10 +826,10 @@ impl Contract {
                 .and_modify(|v| *v += uncovered_debt)
                 .or_insert(uncovered_debt);
             write_protocol_debts_to_storage(protocol_debts);
-            events::emit::new_protocol_debts(&position.token_d_id, uncovered_debt);
+            events::emit::new_protocol_debts(&position.token_d_id, uncovered_debt, asset_debt.config.extra_decimals);
         }
 
-        events::emit::forceclose_protocol_loss(&position.token_d_id, remaining_debt);
+        events::emit::forceclose_protocol_loss(&position.token_d_id, remaining_debt, asset_debt.config.extra_decimals);
         asset_debt
             .margin_debt
             .withdraw(position.token_d_shares, remaining_debt);
@@ -621,7 +839,7 @@ impl Contract {
     /// Settles a closed position: converts remaining assets to benefits
     ///
     /// Returns optional StopServiceFeeInfo if stop order was attached
-    fn settle_closed_position(
+    pub(crate) fn settle_closed_position(
         &mut self,
         account: &mut MarginAccount,
         position: &MarginTradingPosition,
@@ -650,6 +868,8 @@ impl Contract {
         account.storage_tracker.start();
         account.margin_positions.remove(pos_id);
         account.storage_tracker.stop();
+        account.remove_margin_position_transfer(pos_id);
+        account.add_affected_position_farms(position);
 
         // Extract stop service fee if present
         account.stops.remove(pos_id).map(|margin_stop| StopServiceFeeInfo {
@@ -742,6 +962,7 @@ impl Contract {
         // === Section 4: Position settlement ===
         position.is_locking = false;
         account.margin_positions.insert(&sr.pos_id, &position);
+        account.add_affected_position_farms(&position);
 
         // Build result event before potential position removal
         let event = EventDataMarginDecreaseResult {
@@ -959,4 +1180,163 @@ pub fn deposit_benefit_to_account(margin_account: &mut MarginAccount, token_id:
     if benefit_shares > 0 {
         margin_account.deposit_supply_shares(token_id, &U128(benefit_shares));
     }
-}
\ No newline at end of file
+}
+/// Merges extra debt into a position's holding-position fee basis.
+/// Returns the new (debt_cap, uahpi_at_open), chosen so that the fee accrued so far is preserved
+/// (rounded in favor of the protocol) and future fee accrues on the increased debt_cap.
+pub fn rebase_hp_fee_position(
+    debt_cap: Balance,
+    uahpi_at_open: Balance,
+    unit_acc_hp_interest: Balance,
+    extra_debt: Balance,
+) -> (Balance, Balance) {
+    let new_debt_cap = debt_cap + extra_debt;
+    let accrued_hp_fee = u128_ratio(debt_cap, unit_acc_hp_interest - uahpi_at_open, UNIT);
+    if accrued_hp_fee == 0 || new_debt_cap == 0 {
+        return (new_debt_cap, unit_acc_hp_interest);
+    }
+    let uahpi_delta = ((U256::from(accrued_hp_fee) * U256::from(UNIT) + U256::from(new_debt_cap - 1))
+        / U256::from(new_debt_cap))
+    .as_u128();
+    (new_debt_cap, unit_acc_hp_interest.saturating_sub(uahpi_delta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_hp_fee_position_without_accrued_fee() {
+        assert_eq!(rebase_hp_fee_position(100, 5 * UNIT, 5 * UNIT, 50), (150, 5 * UNIT));
+    }
+
+    #[test]
+    fn test_rebase_hp_fee_position_preserves_accrued_fee() {
+        let debt_cap = 1000 * UNIT;
+        let uahpi_at_open = UNIT;
+        let unit_acc_hp_interest = UNIT + UNIT / 10;
+        let accrued = u128_ratio(debt_cap, unit_acc_hp_interest - uahpi_at_open, UNIT);
+        assert_eq!(accrued, 100 * UNIT);
+
+        let (new_debt_cap, new_uahpi_at_open) =
+            rebase_hp_fee_position(debt_cap, uahpi_at_open, unit_acc_hp_interest, 3000 * UNIT);
+        assert_eq!(new_debt_cap, 4000 * UNIT);
+        assert_eq!(u128_ratio(new_debt_cap, unit_acc_hp_interest - new_uahpi_at_open, UNIT), accrued);
+
+        // the increased debt_cap accrues from now on
+        let later = unit_acc_hp_interest + UNIT / 100;
+        assert_eq!(u128_ratio(new_debt_cap, later - new_uahpi_at_open, UNIT), accrued + 40 * UNIT);
+    }
+
+    #[test]
+    fn test_rebase_hp_fee_position_rounds_up_accrued_fee() {
+        let (new_debt_cap, new_uahpi_at_open) = rebase_hp_fee_position(7, 0, UNIT / 3, 4);
+        let accrued = u128_ratio(7, UNIT / 3, UNIT);
+        assert!(u128_ratio(new_debt_cap, UNIT / 3 - new_uahpi_at_open, UNIT) >= accrued);
+    }
+
+    fn v1_swap_detail(actions: &str) -> SwapDetail {
+        let msg = format!("{{\"actions\":{}}}", actions);
+        SwapDetail {
+            dex_id: "dex.near".parse().unwrap(),
+            dex_msg: MsgToDex::RefV1(serde_json::from_str(&msg).unwrap()),
+        }
+    }
+
+    fn v2_swap_detail(pool_ids: &str) -> SwapDetail {
+        let msg = format!(
+            "{{\"Swap\":{{\"pool_ids\":{},\"output_token\":\"c.near\",\"min_output_amount\":\"90\",\"skip_unwrap_near\":true}}}}",
+            pool_ids
+        );
+        SwapDetail {
+            dex_id: "dcl.near".parse().unwrap(),
+            dex_msg: MsgToDex::RefV2(serde_json::from_str(&msg).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_verify_ref_v1_multi_hop_and_split_route() {
+        let a: AccountId = "a.near".parse().unwrap();
+        let c: AccountId = "c.near".parse().unwrap();
+        let swap_detail = v1_swap_detail(r#"[
+            {"pool_id":0,"token_in":"a.near","amount_in":"60","token_out":"b.near","min_amount_out":"1"},
+            {"pool_id":1,"token_in":"b.near","token_out":"c.near","min_amount_out":"50"},
+            {"pool_id":2,"token_in":"a.near","amount_in":"40","token_out":"c.near","min_amount_out":"35"}
+        ]"#);
+        assert!(swap_detail.verify_token_in(&a, 100));
+        assert!(swap_detail.verify_token_out(&c, 85));
+        assert!(!swap_detail.verify_token_in(&a, 60));
+        assert!(!swap_detail.verify_token_out(&c, 86));
+    }
+
+    #[test]
+    fn test_verify_ref_v1_invalid_route() {
+        let a: AccountId = "a.near".parse().unwrap();
+        let c: AccountId = "c.near".parse().unwrap();
+        // broken hop chain
+        let swap_detail = v1_swap_detail(r#"[
+            {"pool_id":0,"token_in":"a.near","amount_in":"100","token_out":"b.near","min_amount_out":"1"},
+            {"pool_id":1,"token_in":"d.near","token_out":"c.near","min_amount_out":"90"}
+        ]"#);
+        assert!(!swap_detail.verify_token_in(&a, 100));
+        assert!(!swap_detail.verify_token_out(&c, 90));
+        // split leg starts from another token
+        let swap_detail = v1_swap_detail(r#"[
+            {"pool_id":0,"token_in":"a.near","amount_in":"60","token_out":"c.near","min_amount_out":"50"},
+            {"pool_id":1,"token_in":"b.near","amount_in":"40","token_out":"c.near","min_amount_out":"40"}
+        ]"#);
+        assert!(!swap_detail.verify_token_in(&a, 60));
+        // split leg ends with another token
+        let swap_detail = v1_swap_detail(r#"[
+            {"pool_id":0,"token_in":"a.near","amount_in":"60","token_out":"c.near","min_amount_out":"50"},
+            {"pool_id":1,"token_in":"a.near","amount_in":"40","token_out":"b.near","min_amount_out":"40"}
+        ]"#);
+        assert!(!swap_detail.verify_token_out(&c, 50));
+        // first action without amount_in
+        let swap_detail = v1_swap_detail(r#"[
+            {"pool_id":0,"token_in":"a.near","token_out":"c.near","min_amount_out":"50"}
+        ]"#);
+        assert!(!swap_detail.verify_token_in(&a, 0));
+    }
+
+    #[test]
+    fn test_verify_ref_v2_multi_hop_route() {
+        let a: AccountId = "a.near".parse().unwrap();
+        let c: AccountId = "c.near".parse().unwrap();
+        let swap_detail = v2_swap_detail(r#"["a.near|b.near|2000","b.near|c.near|400"]"#);
+        assert!(swap_detail.verify_token_in(&a, 100));
+        assert!(swap_detail.verify_token_out(&c, 90));
+        assert!(!swap_detail.verify_token_in(&c, 100));
+        let swap_detail = v2_swap_detail(r#"["a.near|b.near|2000","a.near|c.near|400"]"#);
+        assert!(!swap_detail.verify_token_in(&a, 100));
+        let swap_detail = v2_swap_detail(r#"["a.near|c.near"]"#);
+        assert!(!swap_detail.verify_token_in(&a, 100));
+    }
+
+    #[test]
+    fn test_verify_cross_dex_split_route() {
+        let a: AccountId = "a.near".parse().unwrap();
+        let c: AccountId = "c.near".parse().unwrap();
+        let swap_route = SwapRoute {
+            legs: vec![
+                (v1_swap_detail(r#"[
+                    {"pool_id":0,"token_in":"a.near","amount_in":"60","token_out":"b.near","min_amount_out":"1"},
+                    {"pool_id":1,"token_in":"b.near","token_out":"c.near","min_amount_out":"50"}
+                ]"#), 60),
+                (v2_swap_detail(r#"["a.near|c.near|400"]"#), 100),
+            ],
+        };
+        assert!(swap_route.verify_token_in(&a, 160));
+        assert!(swap_route.verify_token_out(&c, 140));
+        assert!(!swap_route.verify_token_in(&a, 100));
+        assert!(!swap_route.verify_token_out(&c, 90));
+        // the ref-v1 leg trades another amount than its part
+        let swap_route = SwapRoute {
+            legs: vec![
+                (v1_swap_detail(r#"[{"pool_id":0,"token_in":"a.near","amount_in":"60","token_out":"c.near","min_amount_out":"50"}]"#), 50),
+                (v2_swap_detail(r#"["a.near|c.near|400"]"#), 110),
+            ],
+        };
+        assert!(!swap_route.verify_token_in(&a, 160));
+    }
+}
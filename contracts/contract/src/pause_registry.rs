@@ -0,0 +1,180 @@
+use crate::*;
+
+/// The kind of actions that can be paused.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum PauseKind {
+    /// Regular account actions, including deposits. The target is the token ID.
+    Execute,
+    /// Liquidations and force closes. The target is the position, e.g. `REGULAR`
+    /// or the shadow LP token ID. Margin liquidations have no target.
+    /// They're paused independently, pausing Execute or MarginTrading doesn't stop them.
+    Liquidation,
+    /// Margin trading. The target is the token ID.
+    MarginTrading,
+    /// Explicit farm claims.
+    FarmClaim,
+    /// Booster staking and unstaking. The target is the booster token ID.
+    BoosterStaking,
+    /// Shadow LP casting. The target is the shadow ID.
+    ShadowCast,
+}
+
+/// A paused action kind, either entirely or only for the given target.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseKey {
+    pub kind: PauseKind,
+    pub target: Option<String>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetCapacityView {
+    pub token_id: TokenId,
+    pub can_deposit: bool,
+    pub can_withdraw: bool,
+    pub can_use_as_collateral: bool,
+    pub can_borrow: bool,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseStateView {
+    pub paused: Vec<PauseKey>,
+    /// Assets with at least one disabled capacity.
+    pub disabled_asset_capacities: Vec<AssetCapacityView>,
+}
+
+pub fn read_pause_registry_from_storage() -> HashSet<PauseKey> {
+    if let Some(content) = env::storage_read(PAUSE_REGISTRY_KEY.as_bytes()) {
+        HashSet::try_from_slice(&content).expect("deserialize pause registry failed.")
+    } else {
+        HashSet::new()
+    }
+}
+
+pub fn write_pause_registry_to_storage(data: HashSet<PauseKey>) {
+    env::storage_write(PAUSE_REGISTRY_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+/// Panics if the action kind is paused entirely or for any of the given targets.
+pub fn assert_not_paused(pause_registry: &HashSet<PauseKey>, kind: PauseKind, targets: &[String]) {
+    if pause_registry.is_empty() {
+        return;
+    }
+    let key = PauseKey { kind, target: None };
+    require!(!pause_registry.contains(&key), format!("{:?} is paused", key.kind));
+    for target in targets {
+        let key = PauseKey { kind: key.kind.clone(), target: Some(target.clone()) };
+        require!(!pause_registry.contains(&key), format!("{:?} is paused for {}", key.kind, target));
+    }
+}
+
+impl Action {
+    pub fn pause_kind_and_targets(&self) -> (PauseKind, Vec<String>) {
+        match self {
+            Action::Withdraw(asset_amount)
+            | Action::ClientEchoWithdraw { asset_amount, .. }
+            | Action::IncreaseCollateral(asset_amount)
+            | Action::PositionIncreaseCollateral { asset_amount, .. }
+            | Action::DecreaseCollateral(asset_amount)
+            | Action::PositionDecreaseCollateral { asset_amount, .. }
+            | Action::Borrow(asset_amount)
+            | Action::PositionBorrow { asset_amount, .. }
+            | Action::Repay(asset_amount)
+            | Action::PositionRepay { asset_amount, .. } => {
+                (PauseKind::Execute, vec![asset_amount.token_id.to_string()])
+            }
+            Action::Liquidate { position, .. } | Action::ForceClose { position, .. } => (
+                PauseKind::Liquidation,
+                vec![position.clone().unwrap_or_else(|| REGULAR_POSITION.to_string())],
+            ),
+            Action::LiquidateMTPositionDirect { .. } => (PauseKind::Liquidation, vec![]),
+        }
+    }
+}
+
+impl MarginAction {
+    pub fn pause_kind_and_targets(&self) -> (PauseKind, Vec<String>) {
+        match self {
+            MarginAction::Withdraw { token_id, .. } => (PauseKind::MarginTrading, vec![token_id.to_string()]),
+            MarginAction::OpenPosition {
+                token_c_id,
+                token_d_id,
+                token_p_id,
+                ..
+            } => (
+                PauseKind::MarginTrading,
+                vec![token_c_id.to_string(), token_d_id.to_string(), token_p_id.to_string()],
+            ),
+            MarginAction::LiquidateMTPosition { .. } | MarginAction::ForceCloseMTPosition { .. } => {
+                (PauseKind::Liquidation, vec![])
+            }
+            _ => (PauseKind::MarginTrading, vec![]),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Pauses the action kind, entirely or only for the given target.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner, a PauseGuardian or guardians.
+    #[payable]
+    pub fn pause(&mut self, kind: PauseKind, target: Option<String>) {
+        assert_one_yocto();
+        self.assert_role_or_guardians(Role::PauseGuardian);
+        let mut pause_registry = read_pause_registry_from_storage();
+        let key = PauseKey { kind, target };
+        events::emit::paused(&env::predecessor_account_id(), &key);
+        require!(pause_registry.insert(key), "Already paused");
+        write_pause_registry_to_storage(pause_registry);
+    }
+
+    /// Unpauses the action kind, entirely or only for the given target.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn unpause(&mut self, kind: PauseKind, target: Option<String>) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut pause_registry = read_pause_registry_from_storage();
+        let key = PauseKey { kind, target };
+        require!(pause_registry.remove(&key), "Not paused");
+        events::emit::unpaused(&env::predecessor_account_id(), &key);
+        write_pause_registry_to_storage(pause_registry);
+    }
+
+    /// Returns paused action kinds and assets with disabled capacities.
+    pub fn get_pause_state(&self) -> PauseStateView {
+        let mut paused: Vec<PauseKey> = read_pause_registry_from_storage().into_iter().collect();
+        paused.sort();
+        let disabled_asset_capacities = self
+            .asset_ids
+            .iter()
+            .filter_map(|token_id| {
+                let config = self.internal_unwrap_asset(&token_id).config;
+                if config.can_deposit && config.can_withdraw && config.can_use_as_collateral && config.can_borrow {
+                    None
+                } else {
+                    Some(AssetCapacityView {
+                        token_id,
+                        can_deposit: config.can_deposit,
+                        can_withdraw: config.can_withdraw,
+                        can_use_as_collateral: config.can_use_as_collateral,
+                        can_borrow: config.can_borrow,
+                    })
+                }
+            })
+            .collect();
+        PauseStateView {
+            paused,
+            disabled_asset_capacities,
+        }
+    }
+}
@@ -64,6 +64,17 @@ impl Position {
         }
     }
 
+    pub fn is_borrowed(&self, token_id: &TokenId) -> bool {
+        match self {
+            Position::RegularPosition(regular_position) => {
+                regular_position.borrowed.contains_key(token_id)
+            }
+            Position::LPTokenPosition(lp_token_position) => {
+                lp_token_position.borrowed.contains_key(token_id)
+            }
+        }
+    }
+
     pub fn increase_collateral(&mut self, token_id: &TokenId, shares: Shares){
         match self {
             Position::RegularPosition(regular_position) => {
@@ -7,6 +7,8 @@ use near_sdk::serde_json;
 pub enum PriceReceiverMsg {
     Execute { actions: Vec<Action> },
     MarginExecute { actions: Vec<MarginAction> },
+    SwapReserve { swap: ReserveSwap },
+    SwapBeneficiaryFee { swap: BeneficiaryFeeSwap },
 }
 
 impl Contract {
@@ -51,6 +53,15 @@ impl OraclePriceReceiver for Contract {
                 self.internal_margin_execute(&sender_id, &mut account, actions, data.into());
                 self.internal_set_margin_account(&sender_id, account);
             }
+            PriceReceiverMsg::SwapReserve { swap } => {
+                self.assert_account_role_or_guardians(&sender_id, Role::TreasuryManager);
+                self.validate_price_data(&data);
+                self.internal_swap_reserve(&sender_id, swap, &data.into());
+            }
+            PriceReceiverMsg::SwapBeneficiaryFee { swap } => {
+                self.validate_price_data(&data);
+                self.internal_swap_beneficiary_fee(swap, &data.into());
+            }
         };
     }
 }
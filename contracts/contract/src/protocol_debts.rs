@@ -12,8 +12,132 @@ pub fn write_protocol_debts_to_storage(data: HashMap<TokenId, u128>) {
     env::storage_write(PROTOCOL_DEBTS_KEY.as_bytes(), &data.try_to_vec().unwrap());
 }
 
+/// The projected resolution of the protocol debt of an asset.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProtocolDebtHaircutView {
+    pub token_id: TokenId,
+    #[serde(with = "u128_dec_format")]
+    pub protocol_debt: Balance,
+    /// The part of the debt covered by the reserve, up to the liquidity that isn't lent out.
+    #[serde(with = "u128_dec_format")]
+    pub reserve_amount: Balance,
+    /// The part of the debt taken from the suppliers.
+    #[serde(with = "u128_dec_format")]
+    pub haircut_amount: Balance,
+    /// The haircut relative to the supplied balance, where 10000 is 100%.
+    pub haircut_rate: u32,
+    /// The part of the debt that stays recorded, because the supplied balance
+    /// can't go below the number of supplied shares or the lent out liquidity.
+    #[serde(with = "u128_dec_format")]
+    pub remaining_debt: Balance,
+}
+
+impl Contract {
+    /// Repays the protocol debt of the token with the given amount.
+    /// Returns the repaid amount.
+    pub fn internal_repay_protocol_debt(&mut self, token_id: &TokenId, amount: Balance) -> Balance {
+        let mut protocol_debts = read_protocol_debts_from_storage();
+        if let Some(debt) = protocol_debts.remove(token_id) {
+            let repay_amount = std::cmp::min(amount, debt);
+            let remain_debt = debt - repay_amount;
+            if remain_debt > 0 {
+                protocol_debts.insert(token_id.clone(), remain_debt);
+            }
+            write_protocol_debts_to_storage(protocol_debts);
+            events::emit::repay_protocol_debts(
+                token_id,
+                repay_amount,
+                self.internal_unwrap_asset(token_id).config.extra_decimals,
+            );
+            repay_amount
+        } else {
+            0
+        }
+    }
+
+    /// Projects how the protocol debt is resolved: the reserve covers it first,
+    /// and the rest of the shortfall is taken from the supplied balance, up to two limits:
+    /// - The supplied balance can't go below the number of supplied shares, which is the
+    ///   invariant of the pool that share amounts rely on (see `Pool::shares_to_amount`).
+    ///   So the suppliers can lose at most the interest they earned above 1:1.
+    /// - The reserve part and the haircut together can't exceed the liquidity that isn't
+    ///   lent out, since the borrowed and margin debts are still owed to the pool.
+    /// The rest of the shortfall stays recorded as the protocol debt.
+    pub fn internal_project_protocol_debt_haircut(
+        &self,
+        token_id: &TokenId,
+        asset: &Asset,
+        protocol_debt: Balance,
+    ) -> ProtocolDebtHaircutView {
+        let available_amount = asset.available_amount();
+        let reserve_amount = std::cmp::min(std::cmp::min(asset.reserved, available_amount), protocol_debt);
+        let shortfall = protocol_debt - reserve_amount;
+        let max_haircut_amount = std::cmp::min(
+            asset.supplied.balance.saturating_sub(asset.supplied.shares.0),
+            available_amount - reserve_amount,
+        );
+        let haircut_amount = std::cmp::min(max_haircut_amount, shortfall);
+        let haircut_rate = if asset.supplied.balance > 0 {
+            (U256::from(haircut_amount) * U256::from(MAX_RATIO) / U256::from(asset.supplied.balance)).as_u32()
+        } else {
+            0
+        };
+        ProtocolDebtHaircutView {
+            token_id: token_id.clone(),
+            protocol_debt,
+            reserve_amount,
+            haircut_amount,
+            haircut_rate,
+            remaining_debt: protocol_debt - reserve_amount - haircut_amount,
+        }
+    }
+}
+
 #[near_bindgen]
 impl Contract {
+    /// Resolves the protocol debt of the asset by taking it from the reserve first,
+    /// and then spreading the rest across the suppliers by reducing the supplied balance.
+    /// The part of the debt that can't be covered stays recorded.
+    /// Returns the resolution.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a TreasuryManager.
+    #[payable]
+    pub fn socialize_protocol_debt(&mut self, token_id: AccountId) -> ProtocolDebtHaircutView {
+        assert_one_yocto();
+        self.assert_role(Role::TreasuryManager);
+        let mut protocol_debts = read_protocol_debts_from_storage();
+        let protocol_debt = protocol_debts.remove(&token_id).expect("No protocol debt");
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let resolution = self.internal_project_protocol_debt_haircut(&token_id, &asset, protocol_debt);
+        asset.reserved -= resolution.reserve_amount;
+        asset.supplied.balance -= resolution.haircut_amount;
+        self.internal_set_asset(&token_id, asset);
+        if resolution.remaining_debt > 0 {
+            protocol_debts.insert(token_id.clone(), resolution.remaining_debt);
+        }
+        write_protocol_debts_to_storage(protocol_debts);
+        events::emit::socialize_protocol_debt(&env::predecessor_account_id(), &resolution);
+        resolution
+    }
+
+    /// Returns the projected resolution of the protocol debts of the given tokens,
+    /// or of all tokens with protocol debts.
+    pub fn get_projected_protocol_debt_haircuts(&self, token_ids: Option<Vec<AccountId>>) -> Vec<ProtocolDebtHaircutView> {
+        let protocol_debts = read_protocol_debts_from_storage();
+        let mut token_ids = token_ids.unwrap_or_else(|| protocol_debts.keys().cloned().collect());
+        token_ids.sort();
+        token_ids
+            .into_iter()
+            .filter_map(|token_id| {
+                let protocol_debt = *protocol_debts.get(&token_id)?;
+                let asset = self.internal_unwrap_asset(&token_id);
+                Some(self.internal_project_protocol_debt_haircut(&token_id, &asset, protocol_debt))
+            })
+            .collect()
+    }
+
     pub fn list_protocol_debts(&self, token_ids: Vec<AccountId>) -> HashMap<AccountId, Option<U128>> {
         let protocol_debts = read_protocol_debts_from_storage();
         token_ids.into_iter().map(|token_id| (token_id.clone(), protocol_debts.get(&token_id).map(|v| U128(*v)))).collect()
@@ -5,13 +5,14 @@ impl Contract {
     #[payable]
     pub fn append_reliable_liquidator_whitelist(&mut self, liquidator_list: Vec<String>) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::RiskAdmin);
         let mut liquidator_whitelist: HashSet<String> =
             if env::storage_has_key(RELIABLE_LIQUIDATOR_WHITELIST.as_bytes()) {
                 internal_get_reliable_liquidator_whitelist()
             } else {
                 HashSet::new()
             };
+        events::emit::settings_updated("append_reliable_liquidator_whitelist", &env::predecessor_account_id(), json!({"liquidator_list": liquidator_list}));
         for liquidator in liquidator_list {
             let is_success = liquidator_whitelist.insert(liquidator.clone());
             require!(is_success, format!("exist liquidator: {}", liquidator));
@@ -25,8 +26,9 @@ impl Contract {
     #[payable]
     pub fn remove_reliable_liquidator_whitelist(&mut self, liquidator_list: Vec<String>) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::RiskAdmin);
         let mut liquidator_whitelist = internal_get_reliable_liquidator_whitelist();
+        events::emit::settings_updated("remove_reliable_liquidator_whitelist", &env::predecessor_account_id(), json!({"liquidator_list": liquidator_list}));
         for liquidator in liquidator_list {
             let is_success = liquidator_whitelist.remove(&liquidator);
             require!(is_success, format!("liquidator {} not exist", liquidator));
@@ -0,0 +1,281 @@
+use crate::*;
+
+/// What a reserve contribution is meant to fund.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum ReserveContributionPurpose {
+    FarmBudget,
+    BadDebtBackstop,
+    General,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReserveContribution {
+    pub token_id: TokenId,
+    pub purpose: ReserveContributionPurpose,
+    pub contributor_id: AccountId,
+    /// The contributed amount that is still attributed to the contributor, in inner decimals.
+    pub amount: U128,
+}
+
+/// The contributions of a token for a purpose.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ReserveContributionPool {
+    /// The total contributions, in inner decimals.
+    pub total: Balance,
+    /// The attributed amount of every contributor, in inner decimals.
+    pub contributions: UnorderedMap<AccountId, Balance>,
+    /// The amount being returned while the contributions are returned page by page.
+    /// No contributions are attributed meanwhile.
+    pub returning_amount: Option<Balance>,
+}
+
+impl ReserveContributionPool {
+    pub fn new(token_id: &TokenId, purpose: ReserveContributionPurpose) -> Self {
+        let prefix = [
+            RESERVE_CONTRIBUTORS_KEY.as_bytes(),
+            &(token_id.clone(), purpose).try_to_vec().unwrap(),
+        ]
+        .concat();
+        Self {
+            total: 0,
+            contributions: UnorderedMap::new(prefix),
+            returning_amount: None,
+        }
+    }
+}
+
+/// The contribution pools, keyed by the token and the purpose.
+/// Deposits only read and write the pool of the contribution.
+pub fn reserve_contribution_pools() -> LookupMap<(TokenId, ReserveContributionPurpose), ReserveContributionPool> {
+    LookupMap::new(RESERVE_CONTRIBUTIONS_KEY.as_bytes())
+}
+
+pub fn read_reserve_contribution_min_amounts_from_storage() -> HashMap<TokenId, U128> {
+    if let Some(content) = env::storage_read(RESERVE_CONTRIBUTION_MIN_AMOUNTS_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize reserve contribution min amounts failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_reserve_contribution_min_amounts_to_storage(data: HashMap<TokenId, U128>) {
+    env::storage_write(RESERVE_CONTRIBUTION_MIN_AMOUNTS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+impl Contract {
+    /// Adds the amount to the reserve of the asset and attributes the part that isn't used
+    /// to repay the protocol debt to the sender.
+    /// Only registered accounts are attributed, others contribute anonymously, and so do
+    /// contributions below the minimum amount of the token or while the contributions are returned.
+    /// The entry of the contribution is stored at the cost of the contributor, who contributes
+    /// anonymously if the storage balance doesn't cover it.
+    pub fn internal_deposit_to_reserve(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+        purpose: ReserveContributionPurpose,
+    ) {
+        let repay_amount = self.internal_repay_protocol_debt(token_id, amount);
+        let reserved_amount = amount - repay_amount;
+        let mut asset = self.internal_unwrap_asset(token_id);
+        asset.reserved += reserved_amount;
+        let extra_decimals = asset.config.extra_decimals;
+        self.internal_set_asset(token_id, asset);
+        events::emit::deposit_to_reserve(sender_id, amount, token_id, extra_decimals);
+
+        let min_amount = read_reserve_contribution_min_amounts_from_storage()
+            .get(token_id)
+            .map_or(0, |min_amount| min_amount.0);
+        if reserved_amount == 0 || reserved_amount < min_amount {
+            return;
+        }
+        let mut pools = reserve_contribution_pools();
+        let pool_key = (token_id.clone(), purpose);
+        let mut pool = pools
+            .get(&pool_key)
+            .unwrap_or_else(|| ReserveContributionPool::new(token_id, purpose));
+        if pool.returning_amount.is_some() {
+            return;
+        }
+        if let Some(mut storage) = self.internal_get_storage(sender_id) {
+            let contribution = pool.contributions.get(sender_id).unwrap_or(0);
+            storage.storage_tracker.start();
+            pool.contributions.insert(sender_id, &(contribution + reserved_amount));
+            storage.storage_tracker.stop();
+            let is_attributed = storage.is_storage_covered_with(storage.storage_tracker.bytes_added);
+            if !is_attributed {
+                // Without spare storage, the sender contributes anonymously.
+                storage.storage_tracker.start();
+                pool.contributions.remove(sender_id);
+                storage.storage_tracker.stop();
+            }
+            self.internal_set_storage(sender_id, storage);
+            if is_attributed {
+                pool.total += reserved_amount;
+                pools.insert(&pool_key, &pool);
+                events::emit::reserve_contribution(sender_id, token_id, purpose, reserved_amount);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the page of the reserve contributions of the token for the purpose.
+    pub fn get_reserve_contributions(
+        &self,
+        token_id: TokenId,
+        purpose: ReserveContributionPurpose,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<ReserveContribution> {
+        if let Some(pool) = reserve_contribution_pools().get(&(token_id.clone(), purpose)) {
+            let keys = pool.contributions.keys_as_vector();
+            let values = pool.contributions.values_as_vector();
+            let from_index = from_index.unwrap_or(0);
+            let limit = limit.unwrap_or(keys.len());
+            (from_index..std::cmp::min(keys.len(), from_index + limit))
+                .map(|index| ReserveContribution {
+                    token_id: token_id.clone(),
+                    purpose,
+                    contributor_id: keys.get(index).unwrap(),
+                    amount: values.get(index).unwrap().into(),
+                })
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Returns the amount contributed by the contributor to the reserve of the asset for the purpose,
+    /// in inner decimals.
+    pub fn get_reserve_contribution(
+        &self,
+        token_id: TokenId,
+        purpose: ReserveContributionPurpose,
+        contributor_id: AccountId,
+    ) -> U128 {
+        reserve_contribution_pools()
+            .get(&(token_id, purpose))
+            .and_then(|pool| pool.contributions.get(&contributor_id))
+            .unwrap_or(0)
+            .into()
+    }
+
+    /// Returns the total amount contributed to the reserve of the asset for the purpose, in inner decimals.
+    pub fn get_reserve_contributions_total(&self, token_id: TokenId, purpose: ReserveContributionPurpose) -> U128 {
+        reserve_contribution_pools()
+            .get(&(token_id, purpose))
+            .map_or(0, |pool| pool.total)
+            .into()
+    }
+
+    /// Sets the minimum amount of the token a contribution needs to be attributed, in inner decimals.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_reserve_contribution_min_amount(&mut self, token_id: TokenId, min_amount: U128) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_unwrap_asset(&token_id);
+        events::emit::settings_updated(
+            "set_reserve_contribution_min_amount",
+            &env::predecessor_account_id(),
+            json!({"token_id": token_id, "min_amount": min_amount}),
+        );
+        let mut min_amounts = read_reserve_contribution_min_amounts_from_storage();
+        min_amounts.insert(token_id, min_amount);
+        write_reserve_contribution_min_amounts_to_storage(min_amounts);
+    }
+
+    /// Returns the minimum amount of the token a contribution needs to be attributed, in inner decimals.
+    pub fn get_reserve_contribution_min_amount(&self, token_id: TokenId) -> U128 {
+        read_reserve_contribution_min_amounts_from_storage()
+            .get(&token_id)
+            .cloned()
+            .unwrap_or(U128(0))
+    }
+
+    /// Ends the contribution program of the asset for the purpose and returns the unused `amount`
+    /// (all contributions by default) from the reserve to the supplied balances of the contributors,
+    /// pro rata to their contributions. Shares of contributors that are no longer registered,
+    /// and rounding remainders, stay in the reserve. The storage of the contributions is released
+    /// to their contributors.
+    /// The contributions are returned `limit` contributors at a time (all by default). Further calls
+    /// continue the return of the same amount, and new contributions are anonymous until it's done.
+    /// Returns whether all contributions are returned.
+    /// - Panics if the amount exceeds the contributions or the reserve of the asset.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn return_reserve_contributions(
+        &mut self,
+        token_id: TokenId,
+        purpose: ReserveContributionPurpose,
+        amount: Option<U128>,
+        limit: Option<u64>,
+    ) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut pools = reserve_contribution_pools();
+        let pool_key = (token_id.clone(), purpose);
+        let mut pool = pools.get(&pool_key).expect("No reserve contributions");
+        let amount = if let Some(returning_amount) = pool.returning_amount {
+            assert!(
+                amount.map_or(true, |amount| amount.0 == returning_amount),
+                "The contributions are being returned with another amount"
+            );
+            returning_amount
+        } else {
+            let amount = amount.map_or(pool.total, |amount| amount.0);
+            assert!(amount <= pool.total, "Return amount exceeds the contributions");
+            amount
+        };
+        pool.returning_amount = Some(amount);
+
+        let keys = pool.contributions.keys_as_vector();
+        let limit = limit.unwrap_or(keys.len());
+        let contributor_ids: Vec<AccountId> = (0..std::cmp::min(keys.len(), limit))
+            .map(|index| keys.get(index).unwrap())
+            .collect();
+
+        let mut returns = vec![];
+        for contributor_id in contributor_ids {
+            let contribution = pool.contributions.get(&contributor_id).unwrap();
+            let return_amount = (U256::from(amount) * U256::from(contribution) / U256::from(pool.total)).as_u128();
+            if let Some(mut account) = self.internal_get_account(&contributor_id, false) {
+                account.storage_tracker.start();
+                pool.contributions.remove(&contributor_id);
+                account.storage_tracker.stop();
+                if return_amount > 0 {
+                    self.internal_deposit(&mut account, &token_id, return_amount);
+                    self.internal_account_apply_affected_farms(&mut account);
+                    returns.push((contributor_id.clone(), return_amount));
+                }
+                self.internal_set_account(&contributor_id, account);
+            } else {
+                pool.contributions.remove(&contributor_id);
+            }
+        }
+        let is_done = pool.contributions.is_empty();
+        if is_done {
+            pools.remove(&pool_key);
+        } else {
+            pools.insert(&pool_key, &pool);
+        }
+
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        asset.reserved = asset
+            .reserved
+            .checked_sub(returns.iter().map(|(_, amount)| amount).sum())
+            .expect("Asset reserved balance not enough!");
+        self.internal_set_asset(&token_id, asset);
+        events::emit::return_reserve_contributions(&env::predecessor_account_id(), &token_id, purpose, &returns);
+        is_done
+    }
+}
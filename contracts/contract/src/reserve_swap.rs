@@ -0,0 +1,284 @@
+use crate::*;
+use near_sdk::serde_json;
+use near_contract_standards::fungible_token::core::ext_ft_core;
+
+pub const RESERVE_SWAP_OP: &str = "swap_reserve";
+
+/// Swaps the reserve of one asset into another asset through a registered dex.
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReserveSwap {
+    pub token_in_id: TokenId,
+    /// The reserve amount to swap, in inner decimals.
+    pub amount_in: U128,
+    pub token_out_id: TokenId,
+    /// The minimum amount out, in inner decimals. It's bounded by the oracle prices
+    /// and the max slippage rate of the margin config.
+    pub min_amount_out: U128,
+    pub swap_indication: SwapIndication,
+}
+
+/// A reserve or beneficiary fee swap waiting for its output from the dex.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct PendingSwap {
+    pub op: String,
+    /// The operator of a reserve swap, or the beneficiary of a fee swap.
+    pub account_id: AccountId,
+    pub dex_id: AccountId,
+    pub token_in_id: TokenId,
+    /// In inner decimals.
+    pub amount_in: Balance,
+    pub token_out_id: TokenId,
+    /// When the input was sent to the dex.
+    pub timestamp: Timestamp,
+}
+
+pub fn pending_swaps() -> LookupMap<u64, PendingSwap> {
+    LookupMap::new(PENDING_SWAPS_KEY.as_bytes())
+}
+
+fn next_pending_swap_id() -> u64 {
+    let swap_id = env::storage_read(PENDING_SWAP_NONCE_KEY.as_bytes())
+        .map_or(0, |content| u64::try_from_slice(&content).expect("deserialize pending swap nonce failed."));
+    env::storage_write(PENDING_SWAP_NONCE_KEY.as_bytes(), &(swap_id + 1).try_to_vec().unwrap());
+    swap_id
+}
+
+impl Contract {
+    /// Stores the pending swap and returns its id,
+    /// which the dex echoes back as the pos_id of the swap reference.
+    pub(crate) fn internal_add_pending_swap(&mut self, pending_swap: PendingSwap) -> u64 {
+        let swap_id = next_pending_swap_id();
+        pending_swaps().insert(&swap_id, &pending_swap);
+        swap_id
+    }
+
+    /// Takes the pending swap whose output is returned with the swap reference.
+    /// Like margin actions, the owner can return the output instead of the dex
+    /// once the swap has been pending for `max_position_action_wait_sec`,
+    /// e.g. after withdrawing the output the dex kept.
+    /// - Panics if the swap isn't pending, or if the output isn't token_out sent by the dex of the swap.
+    pub(crate) fn internal_take_pending_swap(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        swap_ref: &SwapReference,
+    ) -> PendingSwap {
+        let swap_id: u64 = swap_ref.pos_id.parse().expect("Invalid swap id");
+        let pending_swap = pending_swaps().remove(&swap_id).expect("There is no pending swap");
+        require!(pending_swap.op == swap_ref.op && &pending_swap.token_out_id == token_id, "Not allow");
+        if sender_id == &self.internal_config().owner_id {
+            self.assert_stale_pending_swap(&pending_swap);
+            events::emit::pending_swap_settled(swap_id, sender_id, &pending_swap.op);
+        } else {
+            require!(&pending_swap.dex_id == sender_id, "Not allow");
+        }
+        pending_swap
+    }
+
+    fn assert_stale_pending_swap(&self, pending_swap: &PendingSwap) {
+        require!(
+            env::block_timestamp() - pending_swap.timestamp
+                >= sec_to_nano(self.internal_margin_config().max_position_action_wait_sec),
+            "Please wait for the pending swap"
+        );
+    }
+
+    /// Checks the swap indication and bounds `min_amount_out` by the oracle prices
+    /// and the max slippage rate of the margin config.
+    /// Returns the amount in rounded down to the token decimals, its FT amount and the swap detail.
+    pub(crate) fn internal_verify_swap(
+        &self,
+        token_in_id: &TokenId,
+        amount_in: Balance,
+        token_out_id: &TokenId,
+        min_amount_out: Balance,
+        swap_indication: &SwapIndication,
+        prices: &Prices,
+    ) -> (Balance, Balance, SwapDetail) {
+        require!(token_in_id != token_out_id, "Can't swap into the same token");
+        let asset_in = self.internal_unwrap_asset(token_in_id);
+        let asset_out = self.internal_unwrap_asset(token_out_id);
+        let ft_amount_in = amount_in / 10u128.pow(asset_in.config.extra_decimals as u32);
+        let amount_in = ft_amount_in * 10u128.pow(asset_in.config.extra_decimals as u32);
+        require!(amount_in > 0, "The amount in is too low");
+        let ft_min_amount_out = min_amount_out / 10u128.pow(asset_out.config.extra_decimals as u32);
+
+        require!(swap_indication.splits.is_empty(), "Split swaps are only supported for margin positions");
+        let swap_detail = self.parse_swap_indication(swap_indication, ft_amount_in).legs.remove(0).0;
+        require!(swap_detail.verify_token_in(token_in_id, ft_amount_in), "token_in check failed");
+        require!(swap_detail.verify_token_out(token_out_id, ft_min_amount_out), "token_out check failed");
+        require!(
+            is_min_amount_out_reasonable(
+                amount_in,
+                &asset_in,
+                prices.get_unwrap(token_in_id),
+                &asset_out,
+                prices.get_unwrap(token_out_id),
+                min_amount_out,
+                self.internal_margin_config().max_slippage_rate,
+            ),
+            "min_amount_out is too low"
+        );
+        (amount_in, ft_amount_in, swap_detail)
+    }
+
+    /// Sends the input token to the dex with the swap reference as the client echo.
+    pub(crate) fn internal_send_swap(
+        &self,
+        mut swap_detail: SwapDetail,
+        swap_ref: &SwapReference,
+        token_in_id: &TokenId,
+        ft_amount_in: Balance,
+    ) -> Promise {
+        swap_detail.set_client_echo(&swap_ref.to_msg_string());
+        ext_ft_core::ext(token_in_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER_CALL)
+            .ft_transfer_call(
+                swap_detail.dex_id.clone(),
+                U128(ft_amount_in),
+                None,
+                swap_detail.to_msg_string(),
+            )
+    }
+
+    /// Takes the reserve of `token_in_id` and sends it to the dex.
+    /// The output is received through `ft_on_transfer` with the `swap_reserve` swap reference,
+    /// which must match the pending swap.
+    pub fn internal_swap_reserve(&mut self, operator_id: &AccountId, swap: ReserveSwap, prices: &Prices) {
+        let (amount_in, ft_amount_in, swap_detail) = self.internal_verify_swap(
+            &swap.token_in_id,
+            swap.amount_in.0,
+            &swap.token_out_id,
+            swap.min_amount_out.0,
+            &swap.swap_indication,
+            prices,
+        );
+        let mut asset_in = self.internal_unwrap_asset(&swap.token_in_id);
+        asset_in.reserved = asset_in.reserved.checked_sub(amount_in).expect("Asset reserved balance not enough!");
+        self.internal_set_asset(&swap.token_in_id, asset_in);
+        let swap_id = self.internal_add_pending_swap(PendingSwap {
+            op: RESERVE_SWAP_OP.to_string(),
+            account_id: operator_id.clone(),
+            dex_id: swap_detail.dex_id.clone(),
+            token_in_id: swap.token_in_id.clone(),
+            amount_in,
+            token_out_id: swap.token_out_id.clone(),
+            timestamp: env::block_timestamp(),
+        });
+        events::emit::swap_reserve_started(swap_id, operator_id, &swap.token_in_id, amount_in, &swap.token_out_id, swap.min_amount_out.0);
+
+        let swap_ref = SwapReference {
+            account_id: operator_id.clone(),
+            pos_id: swap_id.to_string(),
+            amount_in: amount_in.into(),
+            action_ts: env::block_timestamp().into(),
+            op: RESERVE_SWAP_OP.to_string(),
+            liquidator_id: None,
+            leg: None,
+        };
+        self.internal_send_swap(swap_detail, &swap_ref, &swap.token_in_id, ft_amount_in)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_FT_TRANSFER_CALL_CALLBACK)
+                    .with_unused_gas_weight(0)
+                    .callback_swap_reserve(swap_id, operator_id.clone(), swap.token_in_id, amount_in.into()),
+            );
+    }
+
+    /// Repays the protocol debt of the output token and adds the rest to its reserve.
+    pub fn on_swap_reserve_return(&mut self, sender_id: &AccountId, token_out_id: &TokenId, amount_out: Balance, swap_ref: &SwapReference) {
+        let pending_swap = self.internal_take_pending_swap(sender_id, token_out_id, swap_ref);
+        let repaid_amount = self.internal_repay_protocol_debt(token_out_id, amount_out);
+        let mut asset_out = self.internal_unwrap_asset(token_out_id);
+        asset_out.reserved += amount_out - repaid_amount;
+        self.internal_set_asset(token_out_id, asset_out);
+        events::emit::swap_reserve_succeeded(
+            &pending_swap.account_id,
+            &pending_swap.token_in_id,
+            pending_swap.amount_in,
+            token_out_id,
+            amount_out,
+            repaid_amount,
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Swaps the reserve using the pyth oracle prices.
+    /// With the price oracle, the swap is requested through `oracle_on_call`
+    /// with `PriceReceiverMsg::SwapReserve`.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner, a TreasuryManager or guardians.
+    #[payable]
+    pub fn swap_reserve_with_pyth(&mut self, swap: ReserveSwap) {
+        assert_one_yocto();
+        self.assert_role_or_guardians(Role::TreasuryManager);
+        assert!(self.internal_config().enable_pyth_oracle, "Pyth oracle disabled");
+        let involved_tokens = vec![swap.token_in_id.clone(), swap.token_out_id.clone()];
+        let (promise_token_ids, default_prices) = self.prepare_promise_tokens(&involved_tokens);
+        if !promise_token_ids.is_empty() {
+            let (all_promise_flags, promise) = self.generate_flags_and_promise(&promise_token_ids);
+            promise.then(
+                Self::ext(env::current_account_id())
+                    .callback_swap_reserve_with_pyth(env::predecessor_account_id(), involved_tokens, all_promise_flags, swap, default_prices)
+            );
+        } else {
+            self.internal_swap_reserve(&env::predecessor_account_id(), swap, &Prices::from_prices(default_prices));
+        }
+    }
+
+    #[private]
+    pub fn callback_swap_reserve_with_pyth(&mut self, operator_id: AccountId, involved_tokens: Vec<TokenId>, all_promise_flags: Vec<String>, swap: ReserveSwap, default_prices: HashMap<TokenId, Price>) {
+        assert!(env::promise_results_count() == all_promise_flags.len() as u64, "Invalid promise count");
+        let all_prices = self.generate_all_prices(involved_tokens, all_promise_flags, default_prices);
+        self.internal_swap_reserve(&operator_id, swap, &all_prices);
+    }
+
+    /// Drops the swap that has been pending for `max_position_action_wait_sec`
+    /// without its output, e.g. when the dex failed to return it and kept it.
+    /// The input stays taken from the reserve or fees. The output can still be recovered
+    /// from the dex and deposited to the reserve.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a TreasuryManager.
+    #[payable]
+    pub fn expire_pending_swap(&mut self, swap_id: U64) {
+        assert_one_yocto();
+        self.assert_role(Role::TreasuryManager);
+        let pending_swap = pending_swaps().get(&swap_id.0).expect("There is no pending swap");
+        self.assert_stale_pending_swap(&pending_swap);
+        pending_swaps().remove(&swap_id.0);
+        events::emit::pending_swap_expired(
+            swap_id.0,
+            &env::predecessor_account_id(),
+            &pending_swap.op,
+            &pending_swap.token_in_id,
+            pending_swap.amount_in,
+        );
+    }
+
+    /// Returns the reserve if the dex didn't take the input token.
+    /// The swap is no longer pending then, since no output will be returned.
+    #[private]
+    pub fn callback_swap_reserve(&mut self, swap_id: u64, operator_id: AccountId, token_in_id: TokenId, amount_in: U128) {
+        let mut asset_in = self.internal_unwrap_asset(&token_in_id);
+        let extra_decimals_mult = 10u128.pow(asset_in.config.extra_decimals as u32);
+        let ft_amount_in_used = if let Some(cross_call_result) = promise_result_as_success() {
+            serde_json::from_slice::<U128>(&cross_call_result).unwrap().0
+        } else {
+            0
+        };
+        if ft_amount_in_used == 0 {
+            pending_swaps().remove(&swap_id);
+        }
+        let unused_amount = amount_in.0 - ft_amount_in_used * extra_decimals_mult;
+        if unused_amount > 0 {
+            asset_in.reserved += unused_amount;
+            self.internal_set_asset(&token_in_id, asset_in);
+            events::emit::swap_reserve_refunded(&operator_id, &token_in_id, unused_amount);
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use crate::*;
+
+/// Where the penalty of an early exit from a reward vesting goes.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum VestingPenaltyReceiver {
+    /// The penalty is added to the reserve of the reward token.
+    Reserve,
+    /// The penalty tops up the remaining rewards of the reward token in the given farm,
+    /// e.g. a farm of the booster token to share it with stakers.
+    /// Falls back to the reserve if the farm has no active reward of the token.
+    Farm(FarmId),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardVestingConfig {
+    /// The duration of the linear vesting of claimed farm rewards in seconds.
+    pub vesting_duration_sec: DurationSec,
+    /// The rate of the unvested amount that is taken as a penalty on an early exit.
+    /// E.g. 5000 means 50%.
+    pub early_exit_penalty_rate: u32,
+    pub penalty_receiver: VestingPenaltyReceiver,
+}
+
+impl RewardVestingConfig {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.vesting_duration_sec > 0,
+            "The vesting_duration_sec must be greater than 0"
+        );
+        assert!(
+            self.early_exit_penalty_rate <= MAX_RATIO,
+            "Invalid early_exit_penalty_rate"
+        );
+    }
+}
+
+/// Farm rewards of one token that are vesting linearly for an account.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardVesting {
+    /// The total amount that entered this vesting.
+    #[serde(with = "u128_dec_format")]
+    pub total_amount: Balance,
+    /// The amount that is still locked at update_timestamp.
+    #[serde(with = "u128_dec_format")]
+    pub locked_amount: Balance,
+    /// The vested amount that is not claimed yet at update_timestamp.
+    #[serde(with = "u128_dec_format")]
+    pub unlocked_amount: Balance,
+    #[serde(with = "u64_dec_format")]
+    pub update_timestamp: Timestamp,
+    /// The locked amount is released linearly until this timestamp.
+    #[serde(with = "u64_dec_format")]
+    pub end_timestamp: Timestamp,
+}
+
+impl RewardVesting {
+    /// Moves the amount vested by the current timestamp from locked to unlocked.
+    pub fn update(&mut self) {
+        let timestamp = env::block_timestamp();
+        if timestamp <= self.update_timestamp {
+            return;
+        }
+        let released = if timestamp >= self.end_timestamp {
+            self.locked_amount
+        } else {
+            u128_ratio(
+                self.locked_amount,
+                (timestamp - self.update_timestamp) as u128,
+                (self.end_timestamp - self.update_timestamp) as u128,
+            )
+        };
+        self.locked_amount -= released;
+        self.unlocked_amount += released;
+        self.update_timestamp = timestamp;
+    }
+
+    /// Adds the amount to the vesting. The end of the vesting becomes the
+    /// weighted average of the remaining locked amount and the new amount.
+    pub fn add(&mut self, amount: Balance, vesting_duration_ns: Timestamp) {
+        self.update();
+        let timestamp = env::block_timestamp();
+        let remaining_duration_ns = self.end_timestamp.saturating_sub(timestamp);
+        let end_duration_ns = u128_ratio(
+            self.locked_amount,
+            remaining_duration_ns as u128,
+            self.locked_amount + amount,
+        ) + u128_ratio(
+            amount,
+            vesting_duration_ns as u128,
+            self.locked_amount + amount,
+        );
+        self.total_amount += amount;
+        self.locked_amount += amount;
+        self.update_timestamp = timestamp;
+        self.end_timestamp = timestamp + end_duration_ns as Timestamp;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locked_amount == 0 && self.unlocked_amount == 0
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Deserialize, Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardVestingView {
+    pub token_id: TokenId,
+    /// The total amount that has vested so far, including claimed.
+    #[serde(with = "u128_dec_format")]
+    pub vested_amount: Balance,
+    /// The amount that is still locked.
+    #[serde(with = "u128_dec_format")]
+    pub unvested_amount: Balance,
+    /// The vested amount that can be claimed now.
+    #[serde(with = "u128_dec_format")]
+    pub claimable_amount: Balance,
+    #[serde(with = "u64_dec_format")]
+    pub end_timestamp: Timestamp,
+}
+
+impl RewardVestingView {
+    pub fn new(token_id: TokenId, mut reward_vesting: RewardVesting) -> Self {
+        reward_vesting.update();
+        Self {
+            token_id,
+            vested_amount: reward_vesting.total_amount - reward_vesting.locked_amount,
+            unvested_amount: reward_vesting.locked_amount,
+            claimable_amount: reward_vesting.unlocked_amount,
+            end_timestamp: reward_vesting.end_timestamp,
+        }
+    }
+}
+
+pub fn read_reward_vesting_configs_from_storage() -> HashMap<TokenId, RewardVestingConfig> {
+    if let Some(content) = env::storage_read(REWARD_VESTING_CONFIGS_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize reward vesting configs failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_reward_vesting_configs_to_storage(data: HashMap<TokenId, RewardVestingConfig>) {
+    env::storage_write(REWARD_VESTING_CONFIGS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+impl Contract {
+    /// Deposits a claimed farm reward into the supplied balance of the account,
+    /// or puts it into the vesting if the reward token has a vesting config.
+    pub fn internal_deposit_farm_reward(
+        &mut self,
+        account: &mut Account,
+        reward_vesting_configs: &HashMap<TokenId, RewardVestingConfig>,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        if let Some(reward_vesting_config) = reward_vesting_configs.get(token_id) {
+            account
+                .vestings
+                .entry(token_id.clone())
+                .or_default()
+                .add(amount, to_nano(reward_vesting_config.vesting_duration_sec));
+            events::emit::reward_vested(
+                &account.account_id,
+                amount,
+                token_id,
+                self.internal_unwrap_asset(token_id).config.extra_decimals,
+            );
+        } else {
+            self.internal_auto_compound_farm_reward(account, token_id, amount);
+        }
+    }
+
+    /// Sends the penalty of an early exit to the receiver from the vesting config.
+    pub fn internal_distribute_vesting_penalty(
+        &mut self,
+        penalty_receiver: &VestingPenaltyReceiver,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        if let VestingPenaltyReceiver::Farm(farm_id) = penalty_receiver {
+            if let Some(mut asset_farm) = self.internal_get_asset_farm(farm_id, false) {
+                if let Some(asset_farm_reward) = asset_farm.rewards.get_mut(token_id) {
+                    asset_farm_reward.remaining_rewards += amount;
+                    self.internal_set_asset_farm(farm_id, asset_farm);
+                    return;
+                }
+            }
+        }
+        let mut asset = self.internal_unwrap_asset(token_id);
+        asset.reserved += amount;
+        self.internal_set_asset(token_id, asset);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the vesting config of the farm reward token.
+    /// Rewards claimed after this call enter the vesting.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_reward_vesting_config(&mut self, token_id: TokenId, reward_vesting_config: RewardVestingConfig) {
+        assert_one_yocto();
+        self.assert_owner();
+        reward_vesting_config.assert_valid();
+        self.internal_unwrap_asset(&token_id);
+        let mut reward_vesting_configs = read_reward_vesting_configs_from_storage();
+        events::emit::settings_updated("set_reward_vesting_config", &env::predecessor_account_id(), json!({"token_id": token_id, "reward_vesting_config": reward_vesting_config}));
+        reward_vesting_configs.insert(token_id, reward_vesting_config);
+        write_reward_vesting_configs_to_storage(reward_vesting_configs);
+    }
+
+    /// Removes the vesting config of the farm reward token.
+    /// Rewards that are already vesting keep their schedule.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_reward_vesting_config(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut reward_vesting_configs = read_reward_vesting_configs_from_storage();
+        require!(reward_vesting_configs.remove(&token_id).is_some(), "Invalid token_id");
+        write_reward_vesting_configs_to_storage(reward_vesting_configs);
+        events::emit::settings_updated("remove_reward_vesting_config", &env::predecessor_account_id(), json!({"token_id": token_id}));
+    }
+
+    pub fn get_reward_vesting_configs(&self) -> HashMap<TokenId, RewardVestingConfig> {
+        read_reward_vesting_configs_from_storage()
+    }
+
+    /// Returns vested, unvested and claimable farm rewards of the given account.
+    pub fn get_reward_vestings(&self, account_id: AccountId) -> Vec<RewardVestingView> {
+        self.internal_get_account(&account_id, true)
+            .map(|account| {
+                account
+                    .vestings
+                    .into_iter()
+                    .map(|(token_id, reward_vesting)| RewardVestingView::new(token_id, reward_vesting))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Claims vested farm rewards of the given tokens, or of all tokens if token_ids are not given,
+    /// into the supplied balance of the predecessor.
+    pub fn claim_vested_rewards(&mut self, token_ids: Option<Vec<TokenId>>) {
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let token_ids = token_ids.unwrap_or_else(|| account.vestings.keys().cloned().collect());
+        for token_id in token_ids {
+            let mut reward_vesting = account.vestings.remove(&token_id).expect("Vesting not exist");
+            reward_vesting.update();
+            let amount = std::mem::take(&mut reward_vesting.unlocked_amount);
+            if amount > 0 {
+                self.internal_deposit(&mut account, &token_id, amount);
+                account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+                account.add_affected_farm(FarmId::TokenNetBalance(token_id.clone()));
+                events::emit::vested_reward_claimed(
+                    &account_id,
+                    amount,
+                    &token_id,
+                    self.internal_unwrap_asset(&token_id).config.extra_decimals,
+                );
+            }
+            if !reward_vesting.is_empty() {
+                account.vestings.insert(token_id, reward_vesting);
+            }
+        }
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Exits the vesting of the given token early. The vested amount is claimed in full,
+    /// while the unvested amount is claimed after the early exit penalty.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn exit_reward_vesting(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut reward_vesting = account.vestings.remove(&token_id).expect("Vesting not exist");
+        reward_vesting.update();
+        let reward_vesting_config = read_reward_vesting_configs_from_storage()
+            .remove(&token_id)
+            .unwrap_or(RewardVestingConfig {
+                vesting_duration_sec: 0,
+                early_exit_penalty_rate: 0,
+                penalty_receiver: VestingPenaltyReceiver::Reserve,
+            });
+        let penalty = u128_ratio(
+            reward_vesting.locked_amount,
+            reward_vesting_config.early_exit_penalty_rate as u128,
+            MAX_RATIO as u128,
+        );
+        let amount = reward_vesting.unlocked_amount + reward_vesting.locked_amount - penalty;
+        if penalty > 0 {
+            self.internal_distribute_vesting_penalty(&reward_vesting_config.penalty_receiver, &token_id, penalty);
+        }
+        if amount > 0 {
+            self.internal_deposit(&mut account, &token_id, amount);
+            account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+            account.add_affected_farm(FarmId::TokenNetBalance(token_id.clone()));
+        }
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+        events::emit::reward_vesting_exited(&account_id, amount, penalty, &token_id);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::*;
+
+/// Named admin roles. The contract owner implicitly holds every role.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    /// Asset configs, limits, capacities, margin risk parameters, liquidator whitelists and the blocklist.
+    RiskAdmin,
+    /// Price oracles, LP and DCL configs.
+    OracleAdmin,
+    /// Asset farm rewards and booster tokens.
+    FarmManager,
+    /// Protocol fees and reserves.
+    TreasuryManager,
+    /// Disabling asset capacities and pausing actions.
+    PauseGuardian,
+    /// Margin dexes, margin tokens and client echo senders.
+    MarginDexManager,
+    /// Beneficiaries of the protocol fees.
+    BeneficiaryManager,
+}
+
+pub fn read_roles_from_storage() -> HashMap<Role, HashSet<AccountId>> {
+    if let Some(content) = env::storage_read(ROLES_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize roles failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_roles_to_storage(data: HashMap<Role, HashSet<AccountId>>) {
+    env::storage_write(ROLES_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+pub fn read_pending_owner_id_from_storage() -> Option<AccountId> {
+    env::storage_read(PENDING_OWNER_ID_KEY.as_bytes())
+        .map(|content| AccountId::try_from_slice(&content).expect("deserialize pending owner id failed."))
+}
+
+pub fn has_role(role: &Role, account_id: &AccountId) -> bool {
+    read_roles_from_storage()
+        .get(role)
+        .map_or(false, |account_ids| account_ids.contains(account_id))
+}
+
+impl Contract {
+    /// Requires to be called by the contract owner or an account with the role.
+    pub fn assert_role(&self, role: Role) {
+        assert!(
+            self.is_owner_predecessor() || has_role(&role, &env::predecessor_account_id()),
+            "Not allowed"
+        );
+    }
+
+    /// Requires the account to be the contract owner, an account with the role or guardians.
+    /// Used when the action is requested on behalf of the account, e.g. through the oracle.
+    pub fn assert_account_role_or_guardians(&self, account_id: &AccountId, role: Role) {
+        assert!(
+            account_id == &self.internal_config().owner_id
+                || has_role(&role, account_id)
+                || self.guardians.contains(account_id),
+            "Not allowed"
+        );
+    }
+
+    /// Requires to be called by the contract owner, an account with the role or guardians.
+    pub fn assert_role_or_guardians(&self, role: Role) {
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            self.is_owner_predecessor()
+                || has_role(&role, &predecessor_id)
+                || self.guardians.contains(&predecessor_id),
+            "Not allowed"
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Grants the role to the given accounts.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn grant_role(&mut self, role: Role, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut roles = read_roles_from_storage();
+        let role_account_ids = roles.entry(role.clone()).or_default();
+        for account_id in account_ids {
+            require!(
+                role_account_ids.insert(account_id.clone()),
+                format!("{} already has role {:?}", account_id, role)
+            );
+            events::emit::role_granted(&account_id, &role);
+        }
+        write_roles_to_storage(roles);
+    }
+
+    /// Revokes the role from the given accounts.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn revoke_role(&mut self, role: Role, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut roles = read_roles_from_storage();
+        let role_account_ids = roles.entry(role.clone()).or_default();
+        for account_id in account_ids {
+            require!(
+                role_account_ids.remove(&account_id),
+                format!("{} doesn't have role {:?}", account_id, role)
+            );
+            events::emit::role_revoked(&account_id, &role);
+        }
+        if role_account_ids.is_empty() {
+            roles.remove(&role);
+        }
+        write_roles_to_storage(roles);
+    }
+
+    /// Returns the accounts of every granted role.
+    pub fn get_roles(&self) -> HashMap<Role, Vec<AccountId>> {
+        read_roles_from_storage()
+            .into_iter()
+            .map(|(role, account_ids)| {
+                let mut account_ids: Vec<AccountId> = account_ids.into_iter().collect();
+                account_ids.sort();
+                (role, account_ids)
+            })
+            .collect()
+    }
+
+    /// Returns the roles granted to the given account.
+    pub fn get_account_roles(&self, account_id: AccountId) -> Vec<Role> {
+        let mut roles: Vec<Role> = read_roles_from_storage()
+            .into_iter()
+            .filter(|(_, account_ids)| account_ids.contains(&account_id))
+            .map(|(role, _)| role)
+            .collect();
+        roles.sort();
+        roles
+    }
+
+    /// Accepts the ownership proposed by `set_owner_id`.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the pending owner.
+    #[payable]
+    pub fn accept_owner_id(&mut self) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        require!(
+            read_pending_owner_id_from_storage().as_ref() == Some(&owner_id),
+            "Not the pending owner"
+        );
+        // The owner must be a registered account.
+        self.internal_unwrap_account(&owner_id);
+        env::storage_remove(PENDING_OWNER_ID_KEY.as_bytes());
+        let mut config = self.internal_config();
+        events::emit::owner_transferred(&config.owner_id, &owner_id);
+        config.owner_id = owner_id;
+        self.config.set(&config);
+    }
+
+    pub fn get_pending_owner_id(&self) -> Option<AccountId> {
+        read_pending_owner_id_from_storage()
+    }
+}
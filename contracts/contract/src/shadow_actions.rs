@@ -67,6 +67,8 @@ impl Contract {
     pub fn on_cast_shadow(&mut self, account_id: AccountId, shadow_id: String, amount: U128, msg: String) {
         let config = self.internal_config();
         assert!(env::predecessor_account_id() == config.ref_exchange_id);
+        assert_not_paused(&read_pause_registry_from_storage(), PauseKind::ShadowCast, &[shadow_id.clone()]);
+        assert_not_blocked(&account_id, BlockedOperation::CastShadow);
 
         let (actions, with_pyth) = if !msg.is_empty() {
             match near_sdk::serde_json::from_str(&msg).expect("Can't parse ShadowReceiverMsg") {
@@ -87,7 +89,7 @@ impl Contract {
         
         let mut account = self.internal_unwrap_account(&account_id);
         self.internal_deposit(&mut account, &token_id, amount);
-        events::emit::deposit(&account_id, amount, &token_id);
+        events::emit::deposit(&account_id, amount, &token_id, asset.config.extra_decimals);
         if with_pyth {
             self.internal_execute_with_pyth(&account_id, &mut account, actions);
         } else {
@@ -137,10 +139,11 @@ impl Contract {
         account.internal_set_asset(&withdraw_asset_amount.token_id, account_asset);
 
         asset.supplied.withdraw(shares, amount);
+        let extra_decimals = asset.config.extra_decimals;
 
         self.internal_set_asset(&withdraw_asset_amount.token_id, asset);
         self.internal_set_account(&account_id, account);
-        events::emit::withdraw_succeeded(&account_id, amount, &withdraw_asset_amount.token_id);
+        events::emit::withdraw_succeeded(&account_id, amount, &withdraw_asset_amount.token_id, extra_decimals);
     }
 }
 
@@ -409,9 +412,12 @@ impl Contract {
     pub fn callback_sync_lp_infos(&mut self) {
         if let Some(cross_call_result) = promise_result_as_success() {
             if let Ok(lp_token_infos) = serde_json::from_slice::<HashMap<String, UnitShareTokens>>(&cross_call_result) {
+                let mut lp_token_ids = Vec::with_capacity(lp_token_infos.len());
                 for (key, value) in lp_token_infos {
+                    lp_token_ids.push(key.clone());
                     self.last_lp_token_infos.insert(key, value);
                 }
+                events::emit::sync_lp_token_infos(&lp_token_ids);
             } else {
                 log!("Invalid cross-contract result");
             }
@@ -0,0 +1,431 @@
+use crate::*;
+use near_sdk::{serde_json, IntoStorageKey};
+
+/// The Borsh layout of a versioned struct stored in the contract state.
+pub struct StateSchema {
+    pub name: &'static str,
+    /// The index of the `Current` variant in the versioned enum of the struct.
+    pub version: u8,
+    /// The serialized fields in order, skipping `#[borsh_skip]` fields.
+    pub fields: &'static str,
+}
+
+/// Every change of the fields requires a new version of the struct,
+/// otherwise the existing state would be deserialized with the new layout.
+pub const STATE_SCHEMAS: &[StateSchema] = &[
+    StateSchema {
+        name: "Account",
+        version: 5,
+        fields: "account_id: AccountId, supplied: HashMap<TokenId, Shares>, positions: HashMap<String, Position>, \
+            farms: HashMap<FarmId, AccountFarm>, booster_staking: Option<BoosterStaking>, \
+            booster_stakings: HashMap<TokenId, BoosterStaking>, is_locked: bool, \
+            vestings: HashMap<TokenId, RewardVesting>, auto_compound: Option<AutoCompound>, \
+            booster_delegations: HashMap<TokenId, HashMap<AccountId, BoosterDelegation>>, \
+            booster_delegators: HashMap<TokenId, HashSet<AccountId>>, gauge_vote: Option<GaugeVote>, \
+            last_booster_penalty_per_stakes: HashMap<TokenId, BigDecimal>",
+    },
+    StateSchema {
+        name: "MarginAccount",
+        version: 3,
+        fields: "account_id: AccountId, supplied: HashMap<TokenId, Shares>, \
+            margin_positions: UnorderedMap<PosId, MarginTradingPosition>, \
+            position_latest_actions: HashMap<PosId, U64>, stops: HashMap<PosId, MarginStop>, \
+            farms: HashMap<FarmId, AccountFarm>, unclaimed_farm_rewards: HashMap<TokenId, Balance>",
+    },
+    StateSchema {
+        name: "Asset",
+        version: 6,
+        fields: "supplied: Pool, borrowed: Pool, margin_debt: Pool, margin_pending_debt: Balance, \
+            margin_position: Balance, reserved: Balance, prot_fee: Balance, \
+            beneficiary_fees: HashMap<AccountId, U128>, unit_acc_hp_interest: Balance, \
+            last_update_timestamp: Timestamp, config: AssetConfig, lostfound_shares: Balance",
+    },
+    StateSchema {
+        name: "AssetFarm",
+        version: 2,
+        fields: "block_timestamp: Timestamp, rewards: HashMap<TokenId, AssetFarmReward>, \
+            inactive_rewards: LookupMap<TokenId, VAssetFarmReward>",
+    },
+    StateSchema {
+        name: "Storage",
+        version: 1,
+        fields: "storage_balance: Balance, used_bytes: StorageUsage, sponsor_id: Option<AccountId>, \
+            sponsored_balance: Balance",
+    },
+];
+
+/// The Borsh layout of a struct stored inside of the versioned structs without its own version.
+/// Its fields are part of the fingerprints of the structs that contain it,
+/// so changing them requires a new version of those structs.
+pub struct NestedStateSchema {
+    pub name: &'static str,
+    /// The serialized fields in order, skipping `#[borsh_skip]` fields.
+    pub fields: &'static str,
+}
+
+pub const NESTED_STATE_SCHEMAS: &[NestedStateSchema] = &[
+    NestedStateSchema {
+        name: "Pool",
+        fields: "shares: Shares, balance: Balance",
+    },
+    NestedStateSchema {
+        name: "AssetConfig",
+        fields: "reserve_ratio: u32, beneficiaries: HashMap<AccountId, u32>, target_utilization: u32, \
+            target_utilization_rate: LowU128, max_utilization_rate: LowU128, \
+            holding_position_fee_rate: LowU128, volatility_ratio: u32, extra_decimals: u8, \
+            can_deposit: bool, can_withdraw: bool, can_use_as_collateral: bool, can_borrow: bool, \
+            net_tvl_multiplier: u32, max_change_rate: Option<u32>, supplied_limit: Option<U128>, \
+            borrowed_limit: Option<U128>, min_borrowed_amount: Option<U128>",
+    },
+    NestedStateSchema {
+        name: "AssetFarmReward",
+        fields: "reward_per_day: Balance, booster_log_bases: HashMap<TokenId, U128>, \
+            remaining_rewards: Balance, boosted_shares: Balance, reward_per_share: BigDecimal, \
+            start_timestamp: Option<U64>, end_timestamp: Option<U64>",
+    },
+    NestedStateSchema {
+        name: "MarginTradingPosition",
+        fields: "open_ts: Timestamp, uahpi_at_open: Balance, debt_cap: Balance, token_c_id: TokenId, \
+            token_c_shares: Shares, token_d_id: TokenId, token_d_shares: Shares, token_p_id: TokenId, \
+            token_p_amount: Balance, is_locking: bool",
+    },
+    NestedStateSchema {
+        name: "BoosterStaking",
+        fields: "staked_booster_amount: Balance, x_booster_amount: Balance, unlock_timestamp: u64",
+    },
+    NestedStateSchema {
+        name: "BoosterDelegation",
+        fields: "weight: u32, revocable_timestamp: Timestamp, is_accepted: bool",
+    },
+];
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StateSchemaFingerprint {
+    pub version: u8,
+    /// Hex encoded sha256 of the fields.
+    pub fingerprint: String,
+}
+
+/// Appends the fields and the fields of the nested structs they refer to, e.g.
+/// `supplied: Pool, ... Pool(shares: Shares, balance: Balance)`.
+fn append_schema_fields(fields: &str, layout: &mut String) {
+    layout.push_str(&fields.split_whitespace().collect::<Vec<_>>().join(" "));
+    for nested in NESTED_STATE_SCHEMAS {
+        if fields
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|type_name| type_name == nested.name)
+        {
+            layout.push_str(&format!(" {}(", nested.name));
+            append_schema_fields(nested.fields, layout);
+            layout.push(')');
+        }
+    }
+}
+
+impl StateSchema {
+    /// The layout includes the nested structs, so changing their fields changes the fingerprint.
+    pub fn fingerprint(&self) -> StateSchemaFingerprint {
+        let mut layout = String::new();
+        append_schema_fields(self.fields, &mut layout);
+        StateSchemaFingerprint {
+            version: self.version,
+            fingerprint: hex::encode(env::sha256(layout.as_bytes())),
+        }
+    }
+}
+
+pub fn state_schema_version(name: &str) -> u8 {
+    STATE_SCHEMAS
+        .iter()
+        .find(|schema| schema.name == name)
+        .expect("State schema not found")
+        .version
+}
+
+/// Whether the serialized versioned enum is stored as a legacy variant.
+pub fn is_legacy_state(raw: &[u8], name: &str) -> bool {
+    raw.first().map_or(false, |&version| version < state_schema_version(name))
+}
+
+pub fn current_state_schema_fingerprints() -> HashMap<String, StateSchemaFingerprint> {
+    STATE_SCHEMAS
+        .iter()
+        .map(|schema| (schema.name.to_string(), schema.fingerprint()))
+        .collect()
+}
+
+pub fn read_state_schema_fingerprints_from_storage() -> HashMap<String, StateSchemaFingerprint> {
+    if let Some(content) = env::storage_read(STATE_SCHEMA_FINGERPRINTS_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize state schema fingerprints failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_state_schema_fingerprints_to_storage(data: HashMap<String, StateSchemaFingerprint>) {
+    env::storage_write(STATE_SCHEMA_FINGERPRINTS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+/// Returns the schemas whose stored fields changed without a new version.
+/// The state written before the upgrade can't be read with such schemas.
+pub fn incompatible_state_schemas() -> Vec<String> {
+    let stored = read_state_schema_fingerprints_from_storage();
+    let mut names: Vec<String> = current_state_schema_fingerprints()
+        .into_iter()
+        .filter(|(name, current)| {
+            stored.get(name).map_or(false, |stored| {
+                stored.fingerprint != current.fingerprint && stored.version >= current.version
+            })
+        })
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names
+}
+
+/// Checks the stored schemas and replaces them with the schemas of the current code.
+pub fn internal_migrate_state_schemas() {
+    let incompatible_schemas = incompatible_state_schemas();
+    require!(
+        incompatible_schemas.is_empty(),
+        format!("State schemas changed without a new version: {:?}", incompatible_schemas)
+    );
+    write_state_schema_fingerprints_to_storage(current_state_schema_fingerprints());
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Deserialize, Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StateMigrationFailure {
+    /// One of `accounts`, `margin_accounts`, `assets` or `asset_farms`.
+    pub collection: String,
+    pub key: String,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Deserialize, Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StateMigrationReport {
+    pub num_accounts: u64,
+    pub num_margin_accounts: u64,
+    pub num_assets: u64,
+    pub num_asset_farms: u64,
+    /// The entries stored as legacy variants, which can be rewritten by `migrate_accounts`.
+    pub num_legacy_accounts: u64,
+    pub num_legacy_margin_accounts: u64,
+    pub num_legacy_assets: u64,
+    pub failures: Vec<StateMigrationFailure>,
+    /// Schemas whose fields changed since the stored fingerprints without a new version.
+    pub incompatible_schemas: Vec<String>,
+}
+
+impl StateMigrationReport {
+    fn check<T: BorshDeserialize>(
+        &mut self,
+        collection: &str,
+        key: String,
+        raw: Option<Vec<u8>>,
+        convert: impl FnOnce(T),
+    ) {
+        let error = match raw.map(|raw| T::try_from_slice(&raw)) {
+            Some(Ok(value)) => {
+                convert(value);
+                return;
+            }
+            Some(Err(err)) => err.to_string(),
+            None => "Missing value".to_string(),
+        };
+        self.failures.push(StateMigrationFailure {
+            collection: collection.to_string(),
+            key,
+            error,
+        });
+    }
+}
+
+fn read_lookup_map_raw<K: BorshSerialize>(prefix: StorageKey, key: &K) -> Option<Vec<u8>> {
+    let storage_key = [prefix.into_storage_key(), key.try_to_vec().unwrap()].concat();
+    env::storage_read(&storage_key)
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Deserialize, Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StateMigrationProgress {
+    pub num_migrated_accounts: u64,
+    pub num_migrated_margin_accounts: u64,
+    pub num_migrated_assets: u64,
+    /// The accounts and margin accounts left as legacy variants, because their storage balance
+    /// doesn't cover the storage increase. They can be migrated after a storage deposit.
+    pub num_skipped_accounts: u64,
+    /// The index of the next page, or `None` if the page reached the end of every collection.
+    pub next_index: Option<u64>,
+}
+
+impl Contract {
+    /// Whether the storage balance of the account covers rewriting its entry
+    /// from the legacy length to the current length.
+    fn is_migration_storage_covered(&self, account_id: &AccountId, raw_len: usize, new_len: usize) -> bool {
+        let extra_bytes = new_len.saturating_sub(raw_len) as u64;
+        self.internal_unwrap_storage(account_id).is_storage_covered_with(extra_bytes)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Rewrites the page of accounts, margin accounts and assets stored as legacy variants
+    /// as the current version. The same page index is used for all collections.
+    /// The storage increase of the rewritten accounts is charged to their storage balance,
+    /// and the accounts that can't cover it are skipped.
+    /// Can be called by anyone.
+    pub fn migrate_accounts(&mut self, from_index: Option<u64>, limit: Option<u64>) -> StateMigrationProgress {
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(10);
+        let to_index = from_index + limit;
+        let mut progress = StateMigrationProgress {
+            num_migrated_accounts: 0,
+            num_migrated_margin_accounts: 0,
+            num_migrated_assets: 0,
+            num_skipped_accounts: 0,
+            next_index: None,
+        };
+
+        let accounts: Vec<(AccountId, usize)> = (from_index..std::cmp::min(to_index, self.accounts.len()))
+            .filter_map(|index| {
+                let raw = self.accounts.values_as_vector().get_raw(index).unwrap();
+                is_legacy_state(&raw, "Account").then(|| (self.accounts.keys_as_vector().get(index).unwrap(), raw.len()))
+            })
+            .collect();
+        for (account_id, raw_len) in accounts {
+            let account = self.internal_unwrap_account(&account_id);
+            // The versioned enum adds one byte for the variant.
+            let new_len = account.try_to_vec().unwrap().len() + 1;
+            if self.is_migration_storage_covered(&account_id, raw_len, new_len) {
+                self.internal_set_account(&account_id, account);
+                progress.num_migrated_accounts += 1;
+            } else {
+                progress.num_skipped_accounts += 1;
+            }
+        }
+
+        let margin_accounts: Vec<(AccountId, usize)> = (from_index..std::cmp::min(to_index, self.margin_accounts.len()))
+            .filter_map(|index| {
+                let raw = self.margin_accounts.values_as_vector().get_raw(index).unwrap();
+                is_legacy_state(&raw, "MarginAccount").then(|| (self.margin_accounts.keys_as_vector().get(index).unwrap(), raw.len()))
+            })
+            .collect();
+        for (account_id, raw_len) in margin_accounts {
+            let margin_account = self.internal_unwrap_margin_account(&account_id);
+            let new_len = margin_account.try_to_vec().unwrap().len() + 1;
+            if self.is_migration_storage_covered(&account_id, raw_len, new_len) {
+                self.internal_set_margin_account(&account_id, margin_account);
+                progress.num_migrated_margin_accounts += 1;
+            } else {
+                progress.num_skipped_accounts += 1;
+            }
+        }
+
+        let token_ids: Vec<TokenId> = (from_index..std::cmp::min(to_index, self.asset_ids.len()))
+            .map(|index| self.asset_ids.as_vector().get(index).unwrap())
+            .filter(|token_id| {
+                read_lookup_map_raw(StorageKey::Assets, token_id).map_or(false, |raw| is_legacy_state(&raw, "Asset"))
+            })
+            .collect();
+        for token_id in token_ids {
+            let asset = self.internal_unwrap_asset(&token_id);
+            self.internal_set_asset_without_asset_basic_check(&token_id, asset);
+            progress.num_migrated_assets += 1;
+        }
+
+        let num_entries = [self.accounts.len(), self.margin_accounts.len(), self.asset_ids.len()];
+        if num_entries.iter().any(|&len| len > to_index) {
+            progress.next_index = Some(to_index);
+        }
+        events::emit::accounts_migrated(
+            &env::predecessor_account_id(),
+            from_index,
+            progress.num_migrated_accounts,
+            progress.num_migrated_margin_accounts,
+            progress.num_migrated_assets,
+        );
+        progress
+    }
+
+    /// Deserializes a page of accounts, margin accounts, assets and their asset farms
+    /// through the versioned conversions, and reports the entries that fail.
+    /// The same page index is used for all collections. Net TVL farm is checked on the first page.
+    pub fn check_state_migration(&self, from_index: Option<u64>, limit: Option<u64>) -> StateMigrationReport {
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(20);
+        let mut report = StateMigrationReport {
+            num_accounts: 0,
+            num_margin_accounts: 0,
+            num_assets: 0,
+            num_asset_farms: 0,
+            num_legacy_accounts: 0,
+            num_legacy_margin_accounts: 0,
+            num_legacy_assets: 0,
+            failures: vec![],
+            incompatible_schemas: incompatible_state_schemas(),
+        };
+
+        let account_ids = self.accounts.keys_as_vector();
+        let accounts = self.accounts.values_as_vector();
+        for index in from_index..std::cmp::min(from_index + limit, account_ids.len()) {
+            let account_id = account_ids.get(index).unwrap();
+            let raw = accounts.get_raw(index);
+            report.num_legacy_accounts += raw.as_ref().map_or(false, |raw| is_legacy_state(raw, "Account")) as u64;
+            report.check("accounts", account_id.to_string(), raw, |account: VAccount| {
+                let _ = account.into_account(true);
+            });
+            report.num_accounts += 1;
+        }
+
+        let margin_account_ids = self.margin_accounts.keys_as_vector();
+        let margin_accounts = self.margin_accounts.values_as_vector();
+        for index in from_index..std::cmp::min(from_index + limit, margin_account_ids.len()) {
+            let account_id = margin_account_ids.get(index).unwrap();
+            let raw = margin_accounts.get_raw(index);
+            report.num_legacy_margin_accounts += raw.as_ref().map_or(false, |raw| is_legacy_state(raw, "MarginAccount")) as u64;
+            report.check("margin_accounts", account_id.to_string(), raw, |margin_account: VMarginAccount| {
+                let _ = MarginAccount::from(margin_account);
+            });
+            report.num_margin_accounts += 1;
+        }
+
+        let token_ids = self.asset_ids.as_vector();
+        let mut farm_ids = if from_index == 0 { vec![FarmId::NetTvl] } else { vec![] };
+        for index in from_index..std::cmp::min(from_index + limit, token_ids.len()) {
+            let token_id = token_ids.get(index).unwrap();
+            let raw = read_lookup_map_raw(StorageKey::Assets, &token_id);
+            report.num_legacy_assets += raw.as_ref().map_or(false, |raw| is_legacy_state(raw, "Asset")) as u64;
+            report.check("assets", token_id.to_string(), raw, |asset: VAsset| {
+                let _ = Asset::from(asset);
+            });
+            report.num_assets += 1;
+            farm_ids.push(FarmId::Supplied(token_id.clone()));
+            farm_ids.push(FarmId::Borrowed(token_id.clone()));
+            farm_ids.push(FarmId::TokenNetBalance(token_id.clone()));
+            farm_ids.push(FarmId::MarginDebt(token_id.clone()));
+            farm_ids.push(FarmId::MarginPosition(token_id));
+        }
+        for farm_id in farm_ids {
+            // Asset farms are created with the first farm reward.
+            if let Some(raw) = read_lookup_map_raw(StorageKey::AssetFarms, &farm_id) {
+                report.check("asset_farms", serde_json::to_string(&farm_id).unwrap(), Some(raw), |asset_farm: VAssetFarm| {
+                    let _ = AssetFarm::from(asset_farm);
+                });
+                report.num_asset_farms += 1;
+            }
+        }
+        report
+    }
+
+    /// Returns the state schema fingerprints stored by the last initialization or migration.
+    pub fn get_state_schema_fingerprints(&self) -> HashMap<String, StateSchemaFingerprint> {
+        read_state_schema_fingerprints_from_storage()
+    }
+}
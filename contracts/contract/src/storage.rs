@@ -12,18 +12,25 @@ const MIN_STORAGE_BALANCE: Balance = 10000u128 * env::STORAGE_PRICE_PER_BYTE;
 pub struct Storage {
     pub storage_balance: Balance,
     pub used_bytes: StorageUsage,
+    /// The whitelisted sponsor who funded part of the storage balance.
+    pub sponsor_id: Option<AccountId>,
+    /// The part of the storage balance funded by the sponsor.
+    /// It can't be withdrawn by the account and is refunded to the sponsor on unregister.
+    pub sponsored_balance: Balance,
     #[borsh_skip]
     pub storage_tracker: StorageTracker,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum VStorage {
+    V0(StorageV0),
     Current(Storage),
 }
 
 impl From<VStorage> for Storage {
     fn from(v: VStorage) -> Self {
         match v {
+            VStorage::V0(c) => c.into(),
             VStorage::Current(c) => c,
         }
     }
@@ -40,18 +47,25 @@ impl Storage {
         Self {
             storage_balance: 0,
             used_bytes: 0,
+            sponsor_id: None,
+            sponsored_balance: 0,
             storage_tracker: Default::default(),
         }
     }
 
     fn assert_storage_covered(&self) {
-        let storage_balance_needed = Balance::from(self.used_bytes) * env::storage_byte_cost();
         assert!(
-            storage_balance_needed <= self.storage_balance,
+            self.is_storage_covered_with(0),
             "Not enough storage balance"
         );
     }
 
+    /// Whether the storage balance covers the used bytes with the given extra bytes.
+    pub fn is_storage_covered_with(&self, extra_bytes: StorageUsage) -> bool {
+        let storage_balance_needed = Balance::from(self.used_bytes + extra_bytes) * env::storage_byte_cost();
+        storage_balance_needed <= self.storage_balance
+    }
+
 }
 
 impl Contract {
@@ -109,13 +123,47 @@ impl Contract {
                 available: U128(
                     storage.storage_balance.saturating_sub(
                         std::cmp::max(
-                            Balance::from(storage.used_bytes) * env::storage_byte_cost(),
-                            self.storage_balance_bounds().min.0,
+                            std::cmp::max(
+                                Balance::from(storage.used_bytes) * env::storage_byte_cost(),
+                                self.storage_balance_bounds().min.0,
+                            ),
+                            storage.sponsored_balance,
                         )
                     )
                 ),
             })
     }
+
+    /// Removes the storage of the account and refunds its balance.
+    /// The sponsored part goes back to the sponsor and the rest to the account.
+    pub fn internal_remove_storage(&mut self, account_id: &AccountId) {
+        let account_storage: Storage = self.storage.remove(account_id).unwrap().into();
+        let mut refund = account_storage.storage_balance;
+        if let Some(sponsor_id) = account_storage.sponsor_id {
+            let sponsor_refund = std::cmp::min(account_storage.sponsored_balance, refund);
+            if sponsor_refund > 0 {
+                refund -= sponsor_refund;
+                Promise::new(sponsor_id.clone()).transfer(sponsor_refund);
+                events::emit::storage_sponsor_refund(&sponsor_id, account_id, sponsor_refund);
+            }
+        }
+        if refund > 0 {
+            Promise::new(account_id.clone()).transfer(refund);
+        }
+    }
+
+    /// Creates the account with the given storage and saves them.
+    pub fn internal_register_account(&mut self, account_id: &AccountId, storage: Storage) {
+        let mut account = Account::new(account_id);
+        // HACK: Tracking the extra bytes required to store the storage object itself and
+        // recording this under account storage tracker. It'll be accounted when saving the
+        // account below.
+        account.storage_tracker.start();
+        self.internal_set_storage(account_id, storage);
+        account.storage_tracker.stop();
+        self.internal_set_account(account_id, account);
+        self.internal_set_margin_account(account_id, MarginAccount::new(account_id));
+    }
 }
 
 #[near_bindgen]
@@ -138,6 +186,7 @@ impl StorageManagement for Contract {
             } else {
                 storage.storage_balance += amount;
                 self.internal_set_storage(&account_id, storage);
+                events::emit::storage_deposit(&account_id, &env::predecessor_account_id(), amount);
             }
         } else {
             let min_balance = self.storage_balance_bounds().min.0;
@@ -155,16 +204,8 @@ impl StorageManagement for Contract {
             } else {
                 storage.storage_balance = amount;
             }
-
-            let mut account = Account::new(&account_id);
-            // HACK: Tracking the extra bytes required to store the storage object itself and
-            // recording this under account storage tracker. It'll be accounted when saving the
-            // account below.
-            account.storage_tracker.start();
-            self.internal_set_storage(&account_id, storage);
-            account.storage_tracker.stop();
-            self.internal_set_account(&account_id, account);
-            self.internal_set_margin_account(&account_id, MarginAccount::new(&account_id));
+            events::emit::storage_deposit(&account_id, &env::predecessor_account_id(), storage.storage_balance);
+            self.internal_register_account(&account_id, storage);
         }
         self.internal_storage_balance_of(&account_id).unwrap()
     }
@@ -183,6 +224,7 @@ impl StorageManagement for Contract {
                 storage.storage_balance -= amount;
                 self.internal_set_storage(&account_id, storage);
                 Promise::new(account_id.clone()).transfer(amount);
+                events::emit::storage_withdraw(&account_id, amount);
             }
             self.internal_storage_balance_of(&account_id).unwrap()
         } else {
@@ -208,8 +250,7 @@ impl StorageManagement for Contract {
                 self.margin_accounts.remove(&account_id);
             }
             self.accounts.remove(&account_id);
-            let account_storage: Storage = self.storage.remove(&account_id).unwrap().into();
-            Promise::new(account_id.clone()).transfer(account_storage.storage_balance);
+            self.internal_remove_storage(&account_id);
             true
         } else {
             false
@@ -235,6 +276,8 @@ pub struct StorageBalanceOfDetail {
     pub deposit_amount: U128,
     pub min_deposit_amount: U128,
     pub used_amount: U128,
+    pub sponsor_id: Option<AccountId>,
+    pub sponsored_amount: U128,
 }
 
 #[near_bindgen]
@@ -257,6 +300,8 @@ impl Contract {
                 deposit_amount: storage.storage_balance.into(),
                 min_deposit_amount: self.storage_balance_bounds().min,
                 used_amount: U128(Balance::from(storage.used_bytes) * env::storage_byte_cost()),
+                sponsor_id: storage.sponsor_id,
+                sponsored_amount: storage.sponsored_balance.into(),
             })
     }
 }
@@ -4,4 +4,30 @@ pub const MARGIN_BASE_TOKEN_LIMIT: &str = "bl";
 pub const BOOSTER_TOKENS_KEY: &str = "bt";
 pub const MARGIN_STOP_SERVICE_FEE: &str = "mssf";
 pub const PROTOCOL_DEBTS_KEY: &str = "pd";
-pub const RELIABLE_LIQUIDATOR_WHITELIST: &str = "rlw";
\ No newline at end of file
+pub const RELIABLE_LIQUIDATOR_WHITELIST: &str = "rlw";
+pub const MARGIN_POSITION_TRANSFERS_KEY: &str = "mpt";
+pub const MARGIN_SPLIT_SWAPS_KEY: &str = "msp";
+pub const REWARD_VESTING_CONFIGS_KEY: &str = "rvc";
+pub const BOOSTER_DELEGATION_COOLDOWN_KEY: &str = "bdc";
+pub const GAUGE_CONFIG_KEY: &str = "gc";
+pub const GAUGE_EPOCH_KEY: &str = "ge";
+pub const BOOSTER_EARLY_UNSTAKE_CONFIGS_KEY: &str = "beuc";
+pub const BOOSTER_PENALTY_PER_STAKES_KEY: &str = "bpps";
+pub const BOOSTER_BURNED_PENALTIES_KEY: &str = "bpb";
+pub const STORAGE_SPONSOR_WHITELIST: &str = "ssw";
+pub const STORAGE_SPONSOR_POOLS_KEY: &str = "ssp";
+pub const STORAGE_SPONSOR_MIN_DEPOSITS_KEY: &str = "ssm";
+pub const ADMIN_PROPOSALS_KEY: &str = "ap";
+pub const ADMIN_METHOD_DELAYS_KEY: &str = "amd";
+pub const PAUSE_REGISTRY_KEY: &str = "pr";
+pub const ROLES_KEY: &str = "ro";
+pub const PENDING_OWNER_ID_KEY: &str = "po";
+pub const STATE_SCHEMA_FINGERPRINTS_KEY: &str = "ssf";
+pub const BENEFICIARY_PAYOUT_CONFIGS_KEY: &str = "bpc";
+pub const RESERVE_CONTRIBUTIONS_KEY: &str = "rc";
+pub const RESERVE_CONTRIBUTORS_KEY: &str = "rcc";
+pub const RESERVE_CONTRIBUTION_MIN_AMOUNTS_KEY: &str = "rcm";
+pub const BLOCKLIST_KEY: &str = "bk";
+pub const EXECUTING_ADMIN_PROPOSALS_KEY: &str = "aep";
+pub const PENDING_SWAPS_KEY: &str = "psw";
+pub const PENDING_SWAP_NONCE_KEY: &str = "psn";
@@ -0,0 +1,236 @@
+use crate::*;
+use near_contract_standards::storage_management::{StorageBalance, StorageManagement};
+
+pub fn read_storage_sponsor_whitelist_from_storage() -> HashSet<AccountId> {
+    if let Some(content) = env::storage_read(STORAGE_SPONSOR_WHITELIST.as_bytes()) {
+        HashSet::try_from_slice(&content).expect("deserialize storage sponsor whitelist failed.")
+    } else {
+        HashSet::new()
+    }
+}
+
+pub fn write_storage_sponsor_whitelist_to_storage(data: HashSet<AccountId>) {
+    env::storage_write(STORAGE_SPONSOR_WHITELIST.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+/// The NEAR balances deposited by sponsors to register new accounts.
+pub fn read_storage_sponsor_pools_from_storage() -> HashMap<AccountId, Balance> {
+    if let Some(content) = env::storage_read(STORAGE_SPONSOR_POOLS_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize storage sponsor pools failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_storage_sponsor_pools_to_storage(data: HashMap<AccountId, Balance>) {
+    env::storage_write(STORAGE_SPONSOR_POOLS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+/// The minimum first deposits, in inner decimals, for which sponsors register accounts from their pools.
+pub fn read_storage_sponsor_min_deposits_from_storage() -> HashMap<AccountId, HashMap<TokenId, U128>> {
+    if let Some(content) = env::storage_read(STORAGE_SPONSOR_MIN_DEPOSITS_KEY.as_bytes()) {
+        HashMap::try_from_slice(&content).expect("deserialize storage sponsor min deposits failed.")
+    } else {
+        HashMap::new()
+    }
+}
+
+pub fn write_storage_sponsor_min_deposits_to_storage(data: HashMap<AccountId, HashMap<TokenId, U128>>) {
+    env::storage_write(STORAGE_SPONSOR_MIN_DEPOSITS_KEY.as_bytes(), &data.try_to_vec().unwrap());
+}
+
+pub fn in_storage_sponsor_whitelist(sponsor_id: &AccountId) -> bool {
+    read_storage_sponsor_whitelist_from_storage().contains(sponsor_id)
+}
+
+impl Contract {
+    /// Registers the unregistered account with the minimum storage balance taken from
+    /// the pool of a whitelisted sponsor, if the deposit of the token reaches the minimum
+    /// deposit the sponsor set for it. Sponsors are tried in the order of their account IDs.
+    /// Returns false if the account is already registered or no pool qualifies.
+    pub fn internal_sponsor_registration(&mut self, account_id: &AccountId, token_id: &TokenId, amount: Balance) -> bool {
+        if self.storage.contains_key(account_id) {
+            return false;
+        }
+        let min_balance = self.storage_balance_bounds().min.0;
+        let mut sponsor_ids: Vec<AccountId> = read_storage_sponsor_whitelist_from_storage().into_iter().collect();
+        sponsor_ids.sort();
+        let mut sponsor_pools = read_storage_sponsor_pools_from_storage();
+        let sponsor_min_deposits = read_storage_sponsor_min_deposits_from_storage();
+        let sponsor_id = sponsor_ids.into_iter().find(|sponsor_id| {
+            sponsor_pools.get(sponsor_id).map_or(false, |balance| *balance >= min_balance)
+                && sponsor_min_deposits
+                    .get(sponsor_id)
+                    .and_then(|min_deposits| min_deposits.get(token_id))
+                    .map_or(false, |min_deposit| amount >= min_deposit.0)
+        });
+        if let Some(sponsor_id) = sponsor_id {
+            let balance = sponsor_pools.get_mut(&sponsor_id).unwrap();
+            *balance -= min_balance;
+            if *balance == 0 {
+                sponsor_pools.remove(&sponsor_id);
+            }
+            write_storage_sponsor_pools_to_storage(sponsor_pools);
+
+            let mut storage = Storage::new();
+            storage.storage_balance = min_balance;
+            storage.sponsored_balance = min_balance;
+            storage.sponsor_id = Some(sponsor_id.clone());
+            self.internal_register_account(account_id, storage);
+            events::emit::storage_sponsored(&sponsor_id, account_id, min_balance);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds accounts to the storage sponsor whitelist.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn append_storage_sponsor_whitelist(&mut self, sponsor_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut sponsor_whitelist = read_storage_sponsor_whitelist_from_storage();
+        events::emit::settings_updated("append_storage_sponsor_whitelist", &env::predecessor_account_id(), json!({"sponsor_ids": sponsor_ids}));
+        for sponsor_id in sponsor_ids {
+            require!(sponsor_whitelist.insert(sponsor_id.clone()), format!("exist sponsor: {}", sponsor_id));
+        }
+        write_storage_sponsor_whitelist_to_storage(sponsor_whitelist);
+    }
+
+    /// Removes accounts from the storage sponsor whitelist.
+    /// Removed sponsors can still withdraw their pool balances.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_storage_sponsor_whitelist(&mut self, sponsor_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut sponsor_whitelist = read_storage_sponsor_whitelist_from_storage();
+        events::emit::settings_updated("remove_storage_sponsor_whitelist", &env::predecessor_account_id(), json!({"sponsor_ids": sponsor_ids}));
+        for sponsor_id in sponsor_ids {
+            require!(sponsor_whitelist.remove(&sponsor_id), format!("sponsor {} not exist", sponsor_id));
+        }
+        write_storage_sponsor_whitelist_to_storage(sponsor_whitelist);
+    }
+
+    pub fn get_storage_sponsor_whitelist(&self) -> Vec<AccountId> {
+        read_storage_sponsor_whitelist_from_storage().into_iter().collect()
+    }
+
+    /// Funds the storage balance of the given account on behalf of the predecessor sponsor.
+    /// Registers the account if needed. The sponsored part of the storage balance
+    /// is refunded to the sponsor when the account unregisters.
+    /// - Requires to be called by a whitelisted sponsor.
+    /// - Requires the account to be unregistered, sponsored by the same sponsor,
+    ///   or neither sponsored nor funded by itself.
+    #[payable]
+    pub fn storage_sponsor_deposit(&mut self, account_id: AccountId) -> StorageBalance {
+        let sponsor_id = env::predecessor_account_id();
+        require!(in_storage_sponsor_whitelist(&sponsor_id), "Not a whitelisted sponsor");
+        let amount = env::attached_deposit();
+        require!(amount > 0, "Requires attached deposit");
+        if let Some(mut storage) = self.internal_get_storage(&account_id) {
+            require!(
+                storage.sponsor_id.as_ref() == Some(&sponsor_id)
+                    || (storage.sponsor_id.is_none() && storage.storage_balance == storage.sponsored_balance),
+                "The account is sponsored by another sponsor or funds its own storage"
+            );
+            storage.sponsor_id = Some(sponsor_id.clone());
+            storage.storage_balance += amount;
+            storage.sponsored_balance += amount;
+            self.internal_set_storage(&account_id, storage);
+        } else {
+            require!(
+                amount >= self.storage_balance_bounds().min.0,
+                "The attached deposit is less than the mimimum storage balance"
+            );
+            let mut storage = Storage::new();
+            storage.storage_balance = amount;
+            storage.sponsored_balance = amount;
+            storage.sponsor_id = Some(sponsor_id.clone());
+            self.internal_register_account(&account_id, storage);
+        }
+        events::emit::storage_sponsored(&sponsor_id, &account_id, amount);
+        self.internal_storage_balance_of(&account_id).unwrap()
+    }
+
+    /// Deposits the attached NEAR into the pool of the predecessor sponsor.
+    /// Unregistered accounts making their first deposit through `ft_on_transfer`
+    /// are registered with the minimum storage balance from the pool,
+    /// if the deposit reaches the minimum deposit the sponsor set for the token.
+    /// Returns the pool balance.
+    /// - Requires to be called by a whitelisted sponsor.
+    #[payable]
+    pub fn storage_sponsor_pool_deposit(&mut self) -> U128 {
+        let sponsor_id = env::predecessor_account_id();
+        require!(in_storage_sponsor_whitelist(&sponsor_id), "Not a whitelisted sponsor");
+        let amount = env::attached_deposit();
+        require!(amount > 0, "Requires attached deposit");
+        let mut sponsor_pools = read_storage_sponsor_pools_from_storage();
+        let balance = sponsor_pools.entry(sponsor_id.clone()).or_insert(0);
+        *balance += amount;
+        let balance = *balance;
+        write_storage_sponsor_pools_to_storage(sponsor_pools);
+        events::emit::storage_sponsor_pool_deposit(&sponsor_id, amount);
+        balance.into()
+    }
+
+    /// Withdraws the given amount or the whole balance from the pool of the predecessor.
+    /// Returns the remaining pool balance.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn storage_sponsor_pool_withdraw(&mut self, amount: Option<U128>) -> U128 {
+        assert_one_yocto();
+        let sponsor_id = env::predecessor_account_id();
+        let mut sponsor_pools = read_storage_sponsor_pools_from_storage();
+        let balance = sponsor_pools.remove(&sponsor_id).unwrap_or(0);
+        let amount = amount.map(|a| a.0).unwrap_or(balance);
+        require!(amount > 0, "Nothing to withdraw");
+        require!(amount <= balance, "The amount is greater than the pool balance");
+        let remaining = balance - amount;
+        if remaining > 0 {
+            sponsor_pools.insert(sponsor_id.clone(), remaining);
+        }
+        write_storage_sponsor_pools_to_storage(sponsor_pools);
+        Promise::new(sponsor_id.clone()).transfer(amount);
+        events::emit::storage_sponsor_pool_withdraw(&sponsor_id, amount);
+        remaining.into()
+    }
+
+    /// Sets the minimum first deposits, in inner decimals, for which the pool of the predecessor
+    /// sponsor registers accounts. Deposits of other tokens don't draw from the pool.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by a whitelisted sponsor.
+    #[payable]
+    pub fn set_storage_sponsor_min_deposits(&mut self, min_deposits: HashMap<TokenId, U128>) {
+        assert_one_yocto();
+        let sponsor_id = env::predecessor_account_id();
+        require!(in_storage_sponsor_whitelist(&sponsor_id), "Not a whitelisted sponsor");
+        events::emit::settings_updated("set_storage_sponsor_min_deposits", &sponsor_id, json!({"min_deposits": min_deposits}));
+        let mut sponsor_min_deposits = read_storage_sponsor_min_deposits_from_storage();
+        if min_deposits.is_empty() {
+            sponsor_min_deposits.remove(&sponsor_id);
+        } else {
+            sponsor_min_deposits.insert(sponsor_id, min_deposits);
+        }
+        write_storage_sponsor_min_deposits_to_storage(sponsor_min_deposits);
+    }
+
+    pub fn get_storage_sponsor_min_deposits(&self, sponsor_id: AccountId) -> HashMap<TokenId, U128> {
+        read_storage_sponsor_min_deposits_from_storage()
+            .remove(&sponsor_id)
+            .unwrap_or_default()
+    }
+
+    pub fn get_storage_sponsor_pools(&self) -> HashMap<AccountId, U128> {
+        read_storage_sponsor_pools_from_storage()
+            .into_iter()
+            .map(|(sponsor_id, balance)| (sponsor_id, balance.into()))
+            .collect()
+    }
+}
@@ -4,10 +4,13 @@ use crate::*;
 impl Contract {
     /// A method to migrate a state during the contract upgrade.
     /// Can only be called after upgrade method.
+    /// Fails if a stored struct changed its fields without a new version.
     #[private]
     #[init(ignore_state)]
     pub fn migrate_state() -> Self {
-        env::state_read().unwrap()
+        let contract: Self = env::state_read().unwrap();
+        internal_migrate_state_schemas();
+        contract
     }
 
     /// Returns semver of this contract.
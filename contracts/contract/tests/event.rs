@@ -29,6 +29,7 @@ async fn test_deposit_event() -> Result<()> {
     let value: serde_json::Value =
         serde_json::from_str(&event[EVENT_JSON.len()..]).expect("Failed to parse the event");
     assert_eq!(value["standard"].as_str().unwrap(), "burrow");
+    assert_eq!(value["version"].as_str().unwrap(), "1.1.0");
     assert_eq!(value["event"].as_str().unwrap(), "deposit");
     assert_eq!(
         value["data"][0]["account_id"].as_str().unwrap(),
@@ -44,3 +45,343 @@ async fn test_deposit_event() -> Result<()> {
     );
     Ok(())
 }
+
+/// Blanks out comments and the contents of string and char literals, keeping the positions,
+/// so that braces and names in them aren't taken for code.
+fn sanitize(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut res = bytes.to_vec();
+    let blank = |res: &mut Vec<u8>, from: usize, to: usize| {
+        for b in res[from..to].iter_mut() {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+    };
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"//") {
+            let end = source[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            blank(&mut res, i, end);
+            i = end;
+        } else if bytes[i..].starts_with(b"/*") {
+            let end = source[i..].find("*/").map_or(bytes.len(), |end| i + end + 2);
+            blank(&mut res, i, end);
+            i = end;
+        } else if bytes[i..].starts_with(b"r#\"") || (bytes[i] == b'r' && bytes.get(i + 1) == Some(&b'"')) {
+            let hashes = bytes[i + 1..].iter().take_while(|b| **b == b'#').count();
+            let open = i + 1 + hashes + 1;
+            let closing = format!("\"{}", "#".repeat(hashes));
+            let end = source[open..].find(&closing).map_or(bytes.len(), |end| open + end);
+            blank(&mut res, open, end);
+            i = end + closing.len();
+        } else if bytes[i] == b'"' {
+            let mut end = i + 1;
+            while end < bytes.len() && bytes[end] != b'"' {
+                end += if bytes[end] == b'\\' { 2 } else { 1 };
+            }
+            blank(&mut res, i + 1, end);
+            i = end + 1;
+        } else if bytes[i] == b'\'' && bytes.get(i + 1) == Some(&b'\\') {
+            let end = i + 2 + source[i + 2..].find('\'').unwrap();
+            blank(&mut res, i + 1, end);
+            i = end + 1;
+        } else if bytes[i] == b'\'' && bytes.get(i + 2) == Some(&b'\'') {
+            blank(&mut res, i + 1, i + 2);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+    String::from_utf8(res).unwrap()
+}
+
+/// Returns the index right after the brace that closes the one at `open`.
+fn block_end(source: &str, open: usize) -> usize {
+    let mut depth = 0;
+    for (i, c) in source[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    panic!("Unbalanced braces");
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the identifier at the start of the text.
+fn ident(text: &str) -> String {
+    text.trim_start().chars().take_while(|c| is_ident_char(*c)).collect()
+}
+
+/// Whether the keyword starts at `pos` as a whole word.
+fn is_keyword_at(source: &str, pos: usize, keyword: &str) -> bool {
+    source[pos..].starts_with(keyword)
+        && !source[..pos].ends_with(is_ident_char)
+        && source[pos + keyword.len()..].starts_with(|c: char| c.is_whitespace() || c == '<')
+}
+
+/// Returns the type an `impl` or `trait` header is for, e.g. `Contract` for `<T> Trait<T> for Contract`.
+fn impl_owner(header: &str) -> String {
+    let mut header = header.trim();
+    if header.starts_with('<') {
+        let mut depth = 0;
+        for (i, c) in header.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                header = &header[i + 1..];
+                break;
+            }
+        }
+    }
+    let header = header.rsplit(" for ").next().unwrap();
+    let path = header.trim().split(|c| c == '<' || c == ' ').next().unwrap();
+    path.rsplit("::").next().unwrap().to_string()
+}
+
+struct Function {
+    module: String,
+    /// The type of the `impl` block of a method.
+    owner: Option<String>,
+    name: String,
+    body: String,
+    /// Whether it's a `&mut self` method exposed by a `#[near_bindgen]` impl block.
+    is_mutating_method: bool,
+}
+
+/// Collects the functions with a body in the items of the sanitized source, skipping test modules.
+fn collect_functions(source: &str, module: &str, owner: Option<&str>, is_near_bindgen: bool, functions: &mut Vec<Function>) {
+    let mut item_start = 0;
+    let mut i = 0;
+    while i < source.len() {
+        if source[i..].starts_with('{') {
+            i = block_end(source, i);
+            item_start = i;
+        } else if source[i..].starts_with(';') {
+            i += 1;
+            item_start = i;
+        } else if is_keyword_at(source, i, "fn") {
+            let name = ident(&source[i + 2..]);
+            let (open, semi) = (source[i..].find('{'), source[i..].find(';'));
+            match open {
+                Some(open) if semi.map_or(true, |semi| open < semi) => {
+                    let open = i + open;
+                    let end = block_end(source, open);
+                    let args = source[i + source[i..].find('(').unwrap() + 1..].trim_start();
+                    functions.push(Function {
+                        module: module.to_string(),
+                        owner: owner.map(|owner| owner.to_string()),
+                        name,
+                        body: source[open..end].to_string(),
+                        is_mutating_method: is_near_bindgen && args.starts_with("&mut self"),
+                    });
+                    i = end;
+                }
+                _ => i += semi.unwrap() + 1,
+            }
+            item_start = i;
+        } else if is_keyword_at(source, i, "impl") || is_keyword_at(source, i, "trait") {
+            let keyword_len = if source[i..].starts_with("impl") { 4 } else { 5 };
+            let open = i + source[i..].find('{').unwrap();
+            let end = block_end(source, open);
+            let is_near_bindgen = source[item_start..i].contains("#[near_bindgen]");
+            let owner = impl_owner(&source[i + keyword_len..open]);
+            collect_functions(&source[open + 1..end - 1], module, Some(&owner), is_near_bindgen, functions);
+            i = end;
+            item_start = i;
+        } else if is_keyword_at(source, i, "mod") {
+            let name = ident(&source[i + 3..]);
+            let (open, semi) = (source[i..].find('{'), source[i..].find(';'));
+            match open {
+                Some(open) if semi.map_or(true, |semi| open < semi) => {
+                    let open = i + open;
+                    let end = block_end(source, open);
+                    if !source[item_start..i].contains("cfg(test)") {
+                        let module = format!("{}::{}", module, name);
+                        collect_functions(&source[open + 1..end - 1], &module, None, false, functions);
+                    }
+                    i = end;
+                }
+                _ => i += semi.unwrap() + 1,
+            }
+            item_start = i;
+        } else {
+            i += source[i..].chars().next().unwrap().len_utf8();
+        }
+    }
+}
+
+/// How a call names the function it calls.
+enum Callee {
+    /// `self.name(..)` or `Self::name(..)`
+    SelfMethod,
+    /// `Type::name(..)`
+    Associated(String),
+    /// `module::name(..)`
+    ModulePath(String),
+    /// `receiver.name(..)`, with whether the receiver is a callback promise built with `Self::ext`.
+    Method { is_callback: bool },
+    /// `name(..)`
+    Free,
+}
+
+/// Returns the name and the form of every call in the body.
+fn collect_calls(body: &str) -> Vec<(String, Callee)> {
+    let mut calls = vec![];
+    let mut i = 0;
+    while i < body.len() {
+        if !body[i..].starts_with(is_ident_char) || body[..i].ends_with(is_ident_char) {
+            i += body[i..].chars().next().unwrap().len_utf8();
+            continue;
+        }
+        let name = ident(&body[i..]);
+        let pos = i;
+        i += name.len();
+        if !body[i..].trim_start().starts_with('(') || name.starts_with(char::is_numeric) {
+            continue;
+        }
+        let before = &body[..pos];
+        if before.trim_end().ends_with(" fn") || before.trim_end() == "fn" {
+            continue;
+        }
+        let callee = if before.ends_with("self.") || before.ends_with("Self::") {
+            Callee::SelfMethod
+        } else if before.trim_end().ends_with('.') {
+            let statement_start = before.rfind(|c| c == ';' || c == '{' || c == '}').map_or(0, |start| start + 1);
+            Callee::Method { is_callback: before[statement_start..].contains("Self::ext(") }
+        } else if let Some(path) = before.strip_suffix("::") {
+            let segment: String = path.chars().rev().take_while(|c| is_ident_char(*c)).collect::<Vec<_>>().into_iter().rev().collect();
+            if segment.starts_with(char::is_uppercase) {
+                Callee::Associated(segment)
+            } else {
+                Callee::ModulePath(segment)
+            }
+        } else {
+            Callee::Free
+        };
+        calls.push((name, callee));
+    }
+    calls
+}
+
+/// Returns the indices of the functions the call may resolve to.
+fn resolve(functions: &[Function], caller: &Function, name: &str, callee: &Callee) -> Vec<usize> {
+    let candidates = |filter: &dyn Fn(&Function) -> bool| -> Vec<usize> {
+        functions
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.name == name && filter(f))
+            .map(|(index, _)| index)
+            .collect()
+    };
+    match callee {
+        Callee::SelfMethod => candidates(&|f| f.owner == caller.owner),
+        Callee::Associated(owner) => candidates(&|f| f.owner.as_ref() == Some(owner)),
+        Callee::ModulePath(module) => candidates(&|f| {
+            f.owner.is_none() && (f.module == *module || f.module.ends_with(&format!("::{}", module)))
+        }),
+        Callee::Method { is_callback: true } => candidates(&|f| f.owner.as_deref() == Some("Contract")),
+        Callee::Method { is_callback: false } => {
+            candidates(&|f| f.owner.is_some() && f.owner.as_deref() != Some("Contract"))
+        }
+        Callee::Free => {
+            let local = candidates(&|f| f.owner.is_none() && f.module == caller.module);
+            if local.is_empty() {
+                candidates(&|f| f.owner.is_none())
+            } else {
+                local
+            }
+        }
+    }
+}
+
+/// Every mutating method has to emit an event, directly or through the functions it calls.
+/// Calls are resolved by the impl block or the module of the callee, and a call that resolves
+/// to several functions, of which only some emit events, fails the test as ambiguous.
+#[test]
+fn test_every_mutating_method_emits_event() {
+    let src_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let mut functions = vec![];
+    for entry in std::fs::read_dir(src_dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().map_or(true, |ext| ext != "rs") {
+            continue;
+        }
+        let module = path.file_stem().unwrap().to_str().unwrap().to_string();
+        let source = sanitize(&std::fs::read_to_string(&path).unwrap());
+        collect_functions(&source, &module, None, false, &mut functions);
+    }
+    let calls: Vec<Vec<(String, Vec<usize>)>> = functions
+        .iter()
+        .map(|caller| {
+            collect_calls(&caller.body)
+                .into_iter()
+                .map(|(name, callee)| {
+                    let callees = resolve(&functions, caller, &name, &callee);
+                    (name, callees)
+                })
+                .filter(|(_, callees)| !callees.is_empty())
+                .collect()
+        })
+        .collect();
+
+    let mut emitting: Vec<bool> = functions.iter().map(|f| f.body.contains("events::emit::")).collect();
+    loop {
+        let reached: Vec<usize> = (0..functions.len())
+            .filter(|index| {
+                !emitting[*index]
+                    && calls[*index]
+                        .iter()
+                        .any(|(_, callees)| callees.iter().any(|callee| emitting[*callee]))
+            })
+            .collect();
+        if reached.is_empty() {
+            break;
+        }
+        for index in reached {
+            emitting[index] = true;
+        }
+    }
+
+    let describe = |index: usize| {
+        let f: &Function = &functions[index];
+        format!("{}::{}{}", f.module, f.owner.as_ref().map_or(String::new(), |owner| format!("{}::", owner)), f.name)
+    };
+    let mut ambiguous: Vec<String> = vec![];
+    for (index, caller_calls) in calls.iter().enumerate() {
+        for (name, callees) in caller_calls {
+            if callees.iter().any(|callee| emitting[*callee]) && callees.iter().any(|callee| !emitting[*callee]) {
+                ambiguous.push(format!(
+                    "{} calls {}: {:?}",
+                    describe(index),
+                    name,
+                    callees.iter().map(|callee| describe(*callee)).collect::<Vec<_>>()
+                ));
+            }
+        }
+    }
+    ambiguous.sort();
+    ambiguous.dedup();
+    assert!(ambiguous.is_empty(), "Ambiguous calls: {:#?}", ambiguous);
+
+    let mut silent: Vec<String> = (0..functions.len())
+        .filter(|index| functions[*index].is_mutating_method && !emitting[*index])
+        .map(describe)
+        .collect();
+    silent.sort();
+    assert!(functions.iter().any(|f| f.is_mutating_method));
+    assert!(silent.is_empty(), "Methods without events: {:#?}", silent);
+}
@@ -121,6 +121,7 @@ async fn test_margin_stop_open_position_with_stop() -> Result<()> {
         wrap_token_contract.0.id(), d(20, 24).into(),
         nusdt_token_contract.0.id(), d(180, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -238,6 +239,7 @@ async fn test_margin_stop_set_after_open() -> Result<()> {
         wrap_token_contract.0.id(), d(20, 24).into(),
         nusdt_token_contract.0.id(), d(180, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -368,6 +370,7 @@ async fn test_margin_stop_remove() -> Result<()> {
         wrap_token_contract.0.id(), d(20, 24).into(),
         nusdt_token_contract.0.id(), d(180, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -527,6 +530,7 @@ async fn test_margin_stop_keeper_executes_stop_loss() -> Result<()> {
         wrap_token_contract.0.id(), d(100, 24).into(),
         nusdt_token_contract.0.id(), d(900, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -592,6 +596,7 @@ async fn test_margin_stop_keeper_executes_stop_loss() -> Result<()> {
         position_amount,
         debt_balance,
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -715,6 +720,7 @@ async fn test_margin_stop_storage_accounting() -> Result<()> {
         wrap_token_contract.0.id(), d(20, 24).into(),
         nusdt_token_contract.0.id(), d(180, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -902,6 +908,7 @@ async fn test_stop_partial_token_p_rejected_when_c_eq_d() -> Result<()> {
         nusdt_token_contract.0.id(), d(1000, 18).into(),
         wrap_token_contract.0.id(), d(90, 24).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -960,6 +967,7 @@ async fn test_stop_partial_token_p_rejected_when_c_eq_d() -> Result<()> {
             partial_p_amount,
             partial_min_debt,
             SwapIndication {
+                splits: vec![],
                 dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
                 swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                     referral_id: None,
@@ -1082,6 +1090,7 @@ async fn test_stop_excessive_min_debt_rejected_when_c_eq_p() -> Result<()> {
         wrap_token_contract.0.id(), d(50, 24).into(),
         nusdt_token_contract.0.id(), d(450, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -1139,6 +1148,7 @@ async fn test_stop_excessive_min_debt_rejected_when_c_eq_p() -> Result<()> {
             matching_token_p_amount,
             excessive_min_debt,
             SwapIndication {
+                splits: vec![],
                 dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
                 swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                     referral_id: None,
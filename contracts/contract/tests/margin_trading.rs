@@ -66,6 +66,7 @@ async fn test_margin_trading() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(20, 24).into(), nusdt_token_contract.0.id(), d(180, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -105,6 +106,7 @@ async fn test_margin_trading() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
         &pos_id, d(100, 18), d(10, 24), 
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -136,6 +138,7 @@ async fn test_margin_trading() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
         &pos_id, position_amount, min_out_amount, 
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -160,6 +163,461 @@ async fn test_margin_trading() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_margin_trading_increase_position() -> Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let root = worker.root_account()?;
+
+    let nusdt_token_contract = deploy_mock_ft(&root, "nusdt", 18).await?;
+    let wrap_token_contract = deploy_mock_ft(&root, "wrap", 18).await?;
+    let wrap_reserve_amount = d(10000, 24);
+    let nusdt_reserve_amount = d(10000, 6);
+    check!(wrap_token_contract.ft_mint(&root, &root, wrap_reserve_amount));
+    check!(nusdt_token_contract.ft_mint(&root, &root, nusdt_reserve_amount));
+
+    let ref_exchange_contract = deploy_ref_exchange(&root).await?;
+    {
+        check!(nusdt_token_contract.ft_storage_deposit(ref_exchange_contract.0.id()));
+        check!(wrap_token_contract.ft_storage_deposit(ref_exchange_contract.0.id()));
+        check!(ref_exchange_contract.storage_deposit(&root));
+        check!(ref_exchange_contract.extend_whitelisted_tokens(&root, vec![nusdt_token_contract.0.id(), wrap_token_contract.0.id()]));
+    }
+
+    let oracle_contract = deploy_oralce(&root).await?;
+    let burrowland_contract = deploy_burrowland_with_price_oracle(&root).await?;
+    check!(burrowland_contract.add_asset_handler(&root, &wrap_token_contract));
+    check!(burrowland_contract.add_asset_handler(&root, &nusdt_token_contract));
+    check!(wrap_token_contract.ft_storage_deposit(burrowland_contract.0.id()));
+    check!(nusdt_token_contract.ft_storage_deposit(burrowland_contract.0.id()));
+    check!(burrowland_contract.deposit_to_reserve(&wrap_token_contract, &root, wrap_reserve_amount));
+    check!(burrowland_contract.deposit_to_reserve(&nusdt_token_contract, &root, nusdt_reserve_amount));
+
+    let alice = create_account(&root, "alice", None).await;
+    check!(ref_exchange_contract.storage_deposit(&alice));
+    check!(burrowland_contract.storage_deposit(&alice));
+
+    assert!(nusdt_token_contract.ft_mint(&root, &alice, d(10000, 6)).await?.is_success());
+    assert!(wrap_token_contract.ft_mint(&root, &alice, d(100000, 24)).await?.is_success());
+
+    check!(ref_exchange_contract.deposit(&nusdt_token_contract, &alice, d(10000, 6)));
+    check!(ref_exchange_contract.deposit(&wrap_token_contract, &alice, d(10000, 24)));
+
+    check!(ref_exchange_contract.add_simple_swap_pool(&root, vec![nusdt_token_contract.0.id(), wrap_token_contract.0.id()], 5));
+    check!(ref_exchange_contract.add_simple_liquidity(&alice, 0, vec![U128(d(10000, 6)), U128(d(1000, 24))], Some(vec![U128(0), U128(0)])));
+
+    check!(view ref_exchange_contract.get_pool(0));
+
+    let supply_amount = d(1000, 18);
+    let extra_decimals_mult = d(1, 12);
+    check!(nusdt_token_contract.ft_mint(&root, &alice, supply_amount * 10));
+    check!(wrap_token_contract.ft_storage_deposit(alice.id()));
+
+    check!(view burrowland_contract.get_margin_account(&alice));
+
+    check!(burrowland_contract.deposit_to_margin(&nusdt_token_contract, &alice, supply_amount / extra_decimals_mult));
+    
+    check!(burrowland_contract.register_margin_dex(&root, ref_exchange_contract.0.id(), 1));
+    check!(burrowland_contract.register_margin_token(&root, nusdt_token_contract.0.id(), 0));
+    check!(burrowland_contract.register_margin_token(&root, wrap_token_contract.0.id(), 1));
+
+    let current_timestamp = worker.view_block().await?.timestamp();
+    check!(logs burrowland_contract.margin_trading_open_position_by_oracle_call(
+        &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
+        nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(20, 24).into(), nusdt_token_contract.0.id(), d(180, 18).into(),
+        SwapIndication {
+            splits: vec![],
+            dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
+            swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
+                referral_id: None,
+                client_echo: None,
+                skip_degen_price_sync: None,
+                actions: vec![
+                    RefV1Action::Swap(RefV1SwapAction{
+                        pool_id: 0,
+                        token_in: near_sdk::AccountId::new_unchecked(wrap_token_contract.0.id().to_string()),
+                        amount_in: Some(U128(d(20, 24))),
+                        token_out: near_sdk::AccountId::new_unchecked(nusdt_token_contract.0.id().to_string()),
+                        min_amount_out: U128(d(180, 6)),
+                    })
+                ]
+            }).unwrap()
+        }
+    ));
+
+    check!(view burrowland_contract.get_margin_account(&alice));
+
+    let mut alice_margin_account = burrowland_contract.get_margin_account(&alice).await?.unwrap();
+    let pos_id = alice_margin_account.margin_positions.keys().collect::<Vec<&String>>()[0].clone();
+
+    let position = alice_margin_account.margin_positions.get(&pos_id).unwrap();
+    let pre_token_d_amount = position.token_d_info.balance;
+    let pre_token_p_amount = position.token_p_amount;
+    let pre_debt_cap = position.debt_cap;
+
+    let current_timestamp = worker.view_block().await?.timestamp();
+    check!(logs burrowland_contract.margin_trading_increase_mtposition_by_oracle_call(
+        &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
+        &pos_id, d(10, 24), d(90, 18),
+        SwapIndication {
+            splits: vec![],
+            dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
+            swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
+                referral_id: None,
+                client_echo: None,
+                skip_degen_price_sync: None,
+                actions: vec![
+                    RefV1Action::Swap(RefV1SwapAction{
+                        pool_id: 0,
+                        token_in: near_sdk::AccountId::new_unchecked(wrap_token_contract.0.id().to_string()),
+                        amount_in: Some(U128(d(10, 24))),
+                        token_out: near_sdk::AccountId::new_unchecked(nusdt_token_contract.0.id().to_string()),
+                        min_amount_out: U128(d(90, 6)),
+                    })
+                ]
+            }).unwrap()
+        }
+    ));
+
+    alice_margin_account = burrowland_contract.get_margin_account(&alice).await?.unwrap();
+    assert_eq!(alice_margin_account.margin_positions.len(), 1);
+    let position = alice_margin_account.margin_positions.get(&pos_id).unwrap();
+    assert!(!position.is_locking);
+    assert!(position.token_d_info.balance >= pre_token_d_amount + d(10, 24));
+    assert!(position.token_p_amount >= pre_token_p_amount + d(90, 18));
+    assert_eq!(position.debt_cap, pre_debt_cap + d(10, 24));
+    assert!(alice_margin_account.position_latest_actions.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_margin_trading_repay_and_claim() -> Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let root = worker.root_account()?;
+
+    let nusdt_token_contract = deploy_mock_ft(&root, "nusdt", 18).await?;
+    let wrap_token_contract = deploy_mock_ft(&root, "wrap", 18).await?;
+    let wrap_reserve_amount = d(10000, 24);
+    let nusdt_reserve_amount = d(10000, 6);
+    check!(wrap_token_contract.ft_mint(&root, &root, wrap_reserve_amount));
+    check!(nusdt_token_contract.ft_mint(&root, &root, nusdt_reserve_amount));
+
+    let ref_exchange_contract = deploy_ref_exchange(&root).await?;
+    {
+        check!(nusdt_token_contract.ft_storage_deposit(ref_exchange_contract.0.id()));
+        check!(wrap_token_contract.ft_storage_deposit(ref_exchange_contract.0.id()));
+        check!(ref_exchange_contract.storage_deposit(&root));
+        check!(ref_exchange_contract.extend_whitelisted_tokens(&root, vec![nusdt_token_contract.0.id(), wrap_token_contract.0.id()]));
+    }
+
+    let oracle_contract = deploy_oralce(&root).await?;
+    let burrowland_contract = deploy_burrowland_with_price_oracle(&root).await?;
+    check!(burrowland_contract.add_asset_handler(&root, &wrap_token_contract));
+    check!(burrowland_contract.add_asset_handler(&root, &nusdt_token_contract));
+    check!(wrap_token_contract.ft_storage_deposit(burrowland_contract.0.id()));
+    check!(nusdt_token_contract.ft_storage_deposit(burrowland_contract.0.id()));
+    check!(burrowland_contract.deposit_to_reserve(&wrap_token_contract, &root, wrap_reserve_amount));
+    check!(burrowland_contract.deposit_to_reserve(&nusdt_token_contract, &root, nusdt_reserve_amount));
+
+    let alice = create_account(&root, "alice", None).await;
+    check!(ref_exchange_contract.storage_deposit(&alice));
+    check!(burrowland_contract.storage_deposit(&alice));
+
+    assert!(nusdt_token_contract.ft_mint(&root, &alice, d(10000, 6)).await?.is_success());
+    assert!(wrap_token_contract.ft_mint(&root, &alice, d(100000, 24)).await?.is_success());
+
+    check!(ref_exchange_contract.deposit(&nusdt_token_contract, &alice, d(10000, 6)));
+    check!(ref_exchange_contract.deposit(&wrap_token_contract, &alice, d(10000, 24)));
+
+    check!(ref_exchange_contract.add_simple_swap_pool(&root, vec![nusdt_token_contract.0.id(), wrap_token_contract.0.id()], 5));
+    check!(ref_exchange_contract.add_simple_liquidity(&alice, 0, vec![U128(d(10000, 6)), U128(d(1000, 24))], Some(vec![U128(0), U128(0)])));
+
+    check!(view ref_exchange_contract.get_pool(0));
+
+    let supply_amount = d(1000, 18);
+    let extra_decimals_mult = d(1, 12);
+    check!(nusdt_token_contract.ft_mint(&root, &alice, supply_amount * 10));
+    check!(wrap_token_contract.ft_storage_deposit(alice.id()));
+
+    check!(view burrowland_contract.get_margin_account(&alice));
+
+    check!(burrowland_contract.deposit_to_margin(&nusdt_token_contract, &alice, supply_amount / extra_decimals_mult));
+    
+    check!(burrowland_contract.register_margin_dex(&root, ref_exchange_contract.0.id(), 1));
+    check!(burrowland_contract.register_margin_token(&root, nusdt_token_contract.0.id(), 0));
+    check!(burrowland_contract.register_margin_token(&root, wrap_token_contract.0.id(), 1));
+
+    let current_timestamp = worker.view_block().await?.timestamp();
+    check!(logs burrowland_contract.margin_trading_open_position_by_oracle_call(
+        &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
+        nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(20, 24).into(), nusdt_token_contract.0.id(), d(180, 18).into(),
+        SwapIndication {
+            splits: vec![],
+            dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
+            swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
+                referral_id: None,
+                client_echo: None,
+                skip_degen_price_sync: None,
+                actions: vec![
+                    RefV1Action::Swap(RefV1SwapAction{
+                        pool_id: 0,
+                        token_in: near_sdk::AccountId::new_unchecked(wrap_token_contract.0.id().to_string()),
+                        amount_in: Some(U128(d(20, 24))),
+                        token_out: near_sdk::AccountId::new_unchecked(nusdt_token_contract.0.id().to_string()),
+                        min_amount_out: U128(d(180, 6)),
+                    })
+                ]
+            }).unwrap()
+        }
+    ));
+
+    check!(view burrowland_contract.get_margin_account(&alice));
+
+    let mut alice_margin_account = burrowland_contract.get_margin_account(&alice).await?.unwrap();
+    let pos_id = alice_margin_account.margin_positions.keys().collect::<Vec<&String>>()[0].clone();
+
+    let position = alice_margin_account.margin_positions.get(&pos_id).unwrap();
+    let token_c_balance = position.token_c_info.balance;
+    let token_p_amount = position.token_p_amount;
+    let debt_amount = position.token_d_info.balance;
+
+    check!(burrowland_contract.deposit_to_margin(&wrap_token_contract, &alice, d(30, 24)));
+    check!(logs burrowland_contract.margin_trading_repay_and_claim_mtposition(&alice, &pos_id));
+
+    alice_margin_account = burrowland_contract.get_margin_account(&alice).await?.unwrap();
+    assert!(alice_margin_account.margin_positions.is_empty());
+    let supplied = alice_margin_account.supplied.iter()
+        .map(|v| (v.token_id.to_string(), v.balance))
+        .collect::<HashMap<String, u128>>();
+    assert!(supplied[&nusdt_token_contract.0.id().to_string()] >= token_c_balance + token_p_amount - 1);
+    assert!(supplied[&wrap_token_contract.0.id().to_string()] <= d(30, 24) - debt_amount);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_margin_trading_transfer_position() -> Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let root = worker.root_account()?;
+
+    let nusdt_token_contract = deploy_mock_ft(&root, "nusdt", 18).await?;
+    let wrap_token_contract = deploy_mock_ft(&root, "wrap", 18).await?;
+    let wrap_reserve_amount = d(10000, 24);
+    let nusdt_reserve_amount = d(10000, 6);
+    check!(wrap_token_contract.ft_mint(&root, &root, wrap_reserve_amount));
+    check!(nusdt_token_contract.ft_mint(&root, &root, nusdt_reserve_amount));
+
+    let ref_exchange_contract = deploy_ref_exchange(&root).await?;
+    {
+        check!(nusdt_token_contract.ft_storage_deposit(ref_exchange_contract.0.id()));
+        check!(wrap_token_contract.ft_storage_deposit(ref_exchange_contract.0.id()));
+        check!(ref_exchange_contract.storage_deposit(&root));
+        check!(ref_exchange_contract.extend_whitelisted_tokens(&root, vec![nusdt_token_contract.0.id(), wrap_token_contract.0.id()]));
+    }
+
+    let oracle_contract = deploy_oralce(&root).await?;
+    let burrowland_contract = deploy_burrowland_with_price_oracle(&root).await?;
+    check!(burrowland_contract.add_asset_handler(&root, &wrap_token_contract));
+    check!(burrowland_contract.add_asset_handler(&root, &nusdt_token_contract));
+    check!(wrap_token_contract.ft_storage_deposit(burrowland_contract.0.id()));
+    check!(nusdt_token_contract.ft_storage_deposit(burrowland_contract.0.id()));
+    check!(burrowland_contract.deposit_to_reserve(&wrap_token_contract, &root, wrap_reserve_amount));
+    check!(burrowland_contract.deposit_to_reserve(&nusdt_token_contract, &root, nusdt_reserve_amount));
+
+    let alice = create_account(&root, "alice", None).await;
+    check!(ref_exchange_contract.storage_deposit(&alice));
+    check!(burrowland_contract.storage_deposit(&alice));
+
+    assert!(nusdt_token_contract.ft_mint(&root, &alice, d(10000, 6)).await?.is_success());
+    assert!(wrap_token_contract.ft_mint(&root, &alice, d(100000, 24)).await?.is_success());
+
+    check!(ref_exchange_contract.deposit(&nusdt_token_contract, &alice, d(10000, 6)));
+    check!(ref_exchange_contract.deposit(&wrap_token_contract, &alice, d(10000, 24)));
+
+    check!(ref_exchange_contract.add_simple_swap_pool(&root, vec![nusdt_token_contract.0.id(), wrap_token_contract.0.id()], 5));
+    check!(ref_exchange_contract.add_simple_liquidity(&alice, 0, vec![U128(d(10000, 6)), U128(d(1000, 24))], Some(vec![U128(0), U128(0)])));
+
+    check!(view ref_exchange_contract.get_pool(0));
+
+    let supply_amount = d(1000, 18);
+    let extra_decimals_mult = d(1, 12);
+    check!(nusdt_token_contract.ft_mint(&root, &alice, supply_amount * 10));
+    check!(wrap_token_contract.ft_storage_deposit(alice.id()));
+
+    check!(view burrowland_contract.get_margin_account(&alice));
+
+    check!(burrowland_contract.deposit_to_margin(&nusdt_token_contract, &alice, supply_amount / extra_decimals_mult));
+    
+    check!(burrowland_contract.register_margin_dex(&root, ref_exchange_contract.0.id(), 1));
+    check!(burrowland_contract.register_margin_token(&root, nusdt_token_contract.0.id(), 0));
+    check!(burrowland_contract.register_margin_token(&root, wrap_token_contract.0.id(), 1));
+
+    let current_timestamp = worker.view_block().await?.timestamp();
+    check!(logs burrowland_contract.margin_trading_open_position_by_oracle_call(
+        &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
+        nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(20, 24).into(), nusdt_token_contract.0.id(), d(180, 18).into(),
+        SwapIndication {
+            splits: vec![],
+            dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
+            swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
+                referral_id: None,
+                client_echo: None,
+                skip_degen_price_sync: None,
+                actions: vec![
+                    RefV1Action::Swap(RefV1SwapAction{
+                        pool_id: 0,
+                        token_in: near_sdk::AccountId::new_unchecked(wrap_token_contract.0.id().to_string()),
+                        amount_in: Some(U128(d(20, 24))),
+                        token_out: near_sdk::AccountId::new_unchecked(nusdt_token_contract.0.id().to_string()),
+                        min_amount_out: U128(d(180, 6)),
+                    })
+                ]
+            }).unwrap()
+        }
+    ));
+
+    check!(view burrowland_contract.get_margin_account(&alice));
+
+    let alice_margin_account = burrowland_contract.get_margin_account(&alice).await?.unwrap();
+    let pos_id = alice_margin_account.margin_positions.keys().collect::<Vec<&String>>()[0].clone();
+    let position = alice_margin_account.margin_positions.get(&pos_id).unwrap();
+
+    let bob = create_account(&root, "bob", None).await;
+    check!(burrowland_contract.storage_deposit(&bob));
+
+    check!(logs burrowland_contract.transfer_margin_position(&alice, &pos_id, &bob));
+    check!(burrowland_contract.accept_margin_position(&alice, &pos_id), "Not allowed");
+    check!(logs burrowland_contract.accept_margin_position(&bob, &pos_id));
+
+    let alice_margin_account = burrowland_contract.get_margin_account(&alice).await?.unwrap();
+    assert!(alice_margin_account.margin_positions.is_empty());
+    let bob_margin_account = burrowland_contract.get_margin_account(&bob).await?.unwrap();
+    let bob_position = bob_margin_account.margin_positions.get(&pos_id).unwrap();
+    assert_eq!(bob_position.token_p_amount, position.token_p_amount);
+    assert_eq!(bob_position.token_d_info.shares, position.token_d_info.shares);
+    assert_eq!(bob_position.token_c_info.shares, position.token_c_info.shares);
+
+    check!(burrowland_contract.accept_margin_position(&bob, &pos_id), "Position transfer not exist");
+
+    // Pending transfers are paid by the proposer and can be cancelled by either side.
+    let bob_storage = burrowland_contract.get_storage_balance_of_detail(&bob).await?.unwrap();
+    check!(burrowland_contract.transfer_margin_position(&bob, &pos_id, &alice));
+    assert!(burrowland_contract.get_storage_balance_of_detail(&bob).await?.unwrap().used_amount.0 > bob_storage.used_amount.0);
+    assert!(burrowland_contract.get_margin_position_transfer(&pos_id).await?.is_some());
+    check!(logs burrowland_contract.cancel_margin_position_transfer(&alice, &pos_id));
+    assert!(burrowland_contract.get_margin_position_transfer(&pos_id).await?.is_none());
+    assert_eq!(burrowland_contract.get_storage_balance_of_detail(&bob).await?.unwrap().used_amount.0, bob_storage.used_amount.0);
+
+    // Blocked accounts can't receive positions.
+    check!(burrowland_contract.transfer_margin_position(&bob, &pos_id, &alice));
+    check!(burrowland_contract.extend_blocklist(&root, vec![alice.id()]));
+    check!(burrowland_contract.accept_margin_position(&alice, &pos_id), "is blocked from OpenMarginPosition");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_margin_trading_farm() -> Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let root = worker.root_account()?;
+
+    let nusdt_token_contract = deploy_mock_ft(&root, "nusdt", 18).await?;
+    let wrap_token_contract = deploy_mock_ft(&root, "wrap", 18).await?;
+    let wrap_reserve_amount = d(10000, 24);
+    let nusdt_reserve_amount = d(10000, 6);
+    check!(wrap_token_contract.ft_mint(&root, &root, wrap_reserve_amount));
+    check!(nusdt_token_contract.ft_mint(&root, &root, nusdt_reserve_amount));
+
+    let ref_exchange_contract = deploy_ref_exchange(&root).await?;
+    {
+        check!(nusdt_token_contract.ft_storage_deposit(ref_exchange_contract.0.id()));
+        check!(wrap_token_contract.ft_storage_deposit(ref_exchange_contract.0.id()));
+        check!(ref_exchange_contract.storage_deposit(&root));
+        check!(ref_exchange_contract.extend_whitelisted_tokens(&root, vec![nusdt_token_contract.0.id(), wrap_token_contract.0.id()]));
+    }
+
+    let oracle_contract = deploy_oralce(&root).await?;
+    let burrowland_contract = deploy_burrowland_with_price_oracle(&root).await?;
+    check!(burrowland_contract.add_asset_handler(&root, &wrap_token_contract));
+    check!(burrowland_contract.add_asset_handler(&root, &nusdt_token_contract));
+    check!(wrap_token_contract.ft_storage_deposit(burrowland_contract.0.id()));
+    check!(nusdt_token_contract.ft_storage_deposit(burrowland_contract.0.id()));
+    check!(burrowland_contract.deposit_to_reserve(&wrap_token_contract, &root, wrap_reserve_amount));
+    check!(burrowland_contract.deposit_to_reserve(&nusdt_token_contract, &root, nusdt_reserve_amount));
+
+    let alice = create_account(&root, "alice", None).await;
+    check!(ref_exchange_contract.storage_deposit(&alice));
+    check!(burrowland_contract.storage_deposit(&alice));
+
+    assert!(nusdt_token_contract.ft_mint(&root, &alice, d(10000, 6)).await?.is_success());
+    assert!(wrap_token_contract.ft_mint(&root, &alice, d(100000, 24)).await?.is_success());
+
+    check!(ref_exchange_contract.deposit(&nusdt_token_contract, &alice, d(10000, 6)));
+    check!(ref_exchange_contract.deposit(&wrap_token_contract, &alice, d(10000, 24)));
+
+    check!(ref_exchange_contract.add_simple_swap_pool(&root, vec![nusdt_token_contract.0.id(), wrap_token_contract.0.id()], 5));
+    check!(ref_exchange_contract.add_simple_liquidity(&alice, 0, vec![U128(d(10000, 6)), U128(d(1000, 24))], Some(vec![U128(0), U128(0)])));
+
+    check!(view ref_exchange_contract.get_pool(0));
+
+    let supply_amount = d(1000, 18);
+    let extra_decimals_mult = d(1, 12);
+    check!(nusdt_token_contract.ft_mint(&root, &alice, supply_amount * 10));
+    check!(wrap_token_contract.ft_storage_deposit(alice.id()));
+
+    check!(view burrowland_contract.get_margin_account(&alice));
+
+    check!(burrowland_contract.deposit_to_margin(&nusdt_token_contract, &alice, supply_amount / extra_decimals_mult));
+    
+    check!(burrowland_contract.register_margin_dex(&root, ref_exchange_contract.0.id(), 1));
+    check!(burrowland_contract.register_margin_token(&root, nusdt_token_contract.0.id(), 0));
+    check!(burrowland_contract.register_margin_token(&root, wrap_token_contract.0.id(), 1));
+
+    let current_timestamp = worker.view_block().await?.timestamp();
+    check!(logs burrowland_contract.margin_trading_open_position_by_oracle_call(
+        &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
+        nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(20, 24).into(), nusdt_token_contract.0.id(), d(180, 18).into(),
+        SwapIndication {
+            splits: vec![],
+            dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
+            swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
+                referral_id: None,
+                client_echo: None,
+                skip_degen_price_sync: None,
+                actions: vec![
+                    RefV1Action::Swap(RefV1SwapAction{
+                        pool_id: 0,
+                        token_in: near_sdk::AccountId::new_unchecked(wrap_token_contract.0.id().to_string()),
+                        amount_in: Some(U128(d(20, 24))),
+                        token_out: near_sdk::AccountId::new_unchecked(nusdt_token_contract.0.id().to_string()),
+                        min_amount_out: U128(d(180, 6)),
+                    })
+                ]
+            }).unwrap()
+        }
+    ));
+
+    check!(view burrowland_contract.get_margin_account(&alice));
+
+    let farm_id = FarmId::MarginPosition(near_sdk::AccountId::new_unchecked(nusdt_token_contract.0.id().to_string()));
+    check!(burrowland_contract.add_asset_farm_reward(&root, farm_id.clone(), wrap_token_contract.0.id(), d(100, 24).into(), d(100, 18).into(), d(1000, 24).into()));
+    check!(logs burrowland_contract.margin_account_farm_claim_all(&alice, None));
+
+    let alice_margin_account = burrowland_contract.get_margin_account(&alice).await?.unwrap();
+    assert_eq!(alice_margin_account.farms.len(), 1);
+    assert_eq!(alice_margin_account.farms[0].farm_id, farm_id);
+
+    worker.fast_forward(100).await?;
+    check!(logs burrowland_contract.margin_account_farm_claim_all(&alice, None));
+
+    let alice_margin_account = burrowland_contract.get_margin_account(&alice).await?.unwrap();
+    assert!(alice_margin_account.unclaimed_farm_rewards.is_empty());
+    let alice_account = burrowland_contract.get_account(&alice).await?.unwrap();
+    let wrap_supplied = alice_account.supplied.iter()
+        .find(|v| v.token_id.to_string() == wrap_token_contract.0.id().to_string())
+        .map(|v| v.balance)
+        .unwrap_or(0);
+    assert!(wrap_supplied > 0);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_margin_trading_with_pyth() -> Result<()> {
     let worker = near_workspaces::sandbox().await?;
@@ -242,6 +700,7 @@ async fn test_margin_trading_with_pyth() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_open_position_by_pyth(&alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(20, 24).into(), nusdt_token_contract.0.id(), d(180, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -279,6 +738,7 @@ async fn test_margin_trading_with_pyth() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_decrease_mtposition_by_pyth(&alice,
         &pos_id, d(100, 18), d(10, 24), 
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -308,6 +768,7 @@ async fn test_margin_trading_with_pyth() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_close_mtposition_by_pyth(&alice,
         &pos_id, position_amount, min_out_amount, 
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -397,6 +858,7 @@ async fn test_margin_trading_liquidate_direct_short() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(100, 24).into(), nusdt_token_contract.0.id(), d(900, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -522,6 +984,7 @@ async fn test_margin_trading_liquidate_direct_long() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(90, 24).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -644,6 +1107,7 @@ async fn test_margin_trading_liquidate() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(100, 24).into(), nusdt_token_contract.0.id(), d(900, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -679,6 +1143,7 @@ async fn test_margin_trading_liquidate() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(190000)), &root,
         alice.id(), &pos_id, position_amount, min_out_amount,
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -784,6 +1249,7 @@ async fn test_margin_trading_liquidate_with_pyth() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_open_position_by_pyth(&alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(100, 24).into(), nusdt_token_contract.0.id(), d(900, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -825,6 +1291,7 @@ async fn test_margin_trading_liquidate_with_pyth() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_liquidate_mtposition_by_pyth(&root,
         alice.id(), &pos_id, position_amount, min_out_amount,
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -912,6 +1379,7 @@ async fn test_margin_trading_forceclose() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(100, 24).into(), nusdt_token_contract.0.id(), d(900, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -945,6 +1413,7 @@ async fn test_margin_trading_forceclose() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(200000)), &root,
         alice.id(), &pos_id, position_amount, min_out_amount / 2,
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -1052,6 +1521,7 @@ async fn test_margin_trading_forceclose_with_pyth() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_open_position_by_pyth(&alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(100, 24).into(), nusdt_token_contract.0.id(), d(900, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -1091,6 +1561,7 @@ async fn test_margin_trading_forceclose_with_pyth() -> Result<()> {
     check!(print burrowland_contract.margin_trading_force_close_mtposition_by_pyth(&root,
         alice.id(), &pos_id, position_amount, min_out_amount / 2,
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -1198,6 +1669,7 @@ async fn test_margin_trading_dcl() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_open_position_by_pyth(&alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(20, 24).into(), nusdt_token_contract.0.id(), d(180, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(dcl_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV2TokenReceiverMessage::Swap { 
                 pool_ids: vec![pool_id.clone()],
@@ -1228,6 +1700,7 @@ async fn test_margin_trading_dcl() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_decrease_mtposition_by_pyth(&alice,
         &pos_id, d(100, 18), d(10, 24), 
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(dcl_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV2TokenReceiverMessage::Swap { 
                 pool_ids: vec![pool_id.clone()],
@@ -1250,6 +1723,7 @@ async fn test_margin_trading_dcl() -> Result<()> {
     check!(print burrowland_contract.margin_trading_close_mtposition_by_pyth(&alice,
         &pos_id, position_amount, min_out_amount, 
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(dcl_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV2TokenReceiverMessage::Swap { 
                 pool_ids: vec![pool_id.clone()],
@@ -1348,6 +1822,7 @@ async fn test_margin_trading_liquidate_dcl() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_open_position_by_pyth(&alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(100, 24).into(), nusdt_token_contract.0.id(), d(900, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(dcl_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV2TokenReceiverMessage::Swap { 
                 pool_ids: vec![pool_id.clone()],
@@ -1382,6 +1857,7 @@ async fn test_margin_trading_liquidate_dcl() -> Result<()> {
     check!(print burrowland_contract.margin_trading_liquidate_mtposition_by_pyth(&root,
         alice.id(), &pos_id, position_amount, min_out_amount,
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(dcl_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV2TokenReceiverMessage::Swap { 
                 pool_ids: vec![pool_id.clone()],
@@ -1481,6 +1957,7 @@ async fn test_margin_trading_forceclose_dcl() -> Result<()> {
     check!(logs burrowland_contract.margin_trading_open_position_by_pyth(&alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(100, 24).into(), nusdt_token_contract.0.id(), d(900, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(dcl_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV2TokenReceiverMessage::Swap { 
                 pool_ids: vec![pool_id.clone()],
@@ -1516,6 +1993,7 @@ async fn test_margin_trading_forceclose_dcl() -> Result<()> {
     check!(print burrowland_contract.margin_trading_force_close_mtposition_by_pyth(&root,
         alice.id(), &pos_id, position_amount, min_out_amount / 2,
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(dcl_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV2TokenReceiverMessage::Swap { 
                 pool_ids: vec![pool_id.clone()],
@@ -1594,6 +2072,7 @@ async fn test_margin_trading_forceclose_reserve_not_enough() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(100000)), &alice,
         nusdt_token_contract.0.id(), d(1000, 18).into(), wrap_token_contract.0.id(), d(100, 24).into(), nusdt_token_contract.0.id(), d(900, 18).into(),
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
@@ -1627,6 +2106,7 @@ async fn test_margin_trading_forceclose_reserve_not_enough() -> Result<()> {
         &oracle_contract, price_data(current_timestamp, Some(200000)), &root,
         alice.id(), &pos_id, position_amount, min_out_amount / 2,
         SwapIndication {
+            splits: vec![],
             dex_id: near_sdk::AccountId::new_unchecked(ref_exchange_contract.0.id().to_string()),
             swap_action_text: serde_json::to_string(&RefV1TokenReceiverMessage::Execute{
                 referral_id: None,
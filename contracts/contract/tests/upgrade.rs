@@ -41,6 +41,8 @@ async fn test_upgrade() -> Result<()> {
         .deposit(NearToken::from_yoctonear(1))
         .transact());
     check!(view previous_burrowland_contract.get_config());
+    let alice = create_account(&root, "alice", None).await;
+    check!(previous_burrowland_contract.storage_deposit(&alice));
 
     check!(print root
         .call(previous_burrowland_contract.0.id(), "upgrade")
@@ -51,5 +53,80 @@ async fn test_upgrade() -> Result<()> {
     assert_eq!(version, LATEST_VERSION);
     check!(view previous_burrowland_contract.get_config());
     check!(view previous_burrowland_contract.get_asset(&token_id));
+
+    let report = previous_burrowland_contract.check_state_migration(None, None).await?;
+    assert!(report.failures.is_empty(), "{:?}", report.failures);
+    assert!(report.incompatible_schemas.is_empty());
+    assert_eq!(report.num_accounts, 1);
+    assert_eq!(report.num_assets, 1);
+    assert_eq!(report.num_legacy_accounts, 1);
+    check!(root.call(previous_burrowland_contract.0.id(), "migrate_accounts")
+        .args_json(json!({}))
+        .max_gas()
+        .transact());
+    let report = previous_burrowland_contract.check_state_migration(None, None).await?;
+    assert!(report.failures.is_empty(), "{:?}", report.failures);
+    assert_eq!(report.num_legacy_accounts, 0);
+    assert_eq!(report.num_legacy_assets, 0);
+    let fingerprints = previous_burrowland_contract.get_state_schema_fingerprints().await?;
+    assert_eq!(fingerprints.len(), STATE_SCHEMAS.len());
+    for schema in STATE_SCHEMAS {
+        assert_eq!(fingerprints[schema.name].version, schema.version);
+    }
     Ok(())
 }
+
+/// Returns the body of the item starting with the header in the contract sources.
+fn find_item_body(header: &str) -> String {
+    let src_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    for entry in std::fs::read_dir(src_dir).unwrap() {
+        let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        if let Some(start) = source.find(header) {
+            let body = &source[start + header.len()..];
+            return body[..body.find("\n}").unwrap()].to_string();
+        }
+    }
+    panic!("{} not found", header);
+}
+
+/// Returns the serialized fields of the struct in the contract sources.
+fn find_struct_fields(name: &str) -> String {
+    let mut fields = vec![];
+    let mut borsh_skip = false;
+    for line in find_item_body(&format!("pub struct {} {{", name)).lines().map(str::trim) {
+        if line.starts_with("#[borsh_skip]") {
+            borsh_skip = true;
+        } else if let Some(field) = line.strip_prefix("pub ") {
+            if !borsh_skip {
+                fields.push(field.trim_end_matches(',').to_string());
+            }
+            borsh_skip = false;
+        }
+    }
+    fields.join(", ")
+}
+
+#[test]
+fn test_nested_state_schemas_match_sources() {
+    for schema in NESTED_STATE_SCHEMAS {
+        let expected_fields = schema.fields.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert_eq!(find_struct_fields(schema.name), expected_fields, "Fields of {} changed", schema.name);
+    }
+}
+
+#[test]
+fn test_state_schemas_match_sources() {
+    for schema in STATE_SCHEMAS {
+        let expected_fields = schema.fields.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert_eq!(find_struct_fields(schema.name), expected_fields, "Fields of {} changed", schema.name);
+
+        let versions = find_item_body(&format!("pub enum V{} {{", schema.name));
+        let current_version = versions
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("//"))
+            .position(|line| line.starts_with("Current("))
+            .unwrap();
+        assert_eq!(current_version, schema.version as usize, "Version of {} changed", schema.name);
+    }
+}
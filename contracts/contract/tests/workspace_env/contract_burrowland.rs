@@ -1,6 +1,6 @@
 use crate::*;
 
-use contract::{Config, AssetConfig, AssetDetailedView, PriceReceiverMsg, AccountDetailedView, AssetAmount, Action, TokenReceiverMsg, MarginStopServiceFee, StorageBalanceOfDetail};
+use contract::{Config, AssetConfig, AssetDetailedView, PriceReceiverMsg, AccountDetailedView, AssetAmount, Action, TokenReceiverMsg, MarginStopServiceFee, StorageBalanceOfDetail, StateMigrationReport, StateSchemaFingerprint};
 
 pub struct Burrowland(pub Contract);
 
@@ -559,6 +559,21 @@ impl Burrowland {
             .await
     }
 
+    pub async fn margin_account_farm_claim_all(
+        &self,
+        caller: &Account,
+        account_id: Option<AccountId>
+    ) -> Result<ExecutionFinalResult> {
+        caller
+            .call(self.0.id(), "margin_account_farm_claim_all")
+            .args_json(json!({
+                "account_id": account_id,
+            }))
+            .max_gas()
+            .transact()
+            .await
+    }
+
     pub async fn extend_guardians(
         &self,
         caller: &Account,
@@ -878,6 +893,32 @@ impl Burrowland {
             .json::<String>()
     }
 
+    pub async fn check_state_migration(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Result<StateMigrationReport> {
+        self.0
+            .call("check_state_migration")
+            .args_json(json!({
+                "from_index": from_index,
+                "limit": limit,
+            }))
+            .view()
+            .await?
+            .json::<StateMigrationReport>()
+    }
+
+    pub async fn get_state_schema_fingerprints(
+        &self,
+    ) -> Result<HashMap<String, StateSchemaFingerprint>> {
+        self.0
+            .call("get_state_schema_fingerprints")
+            .view()
+            .await?
+            .json::<HashMap<String, StateSchemaFingerprint>>()
+    }
+
     pub async fn get_last_lp_token_infos(
         &self,
     ) -> Result<HashMap<String, UnitShareTokens>> {
@@ -1332,6 +1373,28 @@ impl Burrowland {
         ]).await
     }
 
+    pub async fn margin_trading_increase_mtposition_by_oracle_call(
+        &self,
+        oracle: &Oralce,
+        price_data: PriceData,
+        caller: &Account,
+        pos_id: &String,
+        token_d_amount: u128,
+        min_token_p_amount: u128,
+        swap_indication: SwapIndication,
+    ) -> Result<ExecutionFinalResult> {
+        oracle.oracle_call(caller, self.0.id(), price_data, PriceReceiverMsg::MarginExecute {
+            actions: vec![
+                MarginAction::IncreaseMTPosition { 
+                    pos_id: pos_id.clone(),
+                    token_d_amount: token_d_amount.into(),
+                    min_token_p_amount: min_token_p_amount.into(),
+                    swap_indication: swap_indication,
+                }
+            ],
+        }).await
+    }
+
     pub async fn margin_trading_decrease_mtposition_by_oracle_call(
         &self,
         oracle: &Oralce,
@@ -1408,6 +1471,84 @@ impl Burrowland {
         ]).await
     }
 
+    pub async fn margin_trading_repay_and_claim_mtposition(
+        &self,
+        caller: &Account,
+        pos_id: &String,
+    ) -> Result<ExecutionFinalResult> {
+        self.margin_execute_with_pyth(caller, vec![
+            MarginAction::RepayAndClaimMTPosition { 
+                pos_id: pos_id.clone(),
+            }
+        ]).await
+    }
+
+    pub async fn transfer_margin_position(
+        &self,
+        caller: &Account,
+        pos_id: &String,
+        receiver: &Account,
+    ) -> Result<ExecutionFinalResult> {
+        caller
+            .call(self.0.id(), "transfer_margin_position")
+            .args_json(json!({
+                "pos_id": pos_id,
+                "receiver_id": receiver.id(),
+            }))
+            .max_gas()
+            .deposit(NearToken::from_yoctonear(1))
+            .transact()
+            .await
+    }
+
+    pub async fn accept_margin_position(
+        &self,
+        caller: &Account,
+        pos_id: &String,
+    ) -> Result<ExecutionFinalResult> {
+        caller
+            .call(self.0.id(), "accept_margin_position")
+            .args_json(json!({
+                "pos_id": pos_id,
+            }))
+            .max_gas()
+            .deposit(NearToken::from_yoctonear(1))
+            .transact()
+            .await
+    }
+
+    pub async fn cancel_margin_position_transfer(
+        &self,
+        caller: &Account,
+        pos_id: &String,
+    ) -> Result<ExecutionFinalResult> {
+        caller
+            .call(self.0.id(), "cancel_margin_position_transfer")
+            .args_json(json!({
+                "pos_id": pos_id,
+            }))
+            .max_gas()
+            .deposit(NearToken::from_yoctonear(1))
+            .transact()
+            .await
+    }
+
+    pub async fn extend_blocklist(
+        &self,
+        caller: &Account,
+        account_ids: Vec<&AccountId>
+    ) -> Result<ExecutionFinalResult> {
+        caller
+            .call(self.0.id(), "extend_blocklist")
+            .args_json(json!({
+                "account_ids": account_ids,
+            }))
+            .deposit(NearToken::from_yoctonear(1))
+            .max_gas()
+            .transact()
+            .await
+    }
+
     pub async fn margin_trading_close_mtposition_by_pyth(
         &self,
         caller: &Account,
@@ -1711,4 +1852,18 @@ impl Burrowland {
             .json::<Option<MarginAccountDetailedView>>()
     }
 
+    pub async fn get_margin_position_transfer(
+        &self,
+        pos_id: &String,
+    ) -> Result<Option<MarginPositionTransfer>> {
+        self.0
+            .call("get_margin_position_transfer")
+            .args_json(json!({
+                "pos_id": pos_id
+            }))
+            .view()
+            .await?
+            .json::<Option<MarginPositionTransfer>>()
+    }
+
 }
\ No newline at end of file